@@ -16,7 +16,9 @@ use marzano_language::target_language::expand_paths;
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 #[allow(unused_imports)]
 use marzano_core::built_in_functions::BuiltIns;
-use marzano_core::pattern_compiler::{src_to_problem_libs, CompilationResult};
+use marzano_core::pattern_compiler::{
+    src_to_problem_libs_with_options, CompilationOptions, CompilationResult,
+};
 use marzano_core::{
     api::{AnalysisLog, DoneFile, MatchResult},
     problem::Problem,
@@ -46,6 +48,24 @@ impl<'b> RichPattern<'b> {
         language: Option<PatternLanguage>,
         targets: Option<Vec<FileRange>>,
         injected_limit: Option<usize>,
+    ) -> Result<CompilationResult> {
+        self.compile_with_options(
+            pattern_libs,
+            language,
+            targets,
+            injected_limit,
+            CompilationOptions::default(),
+        )
+    }
+
+    #[instrument(skip(self, pattern_libs, targets))]
+    pub fn compile_with_options(
+        &self,
+        pattern_libs: &BTreeMap<String, String>,
+        language: Option<PatternLanguage>,
+        targets: Option<Vec<FileRange>>,
+        injected_limit: Option<usize>,
+        options: CompilationOptions,
     ) -> Result<CompilationResult> {
         let lang = language.unwrap_or_default();
         #[cfg(not(feature = "ai_builtins"))]
@@ -53,7 +73,7 @@ impl<'b> RichPattern<'b> {
         #[cfg(feature = "ai_builtins")]
         let injected_builtins = Some(ai_builtins::ai_builtins::get_ai_built_in_functions());
 
-        src_to_problem_libs(
+        src_to_problem_libs_with_options(
             self.body.to_owned(),
             pattern_libs,
             lang.try_into().unwrap(),
@@ -61,6 +81,7 @@ impl<'b> RichPattern<'b> {
             targets,
             injected_builtins,
             injected_limit,
+            options,
         )
     }
 }
@@ -517,6 +517,7 @@ pub(crate) async fn run_apply_pattern(
     let CompilationResult {
         problem: compiled,
         compilation_warnings,
+        ..
     } = match pattern.compile(final_input.pattern_libs(), lang, filter_range, arg.limit) {
         Ok(c) => c,
         Err(e) => {
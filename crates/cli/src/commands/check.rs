@@ -10,6 +10,7 @@ use marzano_core::{
         RewriteSource,
     },
     fs::apply_rewrite,
+    pattern_compiler::{CompilationOptions, SnippetFallbackLint},
     problem::Problem,
 };
 use marzano_gritmodule::{config::ResolvedGritDefinition, utils::extract_path};
@@ -69,6 +70,9 @@ pub struct CheckArg {
     /// Output annotations for a GitHub actions workflow
     #[clap(long = "github-actions")]
     pub github_actions: bool,
+    /// Fail if any snippet can only be matched as text, because it does not parse as code
+    #[clap(long = "strict-snippets")]
+    pub strict_snippets: bool,
     #[clap(flatten)]
     pub shared_filters: SharedFilterArgs,
 }
@@ -127,6 +131,15 @@ pub(crate) async fn run_check(
     // Construct a resolver
     let resolver = GritModuleResolver::new();
 
+    let compilation_options = CompilationOptions {
+        snippet_fallback: if arg.strict_snippets {
+            SnippetFallbackLint::Deny
+        } else {
+            SnippetFallbackLint::Allow
+        },
+        ..Default::default()
+    };
+
     let mut body_to_pattern: HashMap<String, &ResolvedGritDefinition> = HashMap::new();
     let compile_tasks: Result<HashMap<String, Problem>, _> = enforced
         .iter()
@@ -139,7 +152,13 @@ pub(crate) async fn run_check(
                 .make_pattern(&body, Some(p.local_name.to_string()))
                 .unwrap();
             let lang = PatternLanguage::get_language(&p.body);
-            match rich_pattern.compile(&grit_files, lang, filter_range.clone(), None) {
+            match rich_pattern.compile_with_options(
+                &grit_files,
+                lang,
+                filter_range.clone(),
+                None,
+                compilation_options.clone(),
+            ) {
                 Ok(c) => Ok((p.local_name.clone(), c.problem)),
                 Err(e) => {
                     bail!("Unable to compile pattern {}:\n{}", p.local_name, e);
@@ -56,6 +56,20 @@ pub fn has_limit(
     })
 }
 
+/// Whether the pattern rewrites or inserts code anywhere.
+pub fn has_rewrite(
+    root: &NodeWithSource,
+    libs: &BTreeMap<String, String>,
+    grit_parser: &mut MarzanoGritParser,
+) -> Result<bool> {
+    walk_call_tree(root, libs, grit_parser, &|n| {
+        Ok(matches!(
+            n.node.kind().as_ref(),
+            "rewrite" | "patternAccumulate"
+        ))
+    })
+}
+
 #[allow(dead_code)]
 pub fn is_async(
     root: &NodeWithSource,
@@ -101,6 +101,30 @@ impl MatchResult {
         }
     }
 
+    /// Converts the ranges of a match, or of the original side of a rewrite,
+    /// into UTF-16 code units of `source`, and those of a rewritten file into
+    /// UTF-16 code units of its new content.
+    pub fn to_utf16_ranges(&mut self, source: &str) {
+        match self {
+            MatchResult::Match(m) => m.to_utf16_ranges(source),
+            MatchResult::Rewrite(r) => {
+                r.original.to_utf16_ranges(source);
+                if let Some(ranges) = r.rewritten.byte_ranges.as_mut() {
+                    for range in ranges.iter_mut() {
+                        *range = range.to_utf16_range(&r.rewritten.content);
+                    }
+                }
+            }
+            MatchResult::PatternInfo(_)
+            | MatchResult::AllDone(_)
+            | MatchResult::InputFile(_)
+            | MatchResult::CreateFile(_)
+            | MatchResult::RemoveFile(_)
+            | MatchResult::DoneFile(_)
+            | MatchResult::AnalysisLog(_) => {}
+        }
+    }
+
     pub(crate) fn file_to_match_result<'a>(
         file: &Vector<&FileOwner<Tree>>,
         language: &impl MarzanoLanguage<'a>,
@@ -297,6 +321,29 @@ pub struct Match {
 }
 
 impl Match {
+    /// Returns the byte ranges bound to the metavariable `name`, such as `$x`,
+    /// across all of its bindings in this match.
+    pub fn variable_byte_ranges(&self, name: &str) -> Vec<ByteRange> {
+        self.variables
+            .iter()
+            .filter(|variable| variable.name == name)
+            .flat_map(VariableMatch::byte_ranges)
+            .collect()
+    }
+
+    fn to_utf16_ranges(&mut self, source: &str) {
+        for range in self.ranges.iter_mut() {
+            *range = range.to_utf16_range(source);
+        }
+        for range in self
+            .variables
+            .iter_mut()
+            .flat_map(|variable| variable.ranges.iter_mut())
+        {
+            *range = range.to_utf16_range(source);
+        }
+    }
+
     fn file_to_match<'a>(
         match_ranges: &InputRanges,
         name: &str,
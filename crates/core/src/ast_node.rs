@@ -12,17 +12,110 @@ use grit_pattern_matcher::{
 };
 use grit_util::{AnalysisLogs, AstNode, Language};
 use marzano_language::language::{FieldId, LeafEquivalenceClass, MarzanoLanguage, SortId};
+use marzano_language::target_language::TargetLanguage;
 use marzano_util::node_with_source::NodeWithSource;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ASTNode {
     pub(crate) sort: SortId,
     pub(crate) args: Vec<(FieldId, bool, Pattern<MarzanoQueryContext>)>,
+    /// Set instead of `args` for a chain of an associative operator, see
+    /// `MarzanoLanguage::associative_operator`.
+    #[serde(default)]
+    pub(crate) operator_chain: Option<OperatorChain>,
+    /// Matched against the named child held outside of the node's fields,
+    /// see `MarzanoLanguage::holds_unfielded_child`.
+    #[serde(default)]
+    pub(crate) unfielded_child: Option<Pattern<MarzanoQueryContext>>,
+    /// Whether grouping nodes around the target are seen through, see
+    /// `ungroup_list_element`.
+    #[serde(default)]
+    ungroups: bool,
 }
 
 impl ASTNode {
     pub fn new(sort: SortId, args: Vec<(FieldId, bool, Pattern<MarzanoQueryContext>)>) -> Self {
-        Self { sort, args }
+        Self {
+            sort,
+            args,
+            operator_chain: None,
+            unfielded_child: None,
+            ungroups: false,
+        }
+    }
+
+    /// Matches a chain of `operator`, such as `a && b && c`, by matching
+    /// `operands` against the list of its operands.
+    pub(crate) fn operator_chain(
+        sort: SortId,
+        operator: String,
+        operands: Pattern<MarzanoQueryContext>,
+    ) -> Self {
+        Self {
+            sort,
+            args: Vec::new(),
+            operator_chain: Some(OperatorChain { operator, operands }),
+            unfielded_child: None,
+            ungroups: false,
+        }
+    }
+}
+
+/// The operands of a chain of an associative operator, matched as a list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperatorChain {
+    pub(crate) operator: String,
+    pub(crate) operands: Pattern<MarzanoQueryContext>,
+}
+
+impl OperatorChain {
+    fn execute<'a>(
+        &'a self,
+        node: NodeWithSource<'a>,
+        state: &mut State<'a, MarzanoQueryContext>,
+        context: &'a MarzanoContext,
+        logs: &mut AnalysisLogs,
+    ) -> Result<bool> {
+        let language = context.language;
+        if language.associative_operator(&node) != Some(self.operator.as_str()) {
+            return Ok(false);
+        }
+        // Part of a longer chain, which is matched as a whole instead.
+        if node.parent().is_some_and(|parent| {
+            language.associative_operator(&parent) == Some(self.operator.as_str())
+        }) {
+            return Ok(false);
+        }
+        let mut operands = Vec::new();
+        flatten_operator_chain(node, &self.operator, language, &mut operands);
+        let operands = MarzanoResolvedPattern::from_list_parts(
+            operands
+                .into_iter()
+                .map(MarzanoResolvedPattern::from_node_binding),
+        );
+        self.operands.execute(&operands, state, context, logs)
+    }
+}
+
+/// Collects the operands of a chain of `operator`, from left to right.
+/// Operands grouped in parentheses are kept whole.
+pub(crate) fn flatten_operator_chain<'a>(
+    node: NodeWithSource<'a>,
+    operator: &str,
+    language: &TargetLanguage,
+    operands: &mut Vec<NodeWithSource<'a>>,
+) {
+    for child in node.named_children() {
+        if language.is_comment_node(&child) {
+            continue;
+        }
+        if language.associative_operator(&child) == Some(operator) {
+            flatten_operator_chain(child, operator, language, operands);
+        } else {
+            operands.push(child);
+        }
     }
 }
 
@@ -33,6 +126,12 @@ impl AstNodePattern<MarzanoQueryContext> for ASTNode {
         self.args
             .iter()
             .map(|a| PatternOrPredicate::Pattern(&a.2))
+            .chain(
+                self.operator_chain
+                    .iter()
+                    .map(|chain| PatternOrPredicate::Pattern(&chain.operands)),
+            )
+            .chain(self.unfielded_child.iter().map(PatternOrPredicate::Pattern))
             .collect()
     }
 
@@ -70,10 +169,18 @@ impl Matcher<MarzanoQueryContext> for ASTNode {
             );
         }
 
+        let node = if self.ungroups {
+            ungroup(self.sort, node, context)
+        } else {
+            node
+        };
         if node.node.kind_id() != self.sort {
             return Ok(false);
         }
-        if self.args.is_empty() {
+        if let Some(chain) = &self.operator_chain {
+            return chain.execute(node, init_state, context, logs);
+        }
+        if self.args.is_empty() && self.unfielded_child.is_none() {
             return Ok(true);
         }
         if context.language.is_comment_sort(self.sort) {
@@ -100,6 +207,7 @@ impl Matcher<MarzanoQueryContext> for ASTNode {
                     logs,
                 )
             } else if let Some(child) = node.child_by_field_id(*field_id) {
+                let child = ungroup_for_pattern(pattern, child, context);
                 pattern.execute(
                     &MarzanoResolvedPattern::from_node_binding(child),
                     &mut cur_state,
@@ -120,16 +228,84 @@ impl Matcher<MarzanoQueryContext> for ASTNode {
                 return Ok(false);
             }
         }
+        if let Some(pattern) = &self.unfielded_child {
+            let Some(child) = context.language.unfielded_child(&node) else {
+                return Ok(false);
+            };
+            let child = ungroup_for_pattern(pattern, child, context);
+            if !pattern.execute(
+                &MarzanoResolvedPattern::from_node_binding(child),
+                &mut running_state,
+                context,
+                logs,
+            )? {
+                return Ok(false);
+            }
+        }
         *init_state = running_state;
         Ok(true)
     }
 }
 
-#[derive(Debug, Clone)]
+/// Sees through grouping nodes the pattern doesn't ask for itself, so the
+/// snippet `a + b` matches `(a) + b`, while the snippet `(a) + b` only
+/// matches the parenthesized form.
+fn ungroup_for_pattern<'a>(
+    pattern: &Pattern<MarzanoQueryContext>,
+    node: NodeWithSource<'a>,
+    context: &MarzanoContext,
+) -> NodeWithSource<'a> {
+    if let Pattern::AstNode(ast_node) = pattern {
+        ungroup(ast_node.sort, node, context)
+    } else if let Pattern::AstLeafNode(leaf_node) = pattern {
+        ungroup(leaf_node.sort, node, context)
+    } else {
+        node
+    }
+}
+
+/// Makes an element of a list pattern see through grouping nodes, as
+/// `ungroup_for_pattern` does for fields, so the elements of `foo(a, b)`
+/// match those of `foo((a), b)`. The list is matched item by item, which
+/// leaves the unwrapping to the element itself.
+pub(crate) fn ungroup_list_element(pattern: &mut Pattern<MarzanoQueryContext>) {
+    if let Pattern::AstNode(ast_node) = pattern {
+        ast_node.ungroups = true;
+    } else if let Pattern::AstLeafNode(leaf_node) = pattern {
+        leaf_node.ungroups = true;
+    }
+}
+
+fn ungroup<'a>(
+    sort: SortId,
+    mut node: NodeWithSource<'a>,
+    context: &MarzanoContext,
+) -> NodeWithSource<'a> {
+    while node.node.kind_id() != sort {
+        let Some(inner) = context.language.ungrouped_node(&node) else {
+            break;
+        };
+        node = inner;
+    }
+    node
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AstLeafNode {
     sort: SortId,
-    equivalence_class: Option<LeafEquivalenceClass>,
+    /// Normalizers are function pointers, so a deserialized node recomputes
+    /// its class from the language the first time it is matched.
+    #[serde(skip)]
+    equivalence_class: OnceLock<Option<LeafEquivalenceClass>>,
     text: String,
+    /// Whether the text matches regardless of case, see
+    /// `CompilationOptions::case_insensitive_identifiers`.
+    #[serde(default)]
+    case_insensitive: bool,
+    /// Whether grouping nodes around the target are seen through, see
+    /// `ungroup_list_element`.
+    #[serde(default)]
+    ungroups: bool,
 }
 
 impl AstLeafNode {
@@ -140,10 +316,51 @@ impl AstLeafNode {
         let text = text.trim();
         Ok(Self {
             sort,
-            equivalence_class,
+            equivalence_class: OnceLock::from(equivalence_class),
             text: text.to_owned(),
+            case_insensitive: false,
+            ungroups: false,
         })
     }
+
+    /// Makes the node match text which only differs from its own in case.
+    pub(crate) fn ignoring_case(self) -> Self {
+        Self {
+            case_insensitive: true,
+            ..self
+        }
+    }
+
+    pub(crate) fn sort(&self) -> SortId {
+        self.sort
+    }
+
+    pub(crate) fn matches_node(
+        &self,
+        node: &NodeWithSource,
+        language: &TargetLanguage,
+    ) -> Result<bool> {
+        let equivalence_class = self.equivalence_class.get_or_init(|| {
+            language
+                .get_equivalence_class(self.sort, &self.text)
+                .ok()
+                .flatten()
+        });
+        if let Some(e) = equivalence_class {
+            Ok(e.are_equivalent(node.node.kind_id(), node.text()?.trim()))
+        } else if self.sort != node.node.kind_id() {
+            Ok(false)
+        } else if self.case_insensitive {
+            let text = node.text()?;
+            Ok(text
+                .trim()
+                .chars()
+                .flat_map(char::to_lowercase)
+                .eq(self.text.chars().flat_map(char::to_lowercase)))
+        } else {
+            Ok(node.text()?.trim() == self.text)
+        }
+    }
 }
 
 impl AstLeafNodePattern<MarzanoQueryContext> for AstLeafNode {
@@ -163,18 +380,17 @@ impl Matcher<MarzanoQueryContext> for AstLeafNode {
         &'a self,
         binding: &MarzanoResolvedPattern<'a>,
         _state: &mut State<'a, MarzanoQueryContext>,
-        _context: &'a MarzanoContext<'a>,
+        context: &'a MarzanoContext<'a>,
         _logs: &mut AnalysisLogs,
     ) -> Result<bool> {
         let Some(node) = binding.get_last_binding().and_then(Binding::singleton) else {
             return Ok(false);
         };
-        if let Some(e) = &self.equivalence_class {
-            Ok(e.are_equivalent(node.node.kind_id(), node.text()?.trim()))
-        } else if self.sort != node.node.kind_id() {
-            Ok(false)
+        let node = if self.ungroups {
+            ungroup(self.sort, node, context)
         } else {
-            Ok(node.text()?.trim() == self.text)
-        }
+            node
+        };
+        self.matches_node(&node, context.language)
     }
 }
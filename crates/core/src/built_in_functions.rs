@@ -15,6 +15,8 @@ use grit_pattern_matcher::{
 use grit_util::{AnalysisLogs, Language};
 use im::Vector;
 use itertools::Itertools;
+use marzano_language::language::MarzanoLanguage;
+use marzano_util::node_with_source::NodeWithSource;
 use rand::prelude::SliceRandom;
 use rand::Rng;
 use std::collections::BTreeMap;
@@ -152,6 +154,13 @@ impl BuiltIns {
             BuiltInFunction::new("shuffle", vec!["list"], Box::new(shuffle_fn)),
             BuiltInFunction::new("random", vec!["floor", "ceiling"], Box::new(random_fn)),
             BuiltInFunction::new("split", vec!["string", "separator"], Box::new(split_fn)),
+            BuiltInFunction::new("insert_before", vec!["text"], Box::new(insert_before_fn)),
+            BuiltInFunction::new("insert_after", vec!["text"], Box::new(insert_after_fn)),
+            BuiltInFunction::new(
+                "template_literal",
+                vec!["parts"],
+                Box::new(template_literal_fn),
+            ),
         ]
         .into()
     }
@@ -276,6 +285,26 @@ fn trim_fn<'a>(
     Ok(ResolvedPattern::from_string(s))
 }
 
+// The insertion built-ins are compiled into rewrites which keep the match,
+// see `RewriteCompiler`, so they are only called when used anywhere else.
+fn insert_before_fn<'a>(
+    _args: &'a [Option<Pattern<MarzanoQueryContext>>],
+    _context: &'a MarzanoContext<'a>,
+    _state: &mut State<'a, MarzanoQueryContext>,
+    _logs: &mut AnalysisLogs,
+) -> Result<MarzanoResolvedPattern<'a>> {
+    bail!("insert_before can only be used as the right-hand side of a rewrite")
+}
+
+fn insert_after_fn<'a>(
+    _args: &'a [Option<Pattern<MarzanoQueryContext>>],
+    _context: &'a MarzanoContext<'a>,
+    _state: &mut State<'a, MarzanoQueryContext>,
+    _logs: &mut AnalysisLogs,
+) -> Result<MarzanoResolvedPattern<'a>> {
+    bail!("insert_after can only be used as the right-hand side of a rewrite")
+}
+
 fn split_fn<'a>(
     args: &'a [Option<Pattern<MarzanoQueryContext>>],
     context: &'a MarzanoContext<'a>,
@@ -301,6 +330,54 @@ fn split_fn<'a>(
     Ok(ResolvedPattern::from_list_parts(parts))
 }
 
+/// Renders the parts of a string concatenation as a template literal. The
+/// parts can be the concatenation itself or a list of its operands, such as
+/// `[$first, $rest]` for `$first + $...rest`, with nested lists flattened.
+fn template_literal_fn<'a>(
+    args: &'a [Option<Pattern<MarzanoQueryContext>>],
+    context: &'a MarzanoContext<'a>,
+    state: &mut State<'a, MarzanoQueryContext>,
+    logs: &mut AnalysisLogs,
+) -> Result<MarzanoResolvedPattern<'a>> {
+    let args = MarzanoResolvedPattern::from_patterns(args, state, context, logs)?;
+
+    let Some(parts) = &args[0] else {
+        bail!("template_literal requires parameter parts")
+    };
+    let mut nodes = Vec::new();
+    collect_part_nodes(parts, &mut nodes)?;
+    let literal = context.language().template_literal(&nodes).ok_or_else(|| {
+        anyhow!(
+            "{} does not have template literals",
+            context.language().language_name()
+        )
+    })?;
+    Ok(ResolvedPattern::from_string(literal))
+}
+
+fn collect_part_nodes<'a>(
+    parts: &MarzanoResolvedPattern<'a>,
+    nodes: &mut Vec<NodeWithSource<'a>>,
+) -> Result<()> {
+    if let Some(items) = parts.get_list_items() {
+        for item in items {
+            collect_part_nodes(item, nodes)?;
+        }
+    } else if let Some(items) = parts.get_list_binding_items() {
+        for item in items {
+            collect_part_nodes(&item, nodes)?;
+        }
+    } else if let Some(node) = parts
+        .get_last_binding()
+        .and_then(|binding| binding.as_node())
+    {
+        nodes.push(node);
+    } else {
+        bail!("template_literal takes code or lists of code as its parts")
+    }
+    Ok(())
+}
+
 fn random_fn<'a>(
     args: &'a [Option<Pattern<MarzanoQueryContext>>],
     context: &'a MarzanoContext<'a>,
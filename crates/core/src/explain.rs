@@ -0,0 +1,400 @@
+//! Structured explanations of why a snippet did or didn't match a node.
+//!
+//! The tracer mirrors how snippets are matched structurally, but runs without
+//! any query state: every metavariable starts out unbound, so predicates and
+//! patterns that depend on the state of a running query are reported as
+//! untraced rather than guessed at.
+
+use crate::{
+    ast_node::ASTNode,
+    marzano_code_snippet::MarzanoCodeSnippet,
+    problem::MarzanoQueryContext,
+    problem::Problem,
+    snippet_escapes::{process_snippet_escapes, EscapeMap},
+};
+use grit_pattern_matcher::pattern::{
+    AstLeafNodePattern, DynamicPattern, DynamicSnippetPart, List, Pattern, PatternName, Variable,
+};
+use grit_util::{AstNode, ByteRange};
+use marzano_language::language::{MarzanoLanguage, SortId};
+use marzano_util::node_with_source::NodeWithSource;
+use std::collections::HashMap;
+
+/// One step of a match explanation: a (sub-)pattern, the part of the target it
+/// was compared against, and whether it matched.
+#[derive(Debug, Clone)]
+pub struct MatchTrace {
+    /// Describes the pattern, such as the node kind or the literal text.
+    pub pattern: String,
+    /// The field of the parent node the pattern was matched against, if any.
+    pub field: Option<String>,
+    /// The range of the target, or an empty range where an absent field was
+    /// expected.
+    pub range: ByteRange,
+    pub outcome: MatchOutcome,
+    pub children: Vec<MatchTrace>,
+    /// The escape sequences resolved in the source of the snippet, which is
+    /// only recorded on the step describing the snippet itself.
+    pub escapes: EscapeMap,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchOutcome {
+    Matched,
+    Mismatched(String),
+    /// The pattern can only be evaluated while running a query, such as a
+    /// regex with metavariables or a `where` clause.
+    Untraced,
+}
+
+impl MatchTrace {
+    pub fn matched(&self) -> bool {
+        !matches!(self.outcome, MatchOutcome::Mismatched(_))
+    }
+
+    /// Returns the path from this step to the innermost mismatch, following the
+    /// first failing child at every level.
+    pub fn mismatch_path(&self) -> Vec<&MatchTrace> {
+        let mut path = Vec::new();
+        let mut current = self;
+        while !current.matched() {
+            path.push(current);
+            match current.children.iter().find(|child| !child.matched()) {
+                Some(child) => current = child,
+                None => break,
+            }
+        }
+        path
+    }
+
+    fn new(pattern: impl Into<String>, range: ByteRange, outcome: MatchOutcome) -> Self {
+        Self {
+            pattern: pattern.into(),
+            field: None,
+            range,
+            outcome,
+            children: Vec::new(),
+            escapes: EscapeMap::default(),
+        }
+    }
+
+    fn with_children(mut self, children: Vec<MatchTrace>) -> Self {
+        self.children = children;
+        self
+    }
+
+    fn with_escapes(mut self, escapes: EscapeMap) -> Self {
+        self.escapes = escapes;
+        self
+    }
+}
+
+impl Problem {
+    /// Explains why `snippet`, which must be part of this problem, does or
+    /// doesn't match `node`.
+    pub fn explain_snippet(
+        &self,
+        snippet: &MarzanoCodeSnippet,
+        node: &NodeWithSource,
+    ) -> MatchTrace {
+        let mut tracer = Tracer {
+            problem: self,
+            bindings: HashMap::new(),
+        };
+        tracer.trace_snippet(snippet, node)
+    }
+}
+
+/// What a pattern is matched against.
+#[derive(Clone)]
+enum Target<'a> {
+    Node(NodeWithSource<'a>),
+    /// An absent field, at the given position of its parent.
+    Empty(ByteRange),
+    List(Vec<NodeWithSource<'a>>, ByteRange),
+}
+
+impl Target<'_> {
+    fn range(&self) -> ByteRange {
+        match self {
+            Target::Node(node) => node.byte_range(),
+            Target::Empty(range) | Target::List(_, range) => *range,
+        }
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Target::Node(node) => node
+                .text()
+                .map(|t| t.trim().to_string())
+                .unwrap_or_default(),
+            Target::Empty(_) => String::new(),
+            Target::List(nodes, _) => nodes
+                .iter()
+                .filter_map(|node| node.text().ok())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+struct Tracer<'a> {
+    problem: &'a Problem,
+    /// Text bound to each metavariable so far, keyed by scope and index.
+    bindings: HashMap<(usize, usize), String>,
+}
+
+impl Tracer<'_> {
+    fn trace_snippet(&mut self, snippet: &MarzanoCodeSnippet, node: &NodeWithSource) -> MatchTrace {
+        let range = node.byte_range();
+        let description = format!("`{}`", snippet.source);
+        let (_, escapes) = process_snippet_escapes(&snippet.source);
+        let Some((_, pattern)) = snippet
+            .patterns
+            .iter()
+            .find(|(sort, _)| *sort == node.node.kind_id())
+        else {
+            let expected = snippet
+                .patterns
+                .iter()
+                .map(|(sort, _)| self.kind_name(*sort))
+                .collect::<Vec<_>>()
+                .join(" or ");
+            let found = self.kind_name(node.node.kind_id());
+            return MatchTrace::new(
+                description,
+                range,
+                MatchOutcome::Mismatched(format!("expected kind {expected}, found {found}")),
+            )
+            .with_escapes(escapes);
+        };
+        let trace = self.trace(pattern, Target::Node(node.clone()));
+        let outcome = if trace.matched() {
+            MatchOutcome::Matched
+        } else {
+            MatchOutcome::Mismatched("the snippet did not match".to_string())
+        };
+        MatchTrace::new(description, range, outcome)
+            .with_children(vec![trace])
+            .with_escapes(escapes)
+    }
+
+    fn trace(&mut self, pattern: &Pattern<MarzanoQueryContext>, target: Target) -> MatchTrace {
+        let range = target.range();
+        if let Pattern::AstNode(node) = pattern {
+            self.trace_ast_node(node, target)
+        } else if let Pattern::AstLeafNode(leaf) = pattern {
+            let expected = leaf.text().unwrap_or_default().to_string();
+            let Target::Node(node) = self.ungroup(leaf.sort(), target) else {
+                return MatchTrace::new(
+                    format!("`{expected}`"),
+                    range,
+                    MatchOutcome::Mismatched(format!("expected `{expected}`, found nothing")),
+                );
+            };
+            let outcome = if leaf
+                .matches_node(&node, &self.problem.language)
+                .unwrap_or(false)
+            {
+                MatchOutcome::Matched
+            } else {
+                let found = Target::Node(node.clone()).text();
+                MatchOutcome::Mismatched(format!("expected `{expected}`, found `{found}`"))
+            };
+            MatchTrace::new(format!("`{expected}`"), node.byte_range(), outcome)
+        } else if let Pattern::List(list) = pattern {
+            self.trace_list(list, target)
+        } else if let Pattern::Variable(variable) = pattern {
+            self.trace_variable(variable, target)
+        } else if let Pattern::Underscore | Pattern::Top | Pattern::Dots = pattern {
+            MatchTrace::new(pattern.name(), range, MatchOutcome::Matched)
+        } else if let Pattern::Dynamic(DynamicPattern::Snippet(snippet)) = pattern {
+            // absent optional fields are compiled to an empty snippet
+            let [DynamicSnippetPart::String(text)] = snippet.parts.as_slice() else {
+                return MatchTrace::new(pattern.name(), range, MatchOutcome::Untraced);
+            };
+            let found = target.text();
+            let outcome = if found == *text {
+                MatchOutcome::Matched
+            } else if text.is_empty() {
+                MatchOutcome::Mismatched(format!("expected nothing, found `{found}`"))
+            } else {
+                MatchOutcome::Mismatched(format!("expected `{text}`, found `{found}`"))
+            };
+            MatchTrace::new(format!("`{text}`"), range, outcome)
+        } else if let Pattern::And(and) = pattern {
+            let children = and
+                .patterns
+                .iter()
+                .map(|pattern| self.trace(pattern, target.clone()))
+                .collect::<Vec<_>>();
+            let outcome = if children.iter().all(MatchTrace::matched) {
+                MatchOutcome::Matched
+            } else {
+                MatchOutcome::Mismatched("not every pattern matched".to_string())
+            };
+            MatchTrace::new(pattern.name(), range, outcome).with_children(children)
+        } else if let Pattern::Not(not) = pattern {
+            let inner = self.trace(&not.pattern, target);
+            let outcome = if inner.outcome == MatchOutcome::Untraced {
+                MatchOutcome::Untraced
+            } else if inner.matched() {
+                MatchOutcome::Mismatched("the negated pattern matched".to_string())
+            } else {
+                MatchOutcome::Matched
+            };
+            MatchTrace::new(pattern.name(), range, outcome).with_children(vec![inner])
+        } else {
+            MatchTrace::new(pattern.name(), range, MatchOutcome::Untraced)
+        }
+    }
+
+    fn trace_ast_node(&mut self, pattern: &ASTNode, target: Target) -> MatchTrace {
+        let expected = self.kind_name(pattern.sort);
+        let range = target.range();
+        let node = match self.ungroup(pattern.sort, target) {
+            Target::Node(node) => node,
+            other => {
+                let found = other.text();
+                let found = if found.is_empty() {
+                    "nothing".to_string()
+                } else {
+                    format!("`{found}`")
+                };
+                return MatchTrace::new(
+                    expected.clone(),
+                    range,
+                    MatchOutcome::Mismatched(format!("expected kind {expected}, found {found}")),
+                );
+            }
+        };
+        if node.node.kind_id() != pattern.sort {
+            let found = self.kind_name(node.node.kind_id());
+            return MatchTrace::new(
+                expected.clone(),
+                node.byte_range(),
+                MatchOutcome::Mismatched(format!("expected kind {expected}, found {found}")),
+            );
+        }
+        if self.problem.language.is_comment_sort(pattern.sort) {
+            return MatchTrace::new(expected, node.byte_range(), MatchOutcome::Untraced);
+        }
+        let mut children = Vec::new();
+        let mut failed_fields = Vec::new();
+        for (field_id, is_list, field_pattern) in &pattern.args {
+            let field_name = self
+                .problem
+                .language
+                .get_ts_language()
+                .field_name_for_id(*field_id)
+                .unwrap_or_default()
+                .to_string();
+            let end = ByteRange::new(node.node.end_byte() as usize, node.node.end_byte() as usize);
+            let target = if *is_list {
+                Target::List(node.named_children_by_field_id(*field_id).collect(), end)
+            } else if let Some(child) = node.child_by_field_id(*field_id) {
+                Target::Node(child)
+            } else {
+                Target::Empty(end)
+            };
+            let mut trace = self.trace(field_pattern, target);
+            if !trace.matched() {
+                failed_fields.push(format!("`{field_name}`"));
+            }
+            trace.field = Some(field_name);
+            children.push(trace);
+        }
+        let outcome = if failed_fields.is_empty() {
+            MatchOutcome::Matched
+        } else {
+            MatchOutcome::Mismatched(format!(
+                "field {} of {expected} did not match",
+                failed_fields.join(", ")
+            ))
+        };
+        MatchTrace::new(expected, node.byte_range(), outcome).with_children(children)
+    }
+
+    fn trace_list(&mut self, list: &List<MarzanoQueryContext>, target: Target) -> MatchTrace {
+        let range = target.range();
+        let Target::List(nodes, _) = target else {
+            return MatchTrace::new(
+                "list",
+                range,
+                MatchOutcome::Mismatched("expected a list".to_string()),
+            );
+        };
+        if list
+            .patterns
+            .iter()
+            .any(|pattern| matches!(pattern, Pattern::Dots | Pattern::Spread(_)))
+        {
+            // which elements the dots cover is only decided while matching
+            return MatchTrace::new("list", range, MatchOutcome::Untraced);
+        }
+        let children = list
+            .patterns
+            .iter()
+            .zip(nodes.iter())
+            .map(|(pattern, node)| self.trace(pattern, Target::Node(node.clone())))
+            .collect::<Vec<_>>();
+        let outcome = if list.patterns.len() != nodes.len() {
+            MatchOutcome::Mismatched(format!(
+                "expected {} elements, found {}",
+                list.patterns.len(),
+                nodes.len()
+            ))
+        } else if children.iter().all(MatchTrace::matched) {
+            MatchOutcome::Matched
+        } else {
+            MatchOutcome::Mismatched("not every element matched".to_string())
+        };
+        MatchTrace::new("list", range, outcome).with_children(children)
+    }
+
+    fn trace_variable(&mut self, variable: &Variable, target: Target) -> MatchTrace {
+        let name = self
+            .problem
+            .variables
+            .locations
+            .get(variable.scope)
+            .and_then(|scope| scope.get(variable.index))
+            .map_or_else(|| "metavariable".to_string(), |var| var.name.clone());
+        let range = target.range();
+        let text = target.text();
+        let outcome = match self.bindings.get(&(variable.scope, variable.index)) {
+            Some(bound) if *bound != text => MatchOutcome::Mismatched(format!(
+                "{name} is already bound to `{bound}`, found `{text}`"
+            )),
+            Some(_) => MatchOutcome::Matched,
+            None => {
+                self.bindings.insert((variable.scope, variable.index), text);
+                MatchOutcome::Matched
+            }
+        };
+        MatchTrace::new(name, range, outcome)
+    }
+
+    /// Sees through grouping nodes, the same way matching does.
+    fn ungroup<'b>(&self, sort: SortId, target: Target<'b>) -> Target<'b> {
+        let Target::Node(mut node) = target else {
+            return target;
+        };
+        while node.node.kind_id() != sort {
+            let Some(inner) = self.problem.language.ungrouped_node(&node) else {
+                break;
+            };
+            node = inner;
+        }
+        Target::Node(node)
+    }
+
+    fn kind_name(&self, sort: SortId) -> String {
+        self.problem
+            .language
+            .get_ts_language()
+            .node_kind_for_id(sort)
+            .unwrap_or_default()
+            .to_string()
+    }
+}
@@ -15,9 +15,10 @@ use grit_util::AnalysisLogs;
 #[cfg(feature = "external_functions")]
 use marzano_externals::function::ExternalFunction;
 use marzano_language::foreign_language::ForeignLanguage;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForeignFunctionDefinition {
     pub name: String,
     pub params: Vec<(String, Variable)>,
@@ -0,0 +1,470 @@
+//! Reconstructs approximate GritQL source from compiled patterns.
+//!
+//! Compilation is lossy: comments, formatting, and pattern definitions are
+//! gone, and snippets are printed from their source text rather than their
+//! parsed form. The output is meant for debugging and for tools that want to
+//! show what a pattern compiled to, so constructs that can't be written back
+//! as GritQL are printed as `<name>` placeholders instead of failing.
+
+use crate::{
+    ast_node::ASTNode, marzano_code_snippet::MarzanoCodeSnippet, problem::MarzanoQueryContext,
+    problem::Problem,
+};
+use grit_pattern_matcher::pattern::{
+    AccessorKey, AccessorMap, AstLeafNodePattern, CallBuiltIn, Container, ContainerOrIndex,
+    DynamicPattern, DynamicSnippetPart, ListOrContainer, Pattern, PatternName, Predicate,
+    RegexLike, Variable,
+};
+use itertools::Itertools;
+use marzano_language::language::MarzanoLanguage;
+
+impl Problem {
+    /// Prints the compiled pattern as GritQL, including the `sequential`,
+    /// `file`, and `contains bubble` wrappers added by auto-wrapping.
+    ///
+    /// Recompiling the output yields an equivalent pattern, as long as it
+    /// only calls built-ins: pattern, predicate, and function definitions are
+    /// not printed.
+    pub fn to_grit_source(&self) -> String {
+        self.pattern_to_grit_source(&self.pattern)
+    }
+
+    /// Prints any (sub-)pattern of this problem as GritQL.
+    pub fn pattern_to_grit_source(&self, pattern: &Pattern<MarzanoQueryContext>) -> String {
+        Printer { problem: self }.pattern(pattern)
+    }
+}
+
+struct Printer<'a> {
+    problem: &'a Problem,
+}
+
+impl Printer<'_> {
+    fn pattern(&self, pattern: &Pattern<MarzanoQueryContext>) -> String {
+        match pattern {
+            Pattern::AstNode(node) => self.ast_node(node),
+            Pattern::AstLeafNode(leaf) => format!("`{}`", leaf.text().unwrap_or_default()),
+            Pattern::CodeSnippet(snippet) => self.code_snippet(snippet),
+            Pattern::Dynamic(dynamic) => self.dynamic(dynamic),
+            Pattern::Variable(variable) => self.variable(variable),
+            Pattern::List(list) => format!("[{}]", self.patterns(&list.patterns)),
+            Pattern::ListIndex(index) => self.list_index(&index.list, &index.index),
+            Pattern::Map(map) => self.map(&map.elements),
+            Pattern::Accessor(accessor) => self.accessor(&accessor.map, &accessor.key),
+            Pattern::Call(call) => {
+                let definition = &self.problem.pattern_definitions[call.index];
+                self.call(&definition.name, &definition.params, &call.args)
+            }
+            Pattern::CallBuiltIn(call) => self.call_built_in(call),
+            Pattern::CallFunction(call) => {
+                let definition = &self.problem.function_definitions[call.index];
+                self.call(&definition.name, &definition.params, &call.args)
+            }
+            Pattern::CallForeignFunction(call) => {
+                let definition = &self.problem.foreign_function_definitions[call.index];
+                self.call(&definition.name, &definition.params, &call.args)
+            }
+            Pattern::Regex(regex) => {
+                let source = match &regex.regex {
+                    RegexLike::Regex(regex) => format!("r{}", quote(regex)),
+                    RegexLike::Pattern(pattern) => format!("r{}", self.pattern(pattern)),
+                };
+                if regex.variables.is_empty() {
+                    source
+                } else {
+                    format!("{source}({})", self.variables(&regex.variables))
+                }
+            }
+            Pattern::File(file) => format!(
+                "file(name = {}, body = {})",
+                self.pattern(&file.name),
+                self.pattern(&file.body)
+            ),
+            Pattern::Bubble(bubble) => {
+                let params = &bubble.pattern_def.params;
+                let body = self.pattern(&bubble.pattern_def.pattern);
+                if params.is_empty() {
+                    format!("bubble {body}")
+                } else {
+                    let params = params.iter().map(|(name, _)| name).join(", ");
+                    format!("bubble({params}) {body}")
+                }
+            }
+            Pattern::Limit(limit) => {
+                format!("{} limit {}", self.pattern(&limit.pattern), limit.limit)
+            }
+            Pattern::Assignment(assignment) => format!(
+                "{} = {}",
+                self.container(&assignment.container),
+                self.pattern(&assignment.pattern)
+            ),
+            Pattern::Accumulate(accumulate) => format!(
+                "{} += {}",
+                self.pattern(&accumulate.left),
+                self.pattern(&accumulate.right)
+            ),
+            Pattern::And(and) => format!("and {{ {} }}", self.patterns(&and.patterns)),
+            Pattern::Or(or) => format!("or {{ {} }}", self.patterns(&or.patterns)),
+            Pattern::Any(any) => format!("any {{ {} }}", self.patterns(&any.patterns)),
+            Pattern::Maybe(maybe) => format!("maybe {}", self.pattern(&maybe.pattern)),
+            Pattern::Not(not) => format!("not {}", self.pattern(&not.pattern)),
+            Pattern::If(if_) => format!(
+                "if ({}) {} else {}",
+                self.predicate(&if_.if_),
+                self.pattern(&if_.then),
+                self.pattern(&if_.else_)
+            ),
+            Pattern::Undefined => "undefined".to_string(),
+            Pattern::Top => "top".to_string(),
+            Pattern::Bottom => "bottom".to_string(),
+            Pattern::Underscore => "_".to_string(),
+            Pattern::StringConstant(string) => quote(&string.text),
+            Pattern::IntConstant(int) => int.value.to_string(),
+            Pattern::FloatConstant(float) => format!("{:?}", float.value),
+            Pattern::BooleanConstant(boolean) => boolean.value.to_string(),
+            Pattern::Rewrite(rewrite) => format!(
+                "{} => {}",
+                self.pattern(&rewrite.left),
+                self.dynamic(&rewrite.right)
+            ),
+            Pattern::Log(log) => match &log.message {
+                Some(message) => format!("log(message = {})", self.pattern(message)),
+                None => "log()".to_string(),
+            },
+            Pattern::Contains(contains) => match &contains.until {
+                Some(until) => format!(
+                    "contains {} until {}",
+                    self.pattern(&contains.contains),
+                    self.pattern(until)
+                ),
+                None => format!("contains {}", self.pattern(&contains.contains)),
+            },
+            Pattern::Includes(includes) => format!("includes {}", self.pattern(&includes.includes)),
+            Pattern::Within(within) => format!("within {}", self.pattern(&within.pattern)),
+            Pattern::After(after) => format!("after {}", self.pattern(&after.after)),
+            Pattern::Before(before) => format!("before {}", self.pattern(&before.before)),
+            Pattern::Where(where_) => format!(
+                "{} where {{ {} }}",
+                self.pattern(&where_.pattern),
+                self.side_conditions(&where_.side_condition)
+            ),
+            Pattern::Some(some) => format!("some {}", self.pattern(&some.pattern)),
+            Pattern::Every(every) => format!("every {}", self.pattern(&every.pattern)),
+            Pattern::Add(add) => self.operation(&add.lhs, "+", &add.rhs),
+            Pattern::Subtract(subtract) => self.operation(&subtract.lhs, "-", &subtract.rhs),
+            Pattern::Multiply(multiply) => self.operation(&multiply.lhs, "*", &multiply.rhs),
+            Pattern::Divide(divide) => self.operation(&divide.lhs, "/", &divide.rhs),
+            Pattern::Modulo(modulo) => self.operation(&modulo.lhs, "%", &modulo.rhs),
+            Pattern::Dots => "...".to_string(),
+            Pattern::Spread(spread) => {
+                let name = self.variable(&spread.variable);
+                let name = name.strip_prefix('$').unwrap_or(&name);
+                let lazy = if spread.lazy { "?" } else { "" };
+                format!("$...{name}{lazy}")
+            }
+            Pattern::Sequential(sequential) => format!(
+                "sequential {{ {} }}",
+                sequential
+                    .iter()
+                    .map(|step| self.pattern(&step.pattern))
+                    .join(", ")
+            ),
+            Pattern::Like(like) => format!(
+                "like({}) {}",
+                self.pattern(&like.threshold),
+                self.pattern(&like.like)
+            ),
+            Pattern::Files(_) | Pattern::Range(_) => placeholder(pattern.name()),
+        }
+    }
+
+    fn predicate(&self, predicate: &Predicate<MarzanoQueryContext>) -> String {
+        match predicate {
+            Predicate::Call(call) => {
+                let definition = &self.problem.predicate_definitions[call.index];
+                self.call(&definition.name, &definition.params, &call.args)
+            }
+            Predicate::Not(not) => format!("not {}", self.predicate(&not.predicate)),
+            Predicate::If(if_) => format!(
+                "if ({}) {{ {} }} else {{ {} }}",
+                self.predicate(&if_.if_),
+                self.predicate(&if_.then),
+                self.predicate(&if_.else_)
+            ),
+            Predicate::True => "true".to_string(),
+            Predicate::False => "false".to_string(),
+            Predicate::Or(or) => format!("or {{ {} }}", self.predicates(&or.predicates)),
+            Predicate::And(and) => format!("and {{ {} }}", self.predicates(&and.predicates)),
+            Predicate::Maybe(maybe) => format!("maybe {}", self.predicate(&maybe.predicate)),
+            Predicate::Any(any) => format!("any {{ {} }}", self.predicates(&any.predicates)),
+            Predicate::Rewrite(rewrite) => format!(
+                "{} => {}",
+                self.pattern(&rewrite.left),
+                self.dynamic(&rewrite.right)
+            ),
+            Predicate::Log(log) => match &log.message {
+                Some(message) => format!("log(message = {})", self.pattern(message)),
+                None => "log()".to_string(),
+            },
+            Predicate::Match(match_) => format!(
+                "{} <: {}",
+                self.container(&match_.val),
+                match_
+                    .pattern
+                    .as_ref()
+                    .map_or_else(|| "undefined".to_string(), |pattern| self.pattern(pattern))
+            ),
+            Predicate::Equal(equal) => format!(
+                "{} == {}",
+                self.variable(&equal.var),
+                self.pattern(&equal.pattern)
+            ),
+            Predicate::EditDistance(edit_distance) => format!(
+                "{}~{} == {}",
+                self.variable(&edit_distance.var),
+                edit_distance.max_distance,
+                self.pattern(&edit_distance.target)
+            ),
+            Predicate::Assignment(assignment) => format!(
+                "{} = {}",
+                self.container(&assignment.container),
+                self.pattern(&assignment.pattern)
+            ),
+            Predicate::Accumulate(accumulate) => format!(
+                "{} += {}",
+                self.pattern(&accumulate.left),
+                self.pattern(&accumulate.right)
+            ),
+            Predicate::Return(return_) => format!("return {}", self.pattern(&return_.pattern)),
+        }
+    }
+
+    /// Prints the body of a `where` clause, flattening a top-level `and`.
+    fn side_conditions(&self, predicate: &Predicate<MarzanoQueryContext>) -> String {
+        if let Predicate::And(and) = predicate {
+            self.predicates(&and.predicates)
+        } else {
+            self.predicate(predicate)
+        }
+    }
+
+    fn dynamic(&self, dynamic: &DynamicPattern<MarzanoQueryContext>) -> String {
+        match dynamic {
+            DynamicPattern::Variable(variable) => self.variable(variable),
+            DynamicPattern::Accessor(accessor) => self.accessor(&accessor.map, &accessor.key),
+            DynamicPattern::ListIndex(index) => self.list_index(&index.list, &index.index),
+            DynamicPattern::Snippet(snippet) => {
+                let source: String = snippet
+                    .parts
+                    .iter()
+                    .map(|part| match part {
+                        DynamicSnippetPart::String(string) => string.clone(),
+                        DynamicSnippetPart::Variable(variable) => self.variable(variable),
+                        DynamicSnippetPart::Index(variable) => {
+                            let name = self.variable(variable);
+                            let name = name.strip_prefix('$').unwrap_or(&name);
+                            format!("$[{name}.index]")
+                        }
+                        DynamicSnippetPart::Quote(variable) => {
+                            let name = self.variable(variable);
+                            let name = name.strip_prefix('$').unwrap_or(&name);
+                            format!("$[{name}.quote]")
+                        }
+                        DynamicSnippetPart::Text(variable) => {
+                            let name = self.variable(variable);
+                            let name = name.strip_prefix('$').unwrap_or(&name);
+                            format!("$[{name}.text]")
+                        }
+                        DynamicSnippetPart::Indent(variable) => {
+                            let name = self.variable(variable);
+                            let name = name.strip_prefix('$').unwrap_or(&name);
+                            format!("$[{name}.indent]")
+                        }
+                        DynamicSnippetPart::Kind(variable) => {
+                            let name = self.variable(variable);
+                            let name = name.strip_prefix('$').unwrap_or(&name);
+                            format!("$[{name}.kind]")
+                        }
+                        DynamicSnippetPart::PreviousSibling(variable) => {
+                            let name = self.variable(variable);
+                            let name = name.strip_prefix('$').unwrap_or(&name);
+                            format!("$[{name}.prev]")
+                        }
+                        DynamicSnippetPart::NextSibling(variable) => {
+                            let name = self.variable(variable);
+                            let name = name.strip_prefix('$').unwrap_or(&name);
+                            format!("$[{name}.next]")
+                        }
+                    })
+                    .collect();
+                // The empty snippet is what `.` compiles to.
+                if source.is_empty() {
+                    ".".to_string()
+                } else {
+                    format!("`{source}`")
+                }
+            }
+            DynamicPattern::List(list) => format!(
+                "[{}]",
+                list.elements
+                    .iter()
+                    .map(|element| self.dynamic(element))
+                    .join(", ")
+            ),
+            DynamicPattern::CallBuiltIn(call) => self.call_built_in(call),
+            DynamicPattern::CallFunction(call) => {
+                let definition = &self.problem.function_definitions[call.index];
+                self.call(&definition.name, &definition.params, &call.args)
+            }
+            DynamicPattern::CallForeignFunction(call) => {
+                let definition = &self.problem.foreign_function_definitions[call.index];
+                self.call(&definition.name, &definition.params, &call.args)
+            }
+        }
+    }
+
+    fn code_snippet(&self, snippet: &MarzanoCodeSnippet) -> String {
+        format!("`{}`", snippet.source)
+    }
+
+    fn ast_node(&self, node: &ASTNode) -> String {
+        let lang = self.problem.language.get_ts_language();
+        let kind = lang.node_kind_for_id(node.sort).unwrap_or_default();
+        let args = node
+            .args
+            .iter()
+            .map(|(field_id, _, pattern)| {
+                let field = lang.field_name_for_id(*field_id).unwrap_or_default();
+                format!("{field} = {}", self.pattern(pattern))
+            })
+            .join(", ");
+        format!("{kind}({args})")
+    }
+
+    fn container(&self, container: &Container<MarzanoQueryContext>) -> String {
+        match container {
+            Container::Variable(variable) => self.variable(variable),
+            Container::Accessor(accessor) => self.accessor(&accessor.map, &accessor.key),
+            Container::ListIndex(index) => self.list_index(&index.list, &index.index),
+            Container::FunctionCall(call) => self.call_built_in(call),
+        }
+    }
+
+    fn accessor(&self, map: &AccessorMap<MarzanoQueryContext>, key: &AccessorKey) -> String {
+        let map = match map {
+            AccessorMap::Container(container) => self.container(container),
+            AccessorMap::Map(map) => self.map(&map.elements),
+        };
+        match key {
+            AccessorKey::String(key) => format!("{map}.{key}"),
+            AccessorKey::Variable(variable) => format!("{map}[{}]", self.variable(variable)),
+        }
+    }
+
+    fn list_index(
+        &self,
+        list: &ListOrContainer<MarzanoQueryContext>,
+        index: &ContainerOrIndex<MarzanoQueryContext>,
+    ) -> String {
+        let list = match list {
+            ListOrContainer::Container(container) => self.container(container),
+            ListOrContainer::List(list) => format!("[{}]", self.patterns(&list.patterns)),
+        };
+        let index = match index {
+            ContainerOrIndex::Container(container) => self.container(container),
+            ContainerOrIndex::Index(index) => index.to_string(),
+        };
+        format!("{list}[{index}]")
+    }
+
+    fn map<'b>(
+        &self,
+        elements: impl IntoIterator<Item = (&'b String, &'b Pattern<MarzanoQueryContext>)>,
+    ) -> String {
+        let elements = elements
+            .into_iter()
+            .map(|(key, value)| format!("{key}: {}", self.pattern(value)))
+            .join(", ");
+        format!("{{ {elements} }}")
+    }
+
+    fn call_built_in(&self, call: &CallBuiltIn<MarzanoQueryContext>) -> String {
+        let built_in = &self.problem.built_ins.get_built_ins()[call.index];
+        let args = built_in
+            .params
+            .iter()
+            .zip(&call.args)
+            .filter_map(|(param, arg)| {
+                arg.as_ref()
+                    .map(|arg| format!("{param} = {}", self.pattern(arg)))
+            })
+            .join(", ");
+        format!("{}({args})", built_in.name)
+    }
+
+    /// Prints a call to a definition, passing arguments by name.
+    fn call(
+        &self,
+        name: &str,
+        params: &[(String, Variable)],
+        args: &[Option<Pattern<MarzanoQueryContext>>],
+    ) -> String {
+        let args = params
+            .iter()
+            .zip(args)
+            .filter_map(|((param, _), arg)| {
+                let param = param.strip_prefix('$').unwrap_or(param);
+                arg.as_ref()
+                    .map(|arg| format!("{param} = {}", self.pattern(arg)))
+            })
+            .join(", ");
+        format!("{name}({args})")
+    }
+
+    fn operation(
+        &self,
+        lhs: &Pattern<MarzanoQueryContext>,
+        operator: &str,
+        rhs: &Pattern<MarzanoQueryContext>,
+    ) -> String {
+        format!("({} {operator} {})", self.pattern(lhs), self.pattern(rhs))
+    }
+
+    fn patterns(&self, patterns: &[Pattern<MarzanoQueryContext>]) -> String {
+        patterns
+            .iter()
+            .map(|pattern| self.pattern(pattern))
+            .join(", ")
+    }
+
+    fn predicates(&self, predicates: &[Predicate<MarzanoQueryContext>]) -> String {
+        predicates
+            .iter()
+            .map(|predicate| self.predicate(predicate))
+            .join(", ")
+    }
+
+    fn variables(&self, variables: &[Variable]) -> String {
+        variables
+            .iter()
+            .map(|variable| self.variable(variable))
+            .join(", ")
+    }
+
+    fn variable(&self, variable: &Variable) -> String {
+        self.problem
+            .variables
+            .locations
+            .get(variable.scope)
+            .and_then(|scope| scope.get(variable.index))
+            .map_or_else(|| placeholder("variable"), |var| var.name.clone())
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn placeholder(name: &str) -> String {
+    format!("<{}>", name.to_lowercase())
+}
@@ -7,8 +7,10 @@ mod clean;
 pub mod compact_api;
 pub mod constants;
 mod equivalence;
+pub mod explain;
 mod foreign_function_definition;
 pub mod fs;
+mod grit_source;
 mod inline_snippets;
 mod limits;
 pub mod marzano_binding;
@@ -19,12 +21,18 @@ mod optimizer;
 pub mod parse;
 mod paths;
 pub mod pattern_compiler;
+pub mod prepared_snippet;
 pub mod problem;
+pub mod problem_cache;
+pub mod rewrite_diff;
 mod smart_insert;
+pub mod snippet_escapes;
 mod split_snippet;
 mod suppress;
 mod text_unparser;
+pub mod todo_comments;
 pub mod tree_sitter_serde;
+pub mod validate_snippet;
 mod variables;
 
 // getrandom is a deeply nested dependency used by many things eg. uuid
@@ -15,7 +15,7 @@ use grit_util::{
     AnalysisLogBuilder, AnalysisLogs, AstNode, ByteRange, CodeRange, Language, Position, Range,
 };
 use itertools::{EitherOrBoth, Itertools};
-use marzano_language::language::{FieldId, MarzanoLanguage};
+use marzano_language::language::{FieldId, MarzanoLanguage, NodeTypes};
 use marzano_language::target_language::TargetLanguage;
 use marzano_util::node_with_source::NodeWithSource;
 use std::ops::Range as StdRange;
@@ -52,6 +52,142 @@ impl PartialEq for MarzanoBinding<'_> {
     }
 }
 
+impl<'a> MarzanoBinding<'a> {
+    /// Returns the position of a node binding within the "multiple" field of
+    /// its parent, or `None` if the binding is not an element of a list.
+    pub(crate) fn list_index(&self, language: &TargetLanguage) -> Option<usize> {
+        let Self::Node(node) = self else {
+            return None;
+        };
+        let parent = node.parent()?;
+        language
+            .node_types()
+            .get(parent.node.kind_id() as usize)?
+            .iter()
+            .filter(|field| field.multiple())
+            .find_map(|field| {
+                parent
+                    .named_children_by_field_id(field.id())
+                    .position(|child| &child == node)
+            })
+    }
+
+    /// Returns the source of the binding as written, without applying any
+    /// rewrites.
+    ///
+    /// A list binding covers everything between the tokens delimiting the
+    /// list, such as the parentheses of call arguments, along with its
+    /// separators and spacing.
+    pub(crate) fn verbatim_text(&self, language: &TargetLanguage) -> Result<String> {
+        let Self::List(parent, field_id) = self else {
+            return Ok(self.text(language)?.into_owned());
+        };
+        let Some((first, last)) = get_range_nodes_for_list(parent, field_id, language) else {
+            return Ok(String::new());
+        };
+        let mut start = first.node.start_byte() as usize;
+        let mut end = last.node.end_byte() as usize;
+        let opening = parent.children().next();
+        let closing = parent.children().last();
+        if let (Some(opening), Some(closing)) = (opening, closing) {
+            let (opening, closing) = (opening.node, closing.node);
+            if !opening.is_named()
+                && !closing.is_named()
+                && opening.end_byte() as usize <= start
+                && closing.start_byte() as usize >= end
+            {
+                start = opening.end_byte() as usize;
+                end = closing.start_byte() as usize;
+            }
+        }
+        Ok(parent.source[start..end].to_owned())
+    }
+
+    /// Returns the quotes delimiting a string literal binding, such as `'` or
+    /// `"""`, or `None` if the binding is not a quoted string.
+    pub(crate) fn string_quote(&self) -> Option<String> {
+        let Self::Node(node) = self else {
+            return None;
+        };
+        let text = node.text().ok()?;
+        string_quote(&text).map(str::to_string)
+    }
+
+    /// Returns the whitespace the line of the binding starts with, or `None`
+    /// if the binding is not code in a file.
+    pub(crate) fn indentation(&self, language: &TargetLanguage) -> Option<String> {
+        let node = match self {
+            Self::Node(node) => node.clone(),
+            Self::List(parent, field_id) => get_range_nodes_for_list(parent, field_id, language)?.0,
+            _ => return None,
+        };
+        let start = node.node.start_byte() as usize;
+        let line_start = node.source[..start]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line = &node.source[line_start..start];
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        Some(indent.to_string())
+    }
+
+    /// Returns the kind of the node the binding is, such as `object`, or `None`
+    /// if the binding is not a single node.
+    pub(crate) fn node_kind(&self) -> Option<String> {
+        let Self::Node(node) = self else {
+            return None;
+        };
+        Some(node.node.kind().to_string())
+    }
+
+    /// Returns the source of the named sibling next to the node the binding
+    /// is, skipping comments, looking at the following sibling if `following`
+    /// is set and the preceding one otherwise.
+    ///
+    /// The text is empty if there is no such sibling, and `None` if the
+    /// binding is not a single node.
+    pub(crate) fn sibling_text(
+        &self,
+        language: &TargetLanguage,
+        following: bool,
+    ) -> Option<String> {
+        let Self::Node(node) = self else {
+            return None;
+        };
+        let mut sibling = node.node.clone();
+        loop {
+            let next = if following {
+                sibling.next_named_sibling()
+            } else {
+                sibling.prev_named_sibling()
+            };
+            let Some(next) = next else {
+                return Some(String::new());
+            };
+            let next = NodeWithSource::new(next, node.source);
+            if !language.is_comment_node(&next) {
+                return Some(next.text().ok()?.to_string());
+            }
+            sibling = next.node;
+        }
+    }
+}
+
+fn string_quote(text: &str) -> Option<&str> {
+    // Skip string prefixes, such as Python's `f` and `r`.
+    let literal = &text[text.find(|c: char| !c.is_ascii_alphabetic())?..];
+    let quote_char = literal
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '"' | '\'' | '`'))?;
+    [3, 1].into_iter().find_map(|len| {
+        let quote = literal.get(..len)?;
+        (literal.len() >= 2 * len
+            && quote.chars().all(|c| c == quote_char)
+            && literal.ends_with(quote))
+        .then_some(quote)
+    })
+}
+
 pub(crate) fn pad_snippet(padding: &str, snippet: &str, lang: &impl Language) -> Result<String> {
     let mut lines = snippet.split('\n');
     let mut result = lines.next().unwrap_or_default().to_string();
@@ -172,6 +308,7 @@ impl EffectRange {
         match self.kind {
             EffectKind::Rewrite => self.range.clone(),
             EffectKind::Insert => self.range.end..self.range.end,
+            EffectKind::InsertBefore => self.range.start..self.range.start,
         }
     }
 }
@@ -224,7 +361,7 @@ pub(crate) fn linearize_binding<'a, Q: QueryContext>(
                             memo.insert(range.clone(), None);
                         }
                     }
-                    EffectKind::Insert => {}
+                    EffectKind::Insert | EffectKind::InsertBefore => {}
                 }
             } else {
                 binding.log_empty_field_rewrite_error(language, logs)?;
@@ -237,6 +374,7 @@ pub(crate) fn linearize_binding<'a, Q: QueryContext>(
                 distributed_indent.is_some(),
                 logs,
             )?;
+            let res: Cow<str> = language.post_process_rewrite(&res).into();
             if let Some(range) = binding_range {
                 if matches!(effect.kind, EffectKind::Rewrite) {
                     memo.insert(range, Some(res.to_string()));
@@ -252,16 +390,7 @@ pub(crate) fn linearize_binding<'a, Q: QueryContext>(
             let range = b
                 .range(language)
                 .ok_or_else(|| anyhow!("binding has no position"))?;
-            match k {
-                EffectKind::Insert => Ok((
-                    EffectRange::new(EffectKind::Insert, range.start..range.end),
-                    s.to_string(),
-                )),
-                EffectKind::Rewrite => Ok((
-                    EffectRange::new(EffectKind::Rewrite, range.start..range.end),
-                    s.to_string(),
-                )),
-            }
+            Ok((EffectRange::new(*k, range.start..range.end), s.to_string()))
         })
         .collect::<Result<Vec<_>>>()?;
 
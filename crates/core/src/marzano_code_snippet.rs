@@ -6,16 +6,178 @@ use anyhow::Result;
 use grit_pattern_matcher::{
     binding::Binding,
     context::ExecContext,
-    pattern::{CodeSnippet, DynamicPattern, Matcher, Pattern, PatternName, ResolvedPattern, State},
+    pattern::{
+        CodeSnippet, DynamicPattern, Matcher, Pattern, PatternName, PatternOrResolved,
+        ResolvedPattern, State, Variable,
+    },
 };
-use grit_util::AnalysisLogs;
-use marzano_language::language::SortId;
+use grit_util::{AnalysisLogs, AstNode, ByteRange, Language};
+use marzano_language::{
+    language::{MarzanoLanguage, SortId},
+    target_language::TargetLanguage,
+};
+use marzano_util::node_with_source::NodeWithSource;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarzanoCodeSnippet {
     pub(crate) patterns: Vec<(SortId, Pattern<MarzanoQueryContext>)>,
     pub(crate) source: String,
     pub(crate) dynamic_snippet: Option<DynamicPattern<MarzanoQueryContext>>,
+    #[serde(default)]
+    pub(crate) content_constraints: Vec<ContentConstraint>,
+    #[serde(default)]
+    pub(crate) sibling_constraints: Vec<SiblingConstraint>,
+    /// Range of the snippet's content within the source of the pattern that
+    /// declares it.
+    #[serde(default)]
+    pub(crate) range: Option<ByteRange>,
+}
+
+/// Whether the node is a statement, or the whole content of one: walking up
+/// from it, every parent starts where it does and has no other named child
+/// until a statement is reached.
+pub(crate) fn is_whole_statement(node: &NodeWithSource, language: &TargetLanguage) -> bool {
+    let mut node = node.clone();
+    loop {
+        if language.is_statement(&node) {
+            return true;
+        }
+        let Some(parent) = node.parent() else {
+            return false;
+        };
+        if parent.node.start_byte() != node.node.start_byte()
+            || parent.named_children().count() != 1
+        {
+            return false;
+        }
+        node = parent;
+    }
+}
+
+/// Where a node must be among its siblings, written as `@first`, `@last` or
+/// `@nth(n)` in a snippet, counting from one.
+///
+/// Siblings are the named children of the node's parent, other than comments,
+/// whichever field of the parent they are in. Lists such as the statements of
+/// a block or the arguments of a call have a node of their own, so this is
+/// the position within the list; for a parent with several fields, such as
+/// the condition and body of an `if`, it is the position among all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum SiblingPosition {
+    First,
+    Last,
+    Nth(usize),
+}
+
+/// A sibling position the node bound to a metavariable, or the node matched
+/// by the snippet, must be at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SiblingConstraint {
+    /// `None` constrains the node the snippet matches.
+    pub(crate) variable: Option<Variable>,
+    pub(crate) position: SiblingPosition,
+}
+
+impl SiblingPosition {
+    fn holds(self, node: &NodeWithSource, language: &TargetLanguage) -> bool {
+        let Some(parent) = node.parent() else {
+            return false;
+        };
+        let siblings = parent
+            .named_children()
+            .filter(|child| !language.is_comment_node(child))
+            .collect::<Vec<_>>();
+        let Some(index) = siblings.iter().position(|sibling| sibling == node) else {
+            return false;
+        };
+        match self {
+            Self::First => index == 0,
+            Self::Last => index + 1 == siblings.len(),
+            Self::Nth(n) => index + 1 == n,
+        }
+    }
+}
+
+impl SiblingConstraint {
+    fn matches(
+        &self,
+        state: &State<MarzanoQueryContext>,
+        language: &TargetLanguage,
+    ) -> Result<bool> {
+        let Some(variable) = &self.variable else {
+            return Ok(true);
+        };
+        let Some(PatternOrResolved::Resolved(resolved)) =
+            variable.get_pattern_or_resolved(state)?
+        else {
+            return Ok(false);
+        };
+        Ok(resolved
+            .get_last_binding()
+            .and_then(|binding| binding.singleton())
+            .is_some_and(|node| self.position.holds(&node, language)))
+    }
+}
+
+/// A regex the content of a metavariable inside a string literal must match,
+/// written as `"$name~/regex/"` in a snippet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ContentConstraint {
+    pub(crate) variable: Variable,
+    /// Anchored, so it must match the entire decoded content.
+    #[serde(
+        serialize_with = "serialize_regex",
+        deserialize_with = "deserialize_regex"
+    )]
+    pub(crate) regex: Regex,
+}
+
+fn serialize_regex<S: Serializer>(regex: &Regex, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(regex.as_str())
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let source = String::deserialize(deserializer)?;
+    Regex::new(&source).map_err(serde::de::Error::custom)
+}
+
+impl ContentConstraint {
+    fn matches<'a>(
+        &self,
+        state: &State<'a, MarzanoQueryContext>,
+        context: &'a MarzanoContext<'a>,
+    ) -> Result<bool> {
+        let text = self.variable.text(state, context.language())?;
+        Ok(self.regex.is_match(&decode_escapes(&text)))
+    }
+}
+
+/// Resolves backslash escapes, so constraints see the value of a literal
+/// rather than its source representation.
+fn decode_escapes(text: &str) -> Cow<str> {
+    if !text.contains('\\') {
+        return Cow::Borrowed(text);
+    }
+    let mut decoded = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => decoded.push('\n'),
+            Some('r') => decoded.push('\r'),
+            Some('t') => decoded.push('\t'),
+            Some('0') => decoded.push('\0'),
+            Some(other) => decoded.push(other),
+            None => decoded.push('\\'),
+        }
+    }
+    Cow::Owned(decoded)
 }
 
 impl MarzanoCodeSnippet {
@@ -28,8 +190,38 @@ impl MarzanoCodeSnippet {
             patterns,
             source: source.to_string(),
             dynamic_snippet,
+            content_constraints: Vec::new(),
+            sibling_constraints: Vec::new(),
+            range: None,
         }
     }
+
+    /// The source of the snippet, without its backticks.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Range of the snippet within the source of the pattern that declares
+    /// it, which may be a library rather than the main pattern. `None` for a
+    /// snippet that wasn't compiled from source.
+    pub fn range(&self) -> Option<ByteRange> {
+        self.range
+    }
+
+    pub(crate) fn with_range(mut self, range: ByteRange) -> Self {
+        self.range = Some(range);
+        self
+    }
+
+    pub(crate) fn with_content_constraints(mut self, constraints: Vec<ContentConstraint>) -> Self {
+        self.content_constraints = constraints;
+        self
+    }
+
+    pub(crate) fn with_sibling_constraints(mut self, constraints: Vec<SiblingConstraint>) -> Self {
+        self.sibling_constraints = constraints;
+        self
+    }
 }
 
 impl CodeSnippet<MarzanoQueryContext> for MarzanoCodeSnippet {
@@ -64,15 +256,83 @@ impl Matcher<MarzanoQueryContext> for MarzanoCodeSnippet {
         let Some(node) = binding.singleton() else {
             return Ok(false);
         };
+        self.execute_node(
+            node,
+            resolved,
+            state,
+            context,
+            logs,
+            |sort| {
+                self.patterns
+                    .iter()
+                    .find(|(id, _)| *id == sort)
+                    .map(|(_, pattern)| pattern)
+            },
+            |sort| context.language.transparent_sorts().contains(&sort),
+        )
+    }
+}
 
-        if let Some((_, pattern)) = self
-            .patterns
-            .iter()
-            .find(|(id, _)| *id == node.node.kind_id())
+impl MarzanoCodeSnippet {
+    /// Matches the node bound by `resolved`, looking up the pattern for each
+    /// sort with `pattern_for`, so prepared matchers can use a table instead.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn execute_node<'a>(
+        &'a self,
+        mut node: NodeWithSource<'a>,
+        resolved: &MarzanoResolvedPattern<'a>,
+        state: &mut State<'a, MarzanoQueryContext>,
+        context: &'a MarzanoContext<'a>,
+        logs: &mut AnalysisLogs,
+        pattern_for: impl Fn(SortId) -> Option<&'a Pattern<MarzanoQueryContext>>,
+        is_transparent: impl Fn(SortId) -> bool,
+    ) -> Result<bool> {
+        if self.sibling_constraints.iter().any(|constraint| {
+            constraint.variable.is_none() && !constraint.position.holds(&node, context.language)
+        }) {
+            return Ok(false);
+        }
+
+        // See through transparent wrappers the snippet didn't ask for.
+        let mut unwrapped = None;
+        while pattern_for(node.node.kind_id()).is_none() {
+            if !is_transparent(node.node.kind_id()) {
+                break;
+            }
+            let Some(inner) = context.language.wrapped_node(&node) else {
+                break;
+            };
+            node = inner;
+            unwrapped = Some(MarzanoResolvedPattern::from_node_binding(node.clone()));
+        }
+        let resolved = unwrapped.as_ref().unwrap_or(resolved);
+
+        let Some(pattern) = pattern_for(node.node.kind_id()) else {
+            return Ok(false);
+        };
+        if self.content_constraints.is_empty()
+            && self
+                .sibling_constraints
+                .iter()
+                .all(|constraint| constraint.variable.is_none())
         {
-            pattern.execute(resolved, state, context, logs)
-        } else {
-            Ok(false)
+            return pattern.execute(resolved, state, context, logs);
+        }
+        let mut cur_state = state.clone();
+        if !pattern.execute(resolved, &mut cur_state, context, logs)? {
+            return Ok(false);
+        }
+        for constraint in &self.content_constraints {
+            if !constraint.matches(&cur_state, context)? {
+                return Ok(false);
+            }
+        }
+        for constraint in &self.sibling_constraints {
+            if !constraint.matches(&cur_state, context.language)? {
+                return Ok(false);
+            }
         }
+        *state = cur_state;
+        Ok(true)
     }
 }
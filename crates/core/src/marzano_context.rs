@@ -19,7 +19,9 @@ use grit_pattern_matcher::{
         PredicateDefinition, ResolvedPattern, State,
     },
 };
-use grit_util::{AnalysisLogs, Ast, FileOrigin, InputRanges, MatchRanges};
+use grit_util::{
+    traverse, AnalysisLogs, Ast, AstNode, FileOrigin, InputRanges, MatchRanges, Order, Position,
+};
 use im::vector;
 use marzano_language::{
     language::{MarzanoLanguage, Tree},
@@ -109,6 +111,10 @@ impl<'a> ExecContext<'a, MarzanoQueryContext> for MarzanoContext<'a> {
         self.runtime.ignore_limit_pattern
     }
 
+    fn is_cancelled(&self) -> bool {
+        self.runtime.is_cancelled()
+    }
+
     fn call_built_in(
         &self,
         call: &'a CallBuiltIn<MarzanoQueryContext>,
@@ -252,6 +258,14 @@ impl<'a> ExecContext<'a, MarzanoQueryContext> for MarzanoContext<'a> {
                     let tree = parser
                         .parse_file(&new_src, None, logs, FileOrigin::Mutated(&file.tree))
                         .unwrap();
+                    if self.runtime.validate_rhs && !file.tree.root_node().node.has_error() {
+                        if let Some((position, line)) = first_syntax_error(&tree) {
+                            bail!(
+                                "rewriting {} produced code which doesn't parse, at {position}:\n{line}",
+                                filename_path.display()
+                            );
+                        }
+                    }
                     let root = tree.root_node();
                     let replacement_ranges = get_replacement_ranges(root, self.language());
                     let cleaned_src = replace_cleaned_ranges(replacement_ranges, &new_src)?;
@@ -339,3 +353,21 @@ impl<'a> ExecContext<'a, MarzanoQueryContext> for MarzanoContext<'a> {
         self.name.as_deref()
     }
 }
+
+/// Returns the position of the first syntax error in `tree`, along with the
+/// line it occurs on.
+fn first_syntax_error(tree: &Tree) -> Option<(Position, &str)> {
+    let root = tree.root_node();
+    if !root.node.has_error() {
+        return None;
+    }
+    let error = traverse(root.walk(), Order::Pre)
+        .find(|node| node.node.is_error() || node.node.is_missing())?;
+    let source = tree.source.as_str();
+    let start = error.node.start_byte() as usize;
+    let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |index| start + index);
+    Some((error.range().start, &source[line_start..line_end]))
+}
@@ -1,6 +1,9 @@
 use crate::{
-    marzano_binding::MarzanoBinding, marzano_code_snippet::MarzanoCodeSnippet,
-    marzano_context::MarzanoContext, paths::absolutize, problem::MarzanoQueryContext,
+    marzano_binding::MarzanoBinding,
+    marzano_code_snippet::{is_whole_statement, MarzanoCodeSnippet},
+    marzano_context::MarzanoContext,
+    paths::absolutize,
+    problem::MarzanoQueryContext,
 };
 use anyhow::{anyhow, bail, Result};
 use grit_pattern_matcher::{
@@ -11,10 +14,10 @@ use grit_pattern_matcher::{
     pattern::{
         to_unsigned, Accessor, DynamicPattern, DynamicSnippet, DynamicSnippetPart, File, FilePtr,
         FileRegistry, GritCall, ListIndex, Pattern, PatternName, PatternOrResolved, ResolvedFile,
-        ResolvedPattern, ResolvedSnippet, State,
+        ResolvedPattern, ResolvedSnippet, State, Variable, VariableContent,
     },
 };
-use grit_util::{AnalysisLogs, Ast, AstNode, CodeRange, Range};
+use grit_util::{AnalysisLogs, Ast, AstNode, CodeRange, Language, Range};
 use im::{vector, Vector};
 use marzano_language::{language::FieldId, target_language::TargetLanguage};
 use marzano_util::node_with_source::NodeWithSource;
@@ -360,7 +363,7 @@ impl<'a> ResolvedPattern<'a, MarzanoQueryContext> for MarzanoResolvedPattern<'a>
                     parts.push(ResolvedSnippet::Text(string.into()));
                 }
                 DynamicSnippetPart::Variable(var) => {
-                    let content = &state.bindings[var.scope].last().unwrap()[var.index];
+                    let content = bound_content(state, var);
                     let name = &content.name;
                     // feels weird not sure if clone is correct
                     let value = if let Some(value) = &content.value {
@@ -376,6 +379,98 @@ impl<'a> ResolvedPattern<'a, MarzanoQueryContext> for MarzanoResolvedPattern<'a>
                     let value = value.to_snippets()?;
                     parts.extend(value);
                 }
+                DynamicSnippetPart::Index(var) => {
+                    let content = bound_content(state, var);
+                    let index = content
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.get_last_binding())
+                        .and_then(|binding| binding.list_index(context.language()))
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "cannot take the index of {}, it is not bound to a list element",
+                                content.name
+                            )
+                        })?;
+                    parts.push(ResolvedSnippet::Text(index.to_string().into()));
+                }
+                DynamicSnippetPart::Quote(var) => {
+                    let content = bound_content(state, var);
+                    let quote = content
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.get_last_binding())
+                        .and_then(|binding| binding.string_quote())
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "cannot take the quotes of {}, it is not bound to a string literal",
+                                content.name
+                            )
+                        })?;
+                    parts.push(ResolvedSnippet::Text(quote.into()));
+                }
+                DynamicSnippetPart::Text(var) => {
+                    let content = bound_content(state, var);
+                    let text = content
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.get_last_binding())
+                        .map(|binding| binding.verbatim_text(context.language()))
+                        .transpose()?
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "cannot take the text of {}, it is not bound to code",
+                                content.name
+                            )
+                        })?;
+                    parts.push(ResolvedSnippet::Text(text.into()));
+                }
+                DynamicSnippetPart::Indent(var) => {
+                    let content = bound_content(state, var);
+                    let indent = content
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.get_last_binding())
+                        .and_then(|binding| binding.indentation(context.language()))
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "cannot take the indentation of {}, it is not bound to code",
+                                content.name
+                            )
+                        })?;
+                    parts.push(ResolvedSnippet::Text(indent.into()));
+                }
+                DynamicSnippetPart::Kind(var) => {
+                    let content = bound_content(state, var);
+                    let kind = content
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.get_last_binding())
+                        .and_then(|binding| binding.node_kind())
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "cannot take the kind of {}, it is not bound to a node",
+                                content.name
+                            )
+                        })?;
+                    parts.push(ResolvedSnippet::Text(kind.into()));
+                }
+                DynamicSnippetPart::PreviousSibling(var) | DynamicSnippetPart::NextSibling(var) => {
+                    let following = matches!(part, DynamicSnippetPart::NextSibling(_));
+                    let content = bound_content(state, var);
+                    let sibling = content
+                        .value
+                        .as_ref()
+                        .and_then(|value| value.get_last_binding())
+                        .and_then(|binding| binding.sibling_text(context.language(), following))
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "cannot take the siblings of {}, it is not bound to a node",
+                                content.name
+                            )
+                        })?;
+                    parts.push(ResolvedSnippet::Text(sibling.into()));
+                }
             }
         }
         Ok(Self::Snippets(parts.into()))
@@ -389,7 +484,7 @@ impl<'a> ResolvedPattern<'a, MarzanoQueryContext> for MarzanoResolvedPattern<'a>
     ) -> Result<Self> {
         match pattern {
             DynamicPattern::Variable(var) => {
-                let content = &state.bindings[var.scope].last().unwrap()[var.index];
+                let content = bound_content(state, var);
                 let name = &content.name;
                 // feels weird not sure if clone is correct
                 if let Some(value) = &content.value {
@@ -420,6 +515,13 @@ impl<'a> ResolvedPattern<'a, MarzanoQueryContext> for MarzanoResolvedPattern<'a>
             DynamicPattern::CallBuiltIn(built_in) => built_in.call(state, context, logs),
             DynamicPattern::CallFunction(func) => func.call(state, context, logs),
             DynamicPattern::CallForeignFunction(func) => func.call(state, context, logs),
+            DynamicPattern::Assignment(assignment) => {
+                let resolved = Self::from_pattern(&assignment.pattern, state, context, logs)?;
+                assignment
+                    .container
+                    .set_resolved(state, context.language(), resolved.clone())?;
+                Ok(resolved)
+            }
         }
     }
 
@@ -477,7 +579,7 @@ impl<'a> ResolvedPattern<'a, MarzanoQueryContext> for MarzanoResolvedPattern<'a>
             Pattern::FloatConstant(double) => Ok(Self::Constant(Constant::Float(double.value))),
             Pattern::BooleanConstant(bool) => Ok(Self::Constant(Constant::Boolean(bool.value))),
             Pattern::Variable(var) => {
-                let content = &state.bindings[var.scope].last().unwrap()[var.index];
+                let content = bound_content(state, var);
                 let name = &content.name;
                 // feels weird not sure if clone is correct
                 if let Some(value) = &content.value {
@@ -561,6 +663,7 @@ impl<'a> ResolvedPattern<'a, MarzanoQueryContext> for MarzanoResolvedPattern<'a>
             | Pattern::Some(_)
             | Pattern::Every(_)
             | Pattern::Dots
+            | Pattern::Spread(_)
             | Pattern::Like(_)
             | Pattern::Sequential(_) => Err(anyhow!(format!(
                 "cannot make resolved pattern from arbitrary pattern {}",
@@ -814,6 +917,49 @@ impl<'a> ResolvedPattern<'a, MarzanoQueryContext> for MarzanoResolvedPattern<'a>
         Ok(())
     }
 
+    fn normalize_insert_before(
+        &mut self,
+        binding: &MarzanoBinding<'a>,
+        language: &TargetLanguage,
+    ) -> Result<()> {
+        let Self::Snippets(ref mut snippets) = self else {
+            return Ok(());
+        };
+        let Some(ResolvedSnippet::Text(text)) = snippets.back() else {
+            return Ok(());
+        };
+        let Some(node) = binding.as_node() else {
+            return Ok(());
+        };
+        if !is_whole_statement(&node, language) {
+            return Ok(());
+        }
+        // Statements inserted on their own line start it with the indentation
+        // of the statement they precede, which then moves to the next line.
+        let start = node.node.start_byte() as usize;
+        let line_start = node.source[..start].rfind('\n').map_or(0, |n| n + 1);
+        let indent = &node.source[line_start..start];
+        let separator = if !indent.trim().is_empty() {
+            if text.ends_with(char::is_whitespace) {
+                String::new()
+            } else {
+                " ".to_string()
+            }
+        } else {
+            let newline = if text.ends_with('\n') { "" } else { "\n" };
+            let indent = if language.should_pad_snippet() {
+                ""
+            } else {
+                indent
+            };
+            format!("{newline}{indent}")
+        };
+        if !separator.is_empty() {
+            snippets.push_back(ResolvedSnippet::Text(separator.into()));
+        }
+        Ok(())
+    }
+
     fn is_truthy(
         &self,
         state: &mut State<'a, MarzanoQueryContext>,
@@ -894,6 +1040,14 @@ impl<'a> File<'a, MarzanoQueryContext> for MarzanoFile<'a> {
     }
 }
 
+/// The content bound to `var` in the innermost scope.
+fn bound_content<'a, 'b>(
+    state: &'b State<'a, MarzanoQueryContext>,
+    var: &Variable,
+) -> &'b VariableContent<'a, MarzanoQueryContext> {
+    &state.bindings[var.scope].last().unwrap()[var.index]
+}
+
 fn extract_file_pointer(file: &MarzanoFile) -> Option<FilePtr> {
     match file {
         MarzanoFile::Resolved(_) => None,
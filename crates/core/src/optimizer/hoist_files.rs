@@ -1,7 +1,7 @@
 use anyhow::Result;
 use grit_pattern_matcher::{
     context::QueryContext,
-    pattern::{And, Any, Bubble, Contains, Includes, Or, Pattern, Predicate, Where},
+    pattern::{And, Any, Bubble, Contains, Includes, Or, Pattern, Predicate, RegexLike, Where},
 };
 
 trait FilenamePatternExtractor<Q: QueryContext> {
@@ -109,6 +109,7 @@ pub fn extract_filename_pattern<Q: QueryContext>(
 
         // TODO: decide the rest of these
         Pattern::Dots
+        | Pattern::Spread(_)
         | Pattern::Sequential(_)
         | Pattern::Like(_)
         | Pattern::AstNode(_)
@@ -281,7 +282,10 @@ impl<Q: QueryContext> FilenamePatternExtractor<Q> for Predicate<Q> {
             }
 
             // These are more complicated, implement carefully
-            Predicate::Call(_) | Predicate::Not(_) | Predicate::Equal(_) => Ok(None),
+            Predicate::Call(_)
+            | Predicate::Not(_)
+            | Predicate::Equal(_)
+            | Predicate::EditDistance(_) => Ok(None),
         }
     }
 }
@@ -294,6 +298,11 @@ fn is_safe_to_hoist<Q: QueryContext>(pattern: &Pattern<Q>) -> Result<bool> {
     match pattern {
         Pattern::Includes(inc) => is_safe_to_hoist(&inc.includes),
         Pattern::StringConstant(_) => Ok(true),
+        // globs compile to regexes without any variables to bind
+        Pattern::Regex(regex) => {
+            Ok(regex.variables.is_empty() && matches!(regex.regex, RegexLike::Regex(_)))
+        }
+        Pattern::Not(not) => is_safe_to_hoist(&not.pattern),
         // This is conservative, but it's a start
         Pattern::AstNode(_)
         | Pattern::List(_)
@@ -301,7 +310,6 @@ fn is_safe_to_hoist<Q: QueryContext>(pattern: &Pattern<Q>) -> Result<bool> {
         | Pattern::Map(_)
         | Pattern::Accessor(_)
         | Pattern::Call(_)
-        | Pattern::Regex(_)
         | Pattern::File(_)
         | Pattern::Files(_)
         | Pattern::Bubble(_)
@@ -315,7 +323,6 @@ fn is_safe_to_hoist<Q: QueryContext>(pattern: &Pattern<Q>) -> Result<bool> {
         | Pattern::Or(_)
         | Pattern::Maybe(_)
         | Pattern::Any(_)
-        | Pattern::Not(_)
         | Pattern::If(_)
         | Pattern::Undefined
         | Pattern::Top
@@ -344,6 +351,7 @@ fn is_safe_to_hoist<Q: QueryContext>(pattern: &Pattern<Q>) -> Result<bool> {
         | Pattern::Divide(_)
         | Pattern::Modulo(_)
         | Pattern::Dots
+        | Pattern::Spread(_)
         | Pattern::Sequential(_)
         | Pattern::Like(_) => Ok(false),
     }
@@ -82,6 +82,7 @@ impl NodeCompiler for AccumulateCompiler {
             | Pattern::Divide(_)
             | Pattern::Modulo(_)
             | Pattern::Dots
+            | Pattern::Spread(_)
             | Pattern::Like(_)
             | Pattern::Sequential(_) => None,
         };
@@ -135,7 +135,8 @@ fn is_sequential<Q: QueryContext>(
         | Pattern::Divide(_)
         | Pattern::Modulo(_)
         | Pattern::Like(_)
-        | Pattern::Dots => false,
+        | Pattern::Dots
+        | Pattern::Spread(_) => false,
     }
 }
 
@@ -199,7 +200,8 @@ pub(crate) fn should_autowrap<Q: QueryContext>(
         | Pattern::Divide(_)
         | Pattern::Modulo(_)
         | Pattern::Like(_)
-        | Pattern::Dots => true,
+        | Pattern::Dots
+        | Pattern::Spread(_) => true,
     }
 }
 
@@ -221,8 +223,9 @@ fn extract_limit_pattern<Q: QueryContext>(
         }
         Pattern::Rewrite(r) => {
             let extracted = extract_limit_pattern(r.left, pattern_definitions);
-            let pattern =
-                Pattern::Rewrite(Box::new(Rewrite::new(extracted.0, r.right, r.annotation)));
+            let pattern = Pattern::Rewrite(Box::new(
+                Rewrite::new(extracted.0, r.right, r.annotation).with_kind(r.kind),
+            ));
             (pattern, extracted.1)
         }
         Pattern::Bubble(b) => {
@@ -293,7 +296,8 @@ fn extract_limit_pattern<Q: QueryContext>(
         | Pattern::Divide(_)
         | Pattern::Modulo(_)
         | Pattern::Like(_)
-        | Pattern::Dots => (pattern, None),
+        | Pattern::Dots
+        | Pattern::Spread(_) => (pattern, None),
     }
 }
 
@@ -357,7 +361,8 @@ fn should_wrap_in_file<Q: QueryContext>(
         | Pattern::Divide(_)
         | Pattern::Modulo(_)
         | Pattern::Like(_)
-        | Pattern::Dots => true,
+        | Pattern::Dots
+        | Pattern::Spread(_) => true,
     }
 }
 
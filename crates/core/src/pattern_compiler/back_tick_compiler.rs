@@ -1,11 +1,14 @@
 use super::{
-    compiler::NodeCompilationContext, node_compiler::NodeCompiler,
+    compiler::{NodeCompilationContext, SpreadPreference},
+    node_compiler::NodeCompiler,
     snippet_compiler::parse_snippet_content,
 };
-use crate::problem::MarzanoQueryContext;
+use crate::{
+    problem::MarzanoQueryContext, split_snippet::split_snippet, variables::register_variable,
+};
 use anyhow::{anyhow, bail, Result};
-use grit_pattern_matcher::pattern::Pattern;
-use grit_util::AstNode;
+use grit_pattern_matcher::pattern::{Pattern, RegexLike, RegexPattern};
+use grit_util::{AstNode, ByteRange};
 use marzano_util::node_with_source::NodeWithSource;
 
 pub(crate) struct BackTickCompiler;
@@ -26,7 +29,7 @@ impl NodeCompiler for BackTickCompiler {
             .ok_or_else(|| anyhow!("Unable to extract content from snippet: {source}"))?
             .strip_suffix('`')
             .ok_or_else(|| anyhow!("Unable to extract content from snippet: {source}"))?;
-        parse_snippet_content(content, range.into(), context, is_rhs)
+        parse_snippet_content(content, range, context, is_rhs)
     }
 }
 
@@ -52,6 +55,101 @@ impl NodeCompiler for RawBackTickCompiler {
             .ok_or_else(|| anyhow!("Unable to extract content from raw snippet: {}", source))?
             .strip_suffix('`')
             .ok_or_else(|| anyhow!("Unable to extract content from raw snippet: {}", source))?;
-        parse_snippet_content(content, range.into(), context, is_rhs)
+        parse_snippet_content(content, range, context, is_rhs)
+    }
+}
+
+/// Marks the characters a metavariable of a text snippet can't capture, as
+/// in `$name~[^}]`, so its binding can't cross a delimiter.
+const STOP_SET_MARKER: &str = "~[^";
+
+/// Compiles snippet text into a regex which matches it literally, except for
+/// its metavariables, which capture the text in their place, up to any
+/// character of their stop set.
+///
+/// With `flexible_whitespace`, any run of whitespace in the text matches any
+/// amount of whitespace instead.
+pub(crate) fn text_regex_pattern(
+    content: &str,
+    range: ByteRange,
+    flexible_whitespace: bool,
+    context: &mut NodeCompilationContext,
+) -> Result<RegexPattern<MarzanoQueryContext>> {
+    let push_literal = |regex: &mut String, literal: &str| {
+        if !flexible_whitespace {
+            regex.push_str(&regex::escape(literal));
+            return;
+        }
+        for (index, part) in literal.split_whitespace().enumerate() {
+            if index > 0 || literal.starts_with(char::is_whitespace) {
+                regex.push_str(r"\s*");
+            }
+            regex.push_str(&regex::escape(part));
+        }
+        if literal.ends_with(char::is_whitespace) {
+            regex.push_str(r"\s*");
+        }
+    };
+    let repetition = match context.compilation.options.spread_preference {
+        Some(SpreadPreference::Longest) => "*",
+        Some(SpreadPreference::Shortest) | None => "*?",
+    };
+    let mut regex = String::from("(?s)");
+    let mut variables = Vec::new();
+    let mut last = 0;
+    // Reverse the iterator so we go over the variables in ascending order.
+    for (var_range, var) in split_snippet(content, context.compilation.lang)
+        .into_iter()
+        .rev()
+    {
+        if var_range.start < last {
+            // Part of the previous metavariable's stop set.
+            continue;
+        }
+        push_literal(&mut regex, &content[last..var_range.start]);
+        let (any, stop_set_len) = match stop_set(&content[var_range.end..])? {
+            Some((stops, len)) => (format!("[^{}]", regex::escape(&stops)), len),
+            None => (".".to_string(), 0),
+        };
+        match var.as_ref() {
+            "$_" | "^_" | "$..." | "^..." => {
+                regex.push_str(&format!("{any}{repetition}"));
+            }
+            name => {
+                let var_range =
+                    ByteRange::new(range.start + var_range.start, range.start + var_range.end);
+                variables.push(register_variable(name, var_range, context)?);
+                regex.push_str(&format!("({any}{repetition})"));
+            }
+        }
+        last = var_range.end + stop_set_len;
+    }
+    push_literal(&mut regex, &content[last..]);
+
+    Ok(RegexPattern::new(RegexLike::Regex(regex), variables))
+}
+
+/// The characters of the stop set `source` starts with, if any, along with
+/// the length of the stop set. Within the set, `\]` stands for `]`.
+fn stop_set(source: &str) -> Result<Option<(String, usize)>> {
+    let Some(rest) = source.strip_prefix(STOP_SET_MARKER) else {
+        return Ok(None);
+    };
+    let mut stops = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if c == '\\' {
+            if let Some((_, escaped)) = chars.next() {
+                stops.push(escaped);
+            }
+        } else if c == ']' {
+            if stops.is_empty() {
+                bail!("the stop set of a metavariable needs at least one character");
+            }
+            return Ok(Some((stops, STOP_SET_MARKER.len() + index + 1)));
+        } else {
+            stops.push(c);
+        }
     }
+    bail!("unterminated stop set `{source}`, it must end with `]`")
 }
@@ -1,14 +1,16 @@
 use super::{
     auto_wrap::auto_wrap_pattern,
     compiler::{
-        filter_libs, get_definition_info, get_definitions, CompilationContext, DefinitionInfo,
-        DefinitionInfoKinds, NodeCompilationContext, VariableLocations,
+        filter_libs, get_definition_info, get_definitions, CompilationContext,
+        CompilationDiagnostic, CompilationOptions, DefinitionInfo, DefinitionInfoKinds,
+        NodeCompilationContext, SnippetCompileTiming, VariableLocations,
     },
     pattern_compiler::PatternCompiler,
+    snippet_compiler::lint_broad_snippet,
     CompilationResult, NodeCompiler,
 };
 use crate::{
-    analysis::{has_limit, is_multifile},
+    analysis::{has_limit, has_rewrite, is_multifile},
     built_in_functions::{BuiltInFunction, BuiltIns},
     foreign_function_definition::ForeignFunctionDefinition,
     marzano_context::MarzanoContext,
@@ -26,9 +28,9 @@ use grit_pattern_matcher::{
         PROGRAM_INDEX,
     },
     pattern::{
-        And, BooleanConstant, CallBuiltIn, Container, GritFunctionDefinition, Match, Pattern,
-        PatternDefinition, Predicate, PredicateDefinition, ResolvedPattern,
-        VariableSourceLocations, Where,
+        And, BooleanConstant, CallBuiltIn, Container, DynamicPattern, GritFunctionDefinition,
+        Match, Pattern, PatternDefinition, Predicate, PredicateDefinition, ResolvedPattern,
+        Rewrite, Variable, VariableSourceLocations, Where,
     },
 };
 use grit_util::{AnalysisLogs, Ast, FileRange};
@@ -37,7 +39,7 @@ use marzano_language::{
     self, grit_parser::MarzanoGritParser, language::Tree, target_language::TargetLanguage,
 };
 
-use std::{collections::BTreeMap, path::Path, vec};
+use std::{cell::RefCell, collections::BTreeMap, path::Path, vec};
 
 pub type CallbackMatchFn = dyn for<'a> Fn(
         &<problem::MarzanoQueryContext as grit_pattern_matcher::context::QueryContext>::ResolvedPattern<'a>,
@@ -48,6 +50,15 @@ pub type CallbackMatchFn = dyn for<'a> Fn(
     + Send
     + Sync;
 
+pub type CallbackRewriteFn = dyn for<'a> Fn(
+        &<problem::MarzanoQueryContext as grit_pattern_matcher::context::QueryContext>::ResolvedPattern<'a>,
+        &'a MarzanoContext<'a>,
+        &mut State<'a, MarzanoQueryContext>,
+        &mut AnalysisLogs,
+    ) -> Result<String>
+    + Send
+    + Sync;
+
 /// Pattern Builder allows you to progressively compile a pattern.
 /// You always start with a source GritQL string, but additional patterns can be attached before the final query.
 pub struct PatternBuilder {
@@ -77,10 +88,12 @@ pub struct PatternBuilder {
     foreign_function_definitions: Vec<ForeignFunctionDefinition>,
 
     compilation_warnings: AnalysisLogs,
+    options: CompilationOptions,
+    snippet_timings: RefCell<Vec<SnippetCompileTiming>>,
+    diagnostics: RefCell<Vec<CompilationDiagnostic>>,
 }
 
 impl PatternBuilder {
-    #[allow(clippy::too_many_arguments)]
     pub fn start(
         src: String,
         libs: &BTreeMap<String, String>,
@@ -88,6 +101,28 @@ impl PatternBuilder {
         name: Option<String>,
         grit_parser: &mut MarzanoGritParser,
         custom_built_ins: Option<BuiltIns>,
+    ) -> Result<Self> {
+        Self::start_with_options(
+            src,
+            libs,
+            lang,
+            name,
+            grit_parser,
+            custom_built_ins,
+            CompilationOptions::default(),
+        )
+    }
+
+    /// Same as [`PatternBuilder::start`], but with explicit compilation options.
+    #[allow(clippy::too_many_arguments)]
+    pub fn start_with_options(
+        src: String,
+        libs: &BTreeMap<String, String>,
+        lang: TargetLanguage,
+        name: Option<String>,
+        grit_parser: &mut MarzanoGritParser,
+        custom_built_ins: Option<BuiltIns>,
+        options: CompilationOptions,
     ) -> Result<Self> {
         if src == "." {
             let error = ". never matches and should not be used as a pattern. Did you mean to run 'grit apply <pattern> .'?";
@@ -109,6 +144,9 @@ impl PatternBuilder {
         ]);
         let is_multifile = is_multifile(&root, libs, grit_parser)?;
         let has_limit = has_limit(&root, libs, grit_parser)?;
+        if options.case_insensitive_identifiers && has_rewrite(&root, libs, grit_parser)? {
+            bail!("case-insensitive identifiers are only supported by patterns which don't rewrite code");
+        }
         let libs = filter_libs(libs, &src, grit_parser, !is_multifile)?;
         let DefinitionInfoKinds {
             pattern_indices: pattern_definition_indices,
@@ -117,6 +155,8 @@ impl PatternBuilder {
             foreign_function_indices,
         } = get_definition_info(&libs, &root, grit_parser)?;
 
+        let snippet_timings = RefCell::new(Vec::new());
+        let diagnostics = RefCell::new(Vec::new());
         let context = CompilationContext {
             file: DEFAULT_FILE_NAME,
             built_ins: &built_ins,
@@ -125,6 +165,9 @@ impl PatternBuilder {
             predicate_definition_info: &predicate_definition_indices,
             function_definition_info: &function_definition_indices,
             foreign_function_definition_info: &foreign_function_indices,
+            options: &options,
+            snippet_timings: options.profile_snippets.then_some(&snippet_timings),
+            diagnostics: options.recover_snippet_errors.then_some(&diagnostics),
         };
 
         let DefinitionOutput {
@@ -155,7 +198,9 @@ impl PatternBuilder {
         };
 
         let pattern = if let Some(node) = root.child_by_field_name("pattern") {
-            PatternCompiler::from_node(&node, &mut node_context)?
+            let pattern = PatternCompiler::from_node(&node, &mut node_context)?;
+            lint_broad_snippet(&node, &pattern, &mut node_context)?;
+            pattern
         } else {
             let long_message = "No pattern found.
         If you have written a pattern definition in the form `pattern myPattern() {{ }}`,
@@ -191,6 +236,9 @@ impl PatternBuilder {
             foreign_function_definitions,
 
             compilation_warnings: logs,
+            options,
+            snippet_timings,
+            diagnostics,
         })
     }
 
@@ -211,6 +259,15 @@ impl PatternBuilder {
             predicate_definition_info: &self.predicate_definition_indices,
             function_definition_info: &self.function_definition_indices,
             foreign_function_definition_info: &self.foreign_function_indices,
+            options: &self.options,
+            snippet_timings: self
+                .options
+                .profile_snippets
+                .then_some(&self.snippet_timings),
+            diagnostics: self
+                .options
+                .recover_snippet_errors
+                .then_some(&self.diagnostics),
         };
 
         let mut node_context = NodeCompilationContext {
@@ -265,6 +322,49 @@ impl PatternBuilder {
                 )))
             }));
 
+        let match_var = self.match_variable();
+        let predicate_match = Predicate::Match(Box::new(Match::new(
+            Container::FunctionCall(Box::new(CallBuiltIn::new(
+                index,
+                vec![Some(grit_pattern_matcher::pattern::Pattern::Variable(
+                    match_var,
+                ))],
+            ))),
+            Some(Pattern::BooleanConstant(BooleanConstant::new(true))),
+        )));
+
+        self.wrap_with_condition(predicate_match)
+    }
+
+    /// Rewrite every match to the text returned by a callback, which is
+    /// given the match, for transformations a snippet can't express.
+    pub fn rewrite_callback(mut self, cb: Box<CallbackRewriteFn>) -> Self {
+        let index = self
+            .built_ins
+            .add_callback(Box::new(move |args, context, state, logs| {
+                let args = MarzanoResolvedPattern::from_patterns(args, state, context, logs)?;
+                let first = args[0].as_ref().unwrap();
+
+                let replacement = cb(first, context, state, logs)?;
+
+                Ok(MarzanoResolvedPattern::from_string(replacement))
+            }));
+
+        let match_var = self.match_variable();
+        let rewrite = Predicate::Rewrite(Box::new(Rewrite::new(
+            Pattern::Variable(match_var),
+            DynamicPattern::CallBuiltIn(CallBuiltIn::new(
+                index,
+                vec![Some(Pattern::Variable(match_var))],
+            )),
+            None,
+        )));
+
+        self.wrap_with_condition(rewrite)
+    }
+
+    /// The variable bound to the match, as seen from the top of the pattern.
+    fn match_variable(&mut self) -> Variable {
         let compilation = CompilationContext {
             file: DEFAULT_FILE_NAME,
             built_ins: &self.built_ins,
@@ -273,6 +373,15 @@ impl PatternBuilder {
             predicate_definition_info: &self.predicate_definition_indices,
             function_definition_info: &self.function_definition_indices,
             foreign_function_definition_info: &self.foreign_function_indices,
+            options: &self.options,
+            snippet_timings: self
+                .options
+                .profile_snippets
+                .then_some(&self.snippet_timings),
+            diagnostics: self
+                .options
+                .recover_snippet_errors
+                .then_some(&self.diagnostics),
         };
 
         let mut node_context = NodeCompilationContext {
@@ -285,18 +394,7 @@ impl PatternBuilder {
         };
 
         // We reuse the match name here, not sure that's right.
-        let match_var = variable_from_name(MATCH_VAR, &mut node_context).unwrap();
-        let predicate_match = Predicate::Match(Box::new(Match::new(
-            Container::FunctionCall(Box::new(CallBuiltIn::new(
-                index,
-                vec![Some(grit_pattern_matcher::pattern::Pattern::Variable(
-                    match_var,
-                ))],
-            ))),
-            Some(Pattern::BooleanConstant(BooleanConstant::new(true))),
-        )));
-
-        self.wrap_with_condition(predicate_match)
+        variable_from_name(MATCH_VAR, &mut node_context).unwrap()
     }
 
     /// Add a new built in
@@ -339,6 +437,8 @@ impl PatternBuilder {
         let result = CompilationResult {
             compilation_warnings: target_builder.compilation_warnings,
             problem,
+            snippet_timings: target_builder.snippet_timings.into_inner(),
+            diagnostics: target_builder.diagnostics.into_inner(),
         };
         Ok(result)
     }
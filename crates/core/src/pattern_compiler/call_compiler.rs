@@ -1,11 +1,14 @@
 use super::{
-    ast_node_compiler::AstNodeCompiler, compiler::NodeCompilationContext,
-    node_compiler::NodeCompiler, pattern_compiler::PatternCompiler,
+    ast_node_compiler::AstNodeCompiler,
+    compiler::NodeCompilationContext,
+    log_compiler::{debug_bindings_log, DEBUG_BINDINGS},
+    node_compiler::NodeCompiler,
+    pattern_compiler::PatternCompiler,
 };
 use crate::{built_in_functions::BuiltIns, problem::MarzanoQueryContext};
 use anyhow::{anyhow, bail, Result};
 use grit_pattern_matcher::pattern::{
-    Call, CallForeignFunction, CallFunction, FilePattern, Pattern, PrCall,
+    Call, CallForeignFunction, CallFunction, FilePattern, Pattern, PrCall, RegexLike, RegexPattern,
 };
 use grit_util::{AstNode, ByteRange, Language};
 use itertools::Itertools;
@@ -28,6 +31,10 @@ impl NodeCompiler for CallCompiler {
             .ok_or_else(|| anyhow!("missing name of nodeLike"))?;
         let kind = sort.text()?;
         let kind = kind.trim();
+        if kind == DEBUG_BINDINGS {
+            let log = debug_bindings_log(node, context)?;
+            return Ok(log.map_or(Pattern::Top, |log| Pattern::Log(Box::new(log))));
+        }
         let lang = context.compilation.lang;
         let sort = lang.get_ts_language().id_for_node_kind(kind, true);
         let expected_params = if let Some(built_in) = context
@@ -82,6 +89,17 @@ impl NodeCompiler for CallCompiler {
                 .map(|p| p.1)
                 .unwrap_or(Pattern::Top);
             Ok(Pattern::File(Box::new(FilePattern::new(name, body))))
+        } else if kind == "glob" {
+            if args.keys().any(|arg| arg != "$pattern") {
+                bail!("glob can only have $pattern as a named arg");
+            }
+            let Some(Pattern::StringConstant(glob)) = args.remove("$pattern") else {
+                bail!("glob expects a string literal as its $pattern");
+            };
+            Ok(Pattern::Regex(Box::new(RegexPattern::new(
+                RegexLike::Regex(glob_regex(&glob.text)?),
+                Vec::new(),
+            ))))
         } else if let Some(index) = context
             .compilation
             .built_ins
@@ -173,6 +191,76 @@ impl NodeCompiler for PrCallCompiler {
     }
 }
 
+/// Translates a glob over file paths, such as `**/*.test.ts`, into a regex
+/// matching the whole path.
+///
+/// `*` and `?` stay within a path segment, `**` crosses segments, `[...]`
+/// matches a class of characters and `{a,b}` either alternative. A glob which
+/// doesn't start with `/` may match from the start of any segment, so
+/// `src/*.ts` matches both `src/a.ts` and `/repo/src/a.ts`.
+fn glob_regex(glob: &str) -> Result<String> {
+    let mut translated = String::new();
+    let rest = match glob.strip_prefix('/') {
+        Some(rest) => {
+            translated.push('/');
+            rest
+        }
+        None => {
+            translated.push_str("(?:.*/)?");
+            glob
+        }
+    };
+    let mut alternatives = 0;
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    translated.push_str("(?:.*/)?");
+                } else {
+                    translated.push_str(".*");
+                }
+            }
+            '*' => translated.push_str("[^/]*"),
+            '?' => translated.push_str("[^/]"),
+            '[' => {
+                translated.push('[');
+                if chars.next_if(|c| *c == '!' || *c == '^').is_some() {
+                    translated.push('^');
+                }
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c @ ('\\' | '[' | '&' | '~')) => {
+                            translated.push('\\');
+                            translated.push(c);
+                        }
+                        Some(c) => translated.push(c),
+                        None => bail!("unclosed character class in glob `{glob}`"),
+                    }
+                }
+                translated.push(']');
+            }
+            '{' => {
+                alternatives += 1;
+                translated.push_str("(?:");
+            }
+            ',' if alternatives > 0 => translated.push('|'),
+            '}' if alternatives > 0 => {
+                alternatives -= 1;
+                translated.push(')');
+            }
+            c => translated.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    if alternatives > 0 {
+        bail!("unclosed alternatives in glob `{glob}`");
+    }
+    Ok(translated)
+}
+
 fn collect_params(parameters: &[(String, ByteRange)]) -> Vec<String> {
     parameters.iter().map(|p| p.0.clone()).collect()
 }
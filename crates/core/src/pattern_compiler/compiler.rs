@@ -21,18 +21,22 @@ use grit_pattern_matcher::{
     },
 };
 use grit_util::{
-    traverse, AnalysisLogs, Ast, AstNode, ByteRange, FileRange, Order, Range, VariableMatch,
+    traverse, AnalysisLogs, Ast, AstNode, ByteRange, FileRange, Order, Position, Range,
+    VariableMatch,
 };
 use itertools::Itertools;
 use marzano_language::{
     self, grit_parser::MarzanoGritParser, language::Tree, target_language::TargetLanguage,
 };
-use marzano_util::node_with_source::NodeWithSource;
+use marzano_util::{node_with_source::NodeWithSource, runtime::CancellationToken};
+use serde::{Deserialize, Serialize};
 use std::{
+    cell::RefCell,
     collections::{BTreeMap, BTreeSet},
     ffi::OsStr,
     path::Path,
     str::Utf8Error,
+    time::Duration,
     vec,
 };
 
@@ -47,6 +51,12 @@ pub(crate) struct CompilationContext<'a> {
     pub predicate_definition_info: &'a BTreeMap<String, DefinitionInfo>,
     pub function_definition_info: &'a BTreeMap<String, DefinitionInfo>,
     pub foreign_function_definition_info: &'a BTreeMap<String, DefinitionInfo>,
+    pub options: &'a CompilationOptions,
+    /// Where snippet compile timings are recorded, if profiling is enabled.
+    pub snippet_timings: Option<&'a RefCell<Vec<SnippetCompileTiming>>>,
+    /// Where errors compilation recovers from are collected, if
+    /// `CompilationOptions::recover_snippet_errors` is enabled.
+    pub diagnostics: Option<&'a RefCell<Vec<CompilationDiagnostic>>>,
 }
 
 pub(crate) struct NodeCompilationContext<'a> {
@@ -75,6 +85,21 @@ pub(crate) struct NodeCompilationContext<'a> {
     pub logs: &'a mut AnalysisLogs,
 }
 
+impl NodeCompilationContext<'_> {
+    /// Pushes `error` into the diagnostics sink so compilation can carry on
+    /// with a partial pattern, or returns it if errors aren't being collected.
+    pub(crate) fn recover(&self, error: anyhow::Error, range: Range) -> Result<()> {
+        let Some(diagnostics) = self.compilation.diagnostics else {
+            return Err(error);
+        };
+        diagnostics.borrow_mut().push(CompilationDiagnostic {
+            message: error.to_string(),
+            range: self.compilation.options.source_offset.range(range),
+        });
+        Ok(())
+    }
+}
+
 // this code looks wrong. Todo test to see if we correctly find duplicate
 // parameter names, if not fix.
 fn get_duplicates(list: &[(String, ByteRange)]) -> Vec<&String> {
@@ -570,6 +595,151 @@ fn find_definition_if_exists(
 pub struct CompilationResult {
     pub compilation_warnings: AnalysisLogs,
     pub problem: Problem,
+    /// Empty unless `CompilationOptions::profile_snippets` is enabled.
+    pub snippet_timings: Vec<SnippetCompileTiming>,
+    /// Errors compilation recovered from, in the order they were found.
+    /// Empty unless `CompilationOptions::recover_snippet_errors` is enabled.
+    pub diagnostics: Vec<CompilationDiagnostic>,
+}
+
+/// An error in a snippet which compilation recovered from, leaving the
+/// pattern partially compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilationDiagnostic {
+    pub message: String,
+    pub range: Range,
+}
+
+/// How long it took to compile a single snippet.
+#[derive(Debug, Clone)]
+pub struct SnippetCompileTiming {
+    pub source: String,
+    pub range: Range,
+    pub duration: Duration,
+    /// Number of parse contexts the snippet, along with the sources it was
+    /// rewritten into before parsing, was tried in.
+    pub parse_contexts: usize,
+}
+
+/// Determines how snippets are reported when they cannot be parsed into any
+/// node, and are therefore only matched textually through the dynamic
+/// snippet fallback.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SnippetFallbackLint {
+    /// Silently fall back to matching the snippet as text.
+    #[default]
+    Allow,
+    /// Emit a compilation warning with the range of the snippet.
+    Warn,
+    /// Fail compilation with the range of the snippet.
+    Deny,
+}
+
+/// Which binding metavariables prefer when several amounts of code would let
+/// a snippet match, such as `$a` in the doc comment `/** $a-$b */` against
+/// `/** x-y-z */`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadPreference {
+    /// Bind as much as possible, so `$a` is `x-y`.
+    Longest,
+    /// Bind as little as possible, so `$a` is `x`.
+    Shortest,
+}
+
+/// Where the pattern source starts within the document it was taken from,
+/// such as a code fence in a Markdown file.
+///
+/// Diagnostics reported while compiling snippets are translated by this
+/// offset, so they point into the host document rather than the pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceOffset {
+    pub byte: u32,
+    pub position: Position,
+}
+
+impl SourceOffset {
+    pub fn new(byte: u32, position: Position) -> Self {
+        Self { byte, position }
+    }
+
+    /// Translates a position in the pattern source into the host document.
+    ///
+    /// Only positions on the first line are shifted by the column offset,
+    /// since later lines start at the beginning of a host line.
+    pub fn position(&self, position: Position) -> Position {
+        if position.line == 1 {
+            Position::new(
+                self.position.line,
+                self.position.column + position.column - 1,
+            )
+        } else {
+            Position::new(self.position.line + position.line - 1, position.column)
+        }
+    }
+
+    /// Translates a range in the pattern source into the host document.
+    pub fn range(&self, range: Range) -> Range {
+        Range::new(
+            self.position(range.start),
+            self.position(range.end),
+            range.start_byte + self.byte,
+            range.end_byte + self.byte,
+        )
+    }
+}
+
+impl Default for SourceOffset {
+    fn default() -> Self {
+        Self::new(0, Position::first())
+    }
+}
+
+/// Options that affect how a pattern is compiled.
+#[derive(Debug, Clone, Default)]
+pub struct CompilationOptions {
+    pub snippet_fallback: SnippetFallbackLint,
+    pub source_offset: SourceOffset,
+    /// Record how long each snippet takes to compile, see
+    /// `CompilationResult::snippet_timings`.
+    pub profile_snippets: bool,
+    /// Keep at most this many alternative parses of each snippet, warning
+    /// when any are dropped. Unlimited by default.
+    pub max_snippet_candidates: Option<usize>,
+    /// Collapse runs of whitespace in snippets, outside of string literals,
+    /// before parsing them. Indentation at the start of a line is kept.
+    pub collapse_snippet_whitespace: bool,
+    /// Reject snippets with more distinct metavariables than this, such as
+    /// when compiling untrusted patterns. Unlimited by default.
+    pub max_snippet_metavariables: Option<usize>,
+    /// Match identifiers in snippets regardless of case, as in code search.
+    /// Rejected for patterns which rewrite code, since a rewrite could then
+    /// apply to identifiers it wasn't written for.
+    pub case_insensitive_identifiers: bool,
+    /// How much named spreads, such as `$...args`, and metavariables matched
+    /// textually bind when more than one amount would match. By default
+    /// spreads and metavariables within a literal bind the longest run, while
+    /// doc comments bind the shortest. A spread marked lazy, as in `$...args?`,
+    /// always binds the shortest run.
+    pub spread_preference: Option<SpreadPreference>,
+    /// Collect errors in snippets which compilation can recover from, such as
+    /// an unknown language or bracketed metavariables on the left-hand side,
+    /// instead of failing on the first one. See
+    /// `CompilationResult::diagnostics`.
+    pub recover_snippet_errors: bool,
+    /// Compile `debug_bindings()` calls into logs of every metavariable bound
+    /// where they are reached, reported as `MatchResult::AnalysisLog`s. The
+    /// calls do nothing otherwise, so they can be left in a pattern.
+    pub debug_bindings: bool,
+    /// Stops compilation before parsing any further snippets once cancelled.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl CompilationOptions {
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
 }
 
 #[cfg_attr(
@@ -587,11 +757,43 @@ pub fn src_to_problem_libs(
     file_ranges: Option<Vec<FileRange>>,
     custom_built_ins: Option<BuiltIns>,
     injected_limit: Option<usize>,
+) -> Result<CompilationResult> {
+    src_to_problem_libs_with_options(
+        src,
+        libs,
+        default_lang,
+        name,
+        file_ranges,
+        custom_built_ins,
+        injected_limit,
+        CompilationOptions::default(),
+    )
+}
+
+/// Same as [`src_to_problem_libs`], but with explicit compilation options.
+#[allow(clippy::too_many_arguments)]
+pub fn src_to_problem_libs_with_options(
+    src: String,
+    libs: &BTreeMap<String, String>,
+    default_lang: TargetLanguage,
+    name: Option<String>,
+    file_ranges: Option<Vec<FileRange>>,
+    custom_built_ins: Option<BuiltIns>,
+    injected_limit: Option<usize>,
+    options: CompilationOptions,
 ) -> Result<CompilationResult> {
     let mut parser = MarzanoGritParser::new()?;
     let src_tree = parser.parse_file(&src, Some(Path::new(DEFAULT_FILE_NAME)))?;
     let lang = TargetLanguage::from_tree(&src_tree).unwrap_or(default_lang);
-    let builder = PatternBuilder::start(src, libs, lang, name, &mut parser, custom_built_ins)?;
+    let builder = PatternBuilder::start_with_options(
+        src,
+        libs,
+        lang,
+        name,
+        &mut parser,
+        custom_built_ins,
+        options,
+    )?;
     builder.compile(file_ranges, injected_limit, true)
 }
 
@@ -606,7 +808,7 @@ pub fn src_to_problem(src: String, default_lang: TargetLanguage) -> Result<Probl
     Ok(problem)
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct VariableLocations {
     pub(crate) locations: Vec<Vec<VariableSourceLocations>>,
 }
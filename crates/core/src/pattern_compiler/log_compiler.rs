@@ -3,7 +3,7 @@ use super::{
     pattern_compiler::PatternCompiler, variable_compiler::VariableCompiler,
 };
 use crate::problem::MarzanoQueryContext;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use grit_pattern_matcher::pattern::{Log, VariableInfo};
 use grit_util::AstNode;
 use marzano_util::node_with_source::NodeWithSource;
@@ -36,3 +36,36 @@ impl NodeCompiler for LogCompiler {
         Ok(Log::new(variable, message))
     }
 }
+
+/// The name of the call which logs every bound metavariable, see
+/// `CompilationOptions::debug_bindings`.
+pub(crate) const DEBUG_BINDINGS: &str = "debug_bindings";
+
+/// Compiles a `debug_bindings()` call, as a pattern or a predicate, into a
+/// log of the bindings where it is reached, or into nothing unless
+/// `CompilationOptions::debug_bindings` is set.
+pub(crate) fn debug_bindings_log(
+    node: &NodeWithSource,
+    context: &NodeCompilationContext,
+) -> Result<Option<Log<MarzanoQueryContext>>> {
+    if node
+        .named_children_by_field_name("named_args")
+        .next()
+        .is_some()
+    {
+        bail!("{DEBUG_BINDINGS} does not take any arguments");
+    }
+    Ok(context
+        .compilation
+        .options
+        .debug_bindings
+        .then(Log::bindings))
+}
+
+/// Whether `node` calls `name`, as in `name()`.
+pub(crate) fn is_call_to(node: &NodeWithSource, name: &str) -> Result<bool> {
+    let Some(callee) = node.child_by_field_name("name") else {
+        return Ok(false);
+    };
+    Ok(callee.text()?.trim() == name)
+}
@@ -55,6 +55,7 @@ pub(crate) mod map_compiler;
 pub(crate) mod match_compiler;
 pub(crate) mod maybe_compiler;
 pub(crate) mod modulo_compiler;
+mod multi_language_builder;
 pub(crate) mod multiply_compiler;
 mod node_compiler;
 pub(crate) mod not_compiler;
@@ -70,6 +71,7 @@ pub(crate) mod regex_compiler;
 pub(crate) mod rewrite_compiler;
 pub(crate) mod sequential_compiler;
 pub(crate) mod snippet_compiler;
+mod snippet_preprocessors;
 pub(crate) mod some_compiler;
 pub(crate) mod step_compiler;
 pub(crate) mod subtract_compiler;
@@ -78,5 +80,12 @@ pub(crate) mod where_compiler;
 pub(crate) mod within_compiler;
 
 pub use builder::PatternBuilder;
-pub use compiler::{src_to_problem_libs, CompilationResult};
+pub use compiler::{
+    src_to_problem_libs, src_to_problem_libs_with_options, CompilationDiagnostic,
+    CompilationOptions, CompilationResult, SnippetCompileTiming, SnippetFallbackLint, SourceOffset,
+    SpreadPreference,
+};
+pub use multi_language_builder::{
+    LazyMultiLangSnippet, MultiLanguageBuilder, MultiLanguageProblem,
+};
 pub(crate) use node_compiler::NodeCompiler;
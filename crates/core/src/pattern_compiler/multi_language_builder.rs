@@ -0,0 +1,173 @@
+use super::compiler::{
+    src_to_problem_libs, src_to_problem_libs_with_options, CompilationOptions, SnippetFallbackLint,
+};
+use crate::{
+    api::MatchResult,
+    problem::{MarzanoQueryContext, Problem},
+};
+use anyhow::{bail, Context, Result};
+use grit_pattern_matcher::pattern::Pattern;
+use grit_util::{AnalysisLogs, Language};
+use marzano_language::target_language::TargetLanguage;
+use marzano_util::{rich_path::RichFile, runtime::ExecutionContext};
+use std::{collections::BTreeMap, path::Path};
+
+/// Compiles a snippet per language into a single logical pattern, such as a
+/// null check written `$x == null` in JavaScript and `$x is None` in Python.
+///
+/// Each snippet is compiled on its own, as if it were the whole pattern.
+#[derive(Debug, Default)]
+pub struct MultiLanguageBuilder {
+    snippets: Vec<(TargetLanguage, String)>,
+}
+
+impl MultiLanguageBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the snippet matching the construct in `lang`, written as it would
+    /// be between backticks.
+    pub fn snippet(mut self, lang: TargetLanguage, snippet: impl Into<String>) -> Self {
+        self.snippets.push((lang, snippet.into()));
+        self
+    }
+
+    pub fn compile(self) -> Result<MultiLanguageProblem> {
+        if self.snippets.is_empty() {
+            bail!("a multi-language pattern needs at least one snippet");
+        }
+        let libs = BTreeMap::new();
+        let problems = self
+            .snippets
+            .into_iter()
+            .map(|(lang, snippet)| {
+                let name = lang.language_name();
+                src_to_problem_libs(format!("`{snippet}`"), &libs, lang, None, None, None, None)
+                    .map(|result| result.problem)
+                    .with_context(|| format!("failed to compile the {name} snippet `{snippet}`"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(MultiLanguageProblem { problems })
+    }
+}
+
+/// The problems compiled by a [`MultiLanguageBuilder`], one per language.
+#[derive(Debug)]
+pub struct MultiLanguageProblem {
+    problems: Vec<Problem>,
+}
+
+impl MultiLanguageProblem {
+    /// The problem matching files at `path`, from the first snippet whose
+    /// language handles its extension.
+    pub fn problem_for(&self, path: &Path) -> Option<&Problem> {
+        let extension = path.extension()?.to_str()?;
+        self.problems
+            .iter()
+            .find(|problem| problem.language.match_extension(extension))
+    }
+
+    /// Executes the problem for the language of `file`, returning no results
+    /// if none of the snippets target it.
+    pub fn execute_file(&self, file: &RichFile, context: &ExecutionContext) -> Vec<MatchResult> {
+        match self.problem_for(Path::new(&file.path)) {
+            Some(problem) => problem.execute_file(file, context),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// A single snippet, such as `console.log($x)`, compiled for each language
+/// the first time a file of that language is matched, for applying the same
+/// snippet across the files of a mixed-language repository.
+///
+/// Snippets are compiled as if they were the whole pattern, and must parse as
+/// the language. Languages the snippet doesn't compile for are recorded as
+/// warnings and skipped, rather than failing the whole scan.
+#[derive(Debug)]
+pub struct LazyMultiLangSnippet {
+    snippet: String,
+    /// The problem compiled for each language, by language name, or `None`
+    /// if the snippet doesn't compile for it.
+    problems: BTreeMap<&'static str, Option<Problem>>,
+    warnings: AnalysisLogs,
+}
+
+impl LazyMultiLangSnippet {
+    /// Takes the snippet as it would be written between backticks.
+    pub fn new(snippet: impl Into<String>) -> Self {
+        Self {
+            snippet: snippet.into(),
+            problems: BTreeMap::new(),
+            warnings: vec![].into(),
+        }
+    }
+
+    /// The problem matching the snippet in `lang`, compiling it on first use.
+    pub fn problem(&mut self, lang: &TargetLanguage) -> Option<&Problem> {
+        let name = lang.language_name();
+        if !self.problems.contains_key(name) {
+            let problem = match self.compile(lang) {
+                Ok(problem) => Some(problem),
+                Err(error) => {
+                    self.warnings.add_warning(
+                        None,
+                        format!(
+                            "skipping {name} files, the snippet `{}` does not compile for them: {error:#}",
+                            self.snippet
+                        ),
+                    );
+                    None
+                }
+            };
+            self.problems.insert(name, problem);
+        }
+        self.problems.get(name)?.as_ref()
+    }
+
+    /// The pattern of the snippet in `lang`, see [`LazyMultiLangSnippet::problem`].
+    pub fn pattern(&mut self, lang: &TargetLanguage) -> Option<&Pattern<MarzanoQueryContext>> {
+        self.problem(lang).map(|problem| &problem.pattern)
+    }
+
+    /// Executes the snippet for the language of `file`, guessed from its
+    /// extension, returning no results if it doesn't compile for it.
+    pub fn execute_file(
+        &mut self,
+        file: &RichFile,
+        context: &ExecutionContext,
+    ) -> Vec<MatchResult> {
+        let lang = Path::new(&file.path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(TargetLanguage::from_extension);
+        match lang.as_ref().and_then(|lang| self.problem(lang)) {
+            Some(problem) => problem.execute_file(file, context),
+            None => Vec::new(),
+        }
+    }
+
+    /// Why the snippet was skipped for the languages it doesn't compile for.
+    pub fn warnings(&self) -> &AnalysisLogs {
+        &self.warnings
+    }
+
+    fn compile(&self, lang: &TargetLanguage) -> Result<Problem> {
+        let options = CompilationOptions {
+            snippet_fallback: SnippetFallbackLint::Deny,
+            ..Default::default()
+        };
+        src_to_problem_libs_with_options(
+            format!("`{}`", self.snippet),
+            &BTreeMap::new(),
+            lang.clone(),
+            None,
+            None,
+            None,
+            None,
+            options,
+        )
+        .map(|result| result.problem)
+    }
+}
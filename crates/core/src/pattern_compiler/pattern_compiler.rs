@@ -10,7 +10,7 @@ use super::{
     before_compiler::BeforeCompiler,
     bubble_compiler::BubbleCompiler,
     call_compiler::CallCompiler,
-    compiler::NodeCompilationContext,
+    compiler::{NodeCompilationContext, SpreadPreference},
     constant_compiler::{
         BooleanConstantCompiler, FloatConstantCompiler, IntConstantCompiler, StringConstantCompiler,
     },
@@ -36,6 +36,7 @@ use super::{
     rewrite_compiler::RewriteCompiler,
     sequential_compiler::SequentialCompiler,
     snippet_compiler::CodeSnippetCompiler,
+    snippet_preprocessors::is_named_spread_placeholder,
     some_compiler::SomeCompiler,
     subtract_compiler::SubtractCompiler,
     variable_compiler::VariableCompiler,
@@ -44,19 +45,22 @@ use super::{
 };
 use crate::problem::MarzanoQueryContext;
 use crate::{
-    ast_node::{ASTNode, AstLeafNode},
+    ast_node::{flatten_operator_chain, ungroup_list_element, ASTNode, AstLeafNode},
     variables::register_variable,
 };
 use anyhow::{anyhow, bail, Result};
 use grit_pattern_matcher::{
     context::QueryContext,
     pattern::{
-        is_reserved_metavariable, DynamicPattern, DynamicSnippet, DynamicSnippetPart, List,
-        Pattern, RegexLike, RegexPattern, Variable,
+        is_reserved_metavariable, And, Contains, DynamicPattern, DynamicSnippet,
+        DynamicSnippetPart, List, Not, Or, Pattern, RegexLike, RegexPattern, Variable,
     },
 };
 use grit_util::{traverse, AstCursor, AstNode, ByteRange, GritMetaValue, Language, Order};
-use marzano_language::language::{Field, MarzanoLanguage, NodeTypes};
+use marzano_language::{
+    language::{Field, MarzanoLanguage, NodeTypes},
+    target_language::TargetLanguage,
+};
 use marzano_util::node_with_source::NodeWithSource;
 use regex::Match as RegexMatch;
 use std::collections::HashMap;
@@ -98,6 +102,86 @@ impl PatternCompiler {
             if let Some(metavariable) = metavariable {
                 return Ok(metavariable);
             }
+            if !is_rhs
+                && context.compilation.lang.is_macro_arguments(&node)
+                && context
+                    .compilation
+                    .lang
+                    .replaced_metavariable_regex()
+                    .is_match(&node.text()?)
+            {
+                let regex = macro_arguments_regex(&node, context_range, range_map, context)?;
+                if let Some(regex) = regex {
+                    return Ok(Pattern::Regex(Box::new(regex)));
+                }
+            }
+            if !is_rhs && context.compilation.lang.is_union_type_sort(sort) {
+                let regex = union_members_regex(&node, context_range, range_map, context)?;
+                if let Some(regex) = regex {
+                    return Ok(Pattern::Regex(Box::new(regex)));
+                }
+            }
+            if !is_rhs && context.compilation.lang.is_type_parameters_sort(sort) {
+                let regex = type_parameters_regex(&node, context_range, range_map, context)?;
+                if let Some((regex, only_metavariables)) = regex {
+                    let regex = Pattern::Regex(Box::new(regex));
+                    if !only_metavariables {
+                        return Ok(regex);
+                    }
+                    let absent = Pattern::Dynamic(DynamicPattern::Snippet(DynamicSnippet {
+                        parts: vec![DynamicSnippetPart::String(String::new())],
+                    }));
+                    return Ok(Pattern::Or(Box::new(Or::new(vec![regex, absent]))));
+                }
+            }
+            if !is_rhs && context.compilation.lang.is_switch_body_sort(sort) {
+                // the cases aren't held in a field.
+                return unfielded_children_pattern(&node, context, |case, context| {
+                    node_to_astnode(case, context_range, range_map, context, is_rhs)
+                });
+            }
+            if let Some((assignment_sort, name_field)) = context
+                .compilation
+                .lang
+                .enum_member_assignment(sort)
+                .filter(|_| !is_rhs)
+                .filter(|_| {
+                    !node
+                        .named_children()
+                        .any(|member| context.compilation.lang.is_metavariable_node(&member))
+                })
+            {
+                // members with and without a value aren't held in the same
+                // field, and a member without a value also matches one
+                // assigning any value.
+                return unfielded_children_pattern(&node, context, |member, context| {
+                    let is_assignment = member.node.kind_id() == assignment_sort;
+                    let member =
+                        node_to_astnode(member, context_range, range_map, context, is_rhs)?;
+                    if is_assignment {
+                        return Ok(member);
+                    }
+                    let assignment =
+                        ASTNode::new(assignment_sort, vec![(name_field, false, member.clone())]);
+                    Ok(Pattern::Or(Box::new(Or::new(vec![
+                        member,
+                        Pattern::AstNode(Box::new(assignment)),
+                    ]))))
+                });
+            }
+            if let Some((operator, operands)) = spread_operator_chain(&node, context, is_rhs) {
+                let operands = operands
+                    .into_iter()
+                    .map(|operand| {
+                        node_to_astnode(operand, context_range, range_map, context, is_rhs)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                return Ok(Pattern::AstNode(Box::new(ASTNode::operator_chain(
+                    sort,
+                    operator.to_string(),
+                    Pattern::List(Box::new(List::new(operands))),
+                ))));
+            }
             if node_types[sort as usize].is_empty() {
                 let content = node.text()?;
                 if (node.node.named_child_count() == 0)
@@ -113,70 +197,161 @@ impl PatternCompiler {
                         return Ok(Pattern::Regex(Box::new(regex)));
                     }
                 }
-                return Ok(Pattern::AstLeafNode(AstLeafNode::new(
-                    sort,
-                    &content,
-                    context.compilation.lang,
-                )?));
+                let leaf = AstLeafNode::new(sort, &content, context.compilation.lang)?;
+                let ignores_case = !is_rhs
+                    && context.compilation.options.case_insensitive_identifiers
+                    && node.node.kind().contains("identifier");
+                return Ok(Pattern::AstLeafNode(if ignores_case {
+                    leaf.ignoring_case()
+                } else {
+                    leaf
+                }));
             }
             let fields: &Vec<Field> = &node_types[sort as usize];
-            let args =
-                fields
-                    .iter()
-                    .filter(|field| {
-                        let child_with_source = node
-                            .node
-                            .child_by_field_id(field.id())
-                            .map(|n| NodeWithSource::new(n, node.source));
-                        // Then check if it's an empty, optional field
-                        if context.compilation.lang.is_disregarded_snippet_field(
-                            sort,
-                            field.id(),
-                            &child_with_source,
-                        ) {
-                            return false;
-                        }
-                        // Otherwise compile it
-                        true
-                    })
-                    .map(|field| {
-                        let field_id = field.id();
-                        let mut nodes_list = node
-                            .named_children_by_field_id(field_id)
-                            .map(|n| node_to_astnode(n, context_range, range_map, context, is_rhs))
-                            .collect::<Result<Vec<Pattern<MarzanoQueryContext>>>>()?;
-                        if !field.multiple() {
+            let args = fields
+                .iter()
+                .filter(|field| {
+                    let child_with_source = node
+                        .node
+                        .child_by_field_id(field.id())
+                        .map(|n| NodeWithSource::new(n, node.source));
+                    // Then check if it's an empty, optional field
+                    if context.compilation.lang.is_disregarded_snippet_field(
+                        sort,
+                        field.id(),
+                        &child_with_source,
+                    ) {
+                        return false;
+                    }
+                    // Otherwise compile it
+                    true
+                })
+                .map(|field| {
+                    let field_id = field.id();
+                    let mut nodes_list = node
+                        .named_children_by_field_id(field_id)
+                        .map(|n| node_to_astnode(n, context_range, range_map, context, is_rhs))
+                        .collect::<Result<Vec<Pattern<MarzanoQueryContext>>>>()?;
+                    if !field.multiple() {
+                        let pattern =
+                            single_field_pattern(nodes_list.pop(), sort, field_id, context, is_rhs);
+                        return Ok((field_id, false, pattern));
+                    }
+                    if nodes_list.len() == 1
+                        && matches!(
+                            nodes_list.first(),
+                            Some(Pattern::Variable(_)) | Some(Pattern::Underscore)
+                        )
+                    {
+                        if let Some(slice_sort) = context
+                            .compilation
+                            .lang
+                            .subscript_slice_sort(sort)
+                            .filter(|_| !is_rhs)
+                        {
+                            // a lone index, which leaves slices to snippets
+                            // spelling them out.
+                            let slice = ASTNode::new(slice_sort, Vec::new());
+                            let index = Pattern::And(Box::new(And::new(vec![
+                                Pattern::Not(Box::new(Not::new(Pattern::AstNode(Box::new(slice))))),
+                                nodes_list.pop().unwrap(),
+                            ])));
                             return Ok((
                                 field_id,
-                                false,
-                                nodes_list.pop().unwrap_or(Pattern::Dynamic(
-                                    DynamicPattern::Snippet(DynamicSnippet {
-                                        parts: vec![DynamicSnippetPart::String("".to_string())],
-                                    }),
-                                )),
+                                true,
+                                Pattern::List(Box::new(List::new(vec![index]))),
                             ));
                         }
-                        if nodes_list.len() == 1
-                            && matches!(
-                                nodes_list.first(),
-                                Some(Pattern::Variable(_)) | Some(Pattern::Underscore)
-                            )
-                        {
-                            return Ok((field_id, true, nodes_list.pop().unwrap()));
-                        }
-                        Ok((
-                            field_id,
-                            true,
-                            Pattern::List(Box::new(List::new(nodes_list))),
-                        ))
-                    })
-                    .collect::<Result<Vec<(u16, bool, Pattern<MarzanoQueryContext>)>>>()?;
-            Ok(Pattern::AstNode(Box::new(ASTNode { sort, args })))
+                        return Ok((field_id, true, nodes_list.pop().unwrap()));
+                    }
+                    nodes_list.iter_mut().for_each(ungroup_list_element);
+                    // an empty list is kept as is, so the explicitly empty
+                    // arguments of `foo()` only match calls without any.
+                    Ok((
+                        field_id,
+                        true,
+                        Pattern::List(Box::new(List::new(nodes_list))),
+                    ))
+                })
+                .collect::<Result<Vec<(u16, bool, Pattern<MarzanoQueryContext>)>>>()?;
+            let mut ast_node = ASTNode::new(sort, args);
+            if let Some(child) = context.compilation.lang.unfielded_child(&node) {
+                ast_node.unfielded_child = Some(node_to_astnode(
+                    child,
+                    context_range,
+                    range_map,
+                    context,
+                    is_rhs,
+                )?);
+            }
+            Ok(Pattern::AstNode(Box::new(ast_node)))
         }
         node_to_astnode(node, context_range, &range_map, context, is_rhs)
     }
 }
 
+/// Matches a node of the same sort as `node` whose children aren't held in a
+/// field, such as the cases of a switch, by looking for the pattern `child`
+/// compiles each child of `node` to among the children of the target,
+/// without descending into them.
+fn unfielded_children_pattern<'a>(
+    node: &NodeWithSource<'a>,
+    context: &mut NodeCompilationContext,
+    mut child: impl FnMut(
+        NodeWithSource<'a>,
+        &mut NodeCompilationContext,
+    ) -> Result<Pattern<MarzanoQueryContext>>,
+) -> Result<Pattern<MarzanoQueryContext>> {
+    let body = Pattern::AstNode(Box::new(ASTNode::new(node.node.kind_id(), Vec::new())));
+    let outside_body = Pattern::Not(Box::new(Not::new(body.clone())));
+    let mut patterns = vec![body];
+    for child_node in node.named_children() {
+        if context
+            .compilation
+            .lang
+            .is_comment_sort(child_node.node.kind_id())
+        {
+            continue;
+        }
+        let pattern = child(child_node, context)?;
+        patterns.push(Pattern::Contains(Box::new(Contains::new(
+            pattern,
+            Some(outside_body.clone()),
+        ))));
+    }
+    Ok(Pattern::And(Box::new(And::new(patterns))))
+}
+
+fn single_field_pattern(
+    pattern: Option<Pattern<MarzanoQueryContext>>,
+    sort: u16,
+    field_id: u16,
+    context: &NodeCompilationContext,
+    is_rhs: bool,
+) -> Pattern<MarzanoQueryContext> {
+    let empty = Pattern::Dynamic(DynamicPattern::Snippet(DynamicSnippet {
+        parts: vec![DynamicSnippetPart::String("".to_string())],
+    }));
+    match pattern {
+        // the field is optional, but was given in the snippet, so don't let a
+        // metavariable bind its absence.
+        Some(pattern)
+            if !is_rhs
+                && context
+                    .compilation
+                    .lang
+                    .is_required_snippet_field(sort, field_id) =>
+        {
+            Pattern::And(Box::new(And::new(vec![
+                Pattern::Not(Box::new(Not::new(empty))),
+                pattern,
+            ])))
+        }
+        Some(pattern) => pattern,
+        None => empty,
+    }
+}
+
 impl NodeCompiler for PatternCompiler {
     type TargetPattern = Pattern<MarzanoQueryContext>;
 
@@ -332,6 +507,25 @@ fn implicit_metavariable_regex<Q: QueryContext>(
     range_map: &HashMap<ByteRange, ByteRange>,
     context: &mut NodeCompilationContext,
 ) -> Result<Option<RegexPattern<Q>>> {
+    let captures = match context.compilation.options.spread_preference {
+        Some(SpreadPreference::Shortest) => ("(.*?)", ".*?"),
+        Some(SpreadPreference::Longest) | None => ("(.*)", ".*"),
+    };
+    let regex = text_metavariable_regex(node, captures, context_range, range_map, context)?;
+    Ok(regex.map(|(regex, variables)| RegexPattern::new(RegexLike::Regex(regex), variables)))
+}
+
+/// Builds a regex matching the text of `node`, in which each metavariable
+/// captures `capture_string` and `$_` matches `uncapture_string`.
+///
+/// Returns `None` if the text holds `$...`, which can't be matched textually.
+fn text_metavariable_regex(
+    node: &NodeWithSource,
+    (capture_string, uncapture_string): (&str, &str),
+    context_range: ByteRange,
+    range_map: &HashMap<ByteRange, ByteRange>,
+    context: &mut NodeCompilationContext,
+) -> Result<Option<(String, Vec<Variable>)>> {
     let range = node.range();
     let offset = range.start_byte;
     let mut last = if cfg!(target_arch = "wasm32") {
@@ -339,12 +533,20 @@ fn implicit_metavariable_regex<Q: QueryContext>(
     } else {
         offset
     };
+    let end = if cfg!(target_arch = "wasm32") {
+        char_index_to_byte_index(range.end_byte, node.source)
+    } else {
+        range.end_byte
+    };
     let mut regex_string = String::new();
     let mut variables: Vec<Variable> = vec![];
-    let capture_string = "(.*)";
-    let uncapture_string = ".*";
     let variable_regex = context.compilation.lang.replaced_metavariable_regex();
-    for m in variable_regex.find_iter(node.source) {
+    // Only the metavariables within the node belong to it, the source also
+    // holds its siblings, such as the other quasis of a template string.
+    for m in variable_regex
+        .find_iter(node.source)
+        .take_while(|m| m.end() as u32 <= end)
+    {
         if last > m.start() as u32 {
             continue;
         }
@@ -366,14 +568,400 @@ fn implicit_metavariable_regex<Q: QueryContext>(
             }
         }
     }
-    if last < range.end_byte {
-        regex_string.push_str(&regex::escape(
-            &node.source[last as usize..range.end_byte as usize],
-        ));
+    if last < end {
+        regex_string.push_str(&regex::escape(&node.source[last as usize..end as usize]));
+    }
+    Ok(Some((regex_string, variables)))
+}
+
+/// Brackets which group the elements of a list, so that the separators
+/// they hold don't split the element.
+const LIST_BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+const TYPE_LIST_BRACKETS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+
+/// Matches a single element of a list, which holds neither whitespace nor
+/// `separator` outside of `brackets`, up to three levels deep.
+///
+/// `atoms` matches the tokens which may hold brackets or the separator
+/// without opening a group, such as string literals, and `quotes` are the
+/// characters at their start, which don't match on their own.
+fn list_element_regex(
+    atoms: &str,
+    quotes: &str,
+    brackets: &[(char, char)],
+    separator: char,
+) -> String {
+    let delimiters = brackets
+        .iter()
+        .flat_map(|(open, close)| [*open, *close])
+        .chain(quotes.chars())
+        .map(|c| regex::escape(c.encode_utf8(&mut [0; 4])))
+        .collect::<String>();
+    let groups = |inner: &str| {
+        brackets
+            .iter()
+            .map(|(open, close)| {
+                format!(
+                    "{}{inner}*{}",
+                    regex::escape(open.encode_utf8(&mut [0; 4])),
+                    regex::escape(close.encode_utf8(&mut [0; 4]))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("|")
+    };
+    let mut nested = format!("(?:{atoms}|[^{delimiters}])");
+    for _ in 0..2 {
+        nested = format!("(?:{atoms}|[^{delimiters}]|{})", groups(&nested));
+    }
+    let separator = regex::escape(separator.encode_utf8(&mut [0; 4]));
+    let element = format!(
+        r"(?:{atoms}|[^\s{separator}{delimiters}]|{})",
+        groups(&nested)
+    );
+    format!(r"{element}(?:\s*{element})*")
+}
+
+/// The regex for one element of a list matched by `separated_list_regex`.
+enum ListElementRegex {
+    /// `$...`, matching any number of elements.
+    Dots,
+    Element(String),
+}
+
+/// The regex for an element of a list which is a lone metavariable, matching
+/// a whole `element` of the target.
+fn metavariable_list_element(
+    value: SnippetValues,
+    element: &str,
+    variables: &mut Vec<Variable>,
+) -> ListElementRegex {
+    match value {
+        SnippetValues::Dots => ListElementRegex::Dots,
+        SnippetValues::Underscore => ListElementRegex::Element(format!("(?:{element})")),
+        SnippetValues::Variable(variable) => {
+            variables.push(variable);
+            ListElementRegex::Element(format!("({element})"))
+        }
+    }
+}
+
+/// Joins the regexes of the elements of a list with `separator`, where
+/// `element` matches any single element of the target, for `$...`.
+fn separated_list_regex(elements: Vec<ListElementRegex>, element: &str, separator: &str) -> String {
+    let mut regex_string = String::new();
+    let mut after_element = false;
+    let mut leading_dots = false;
+    for pattern in elements {
+        let ListElementRegex::Element(pattern) = pattern else {
+            if after_element {
+                regex_string.push_str(&format!("(?:{separator}{element})*"));
+            } else {
+                leading_dots = true;
+            }
+            continue;
+        };
+        if after_element {
+            regex_string.push_str(separator);
+        } else if leading_dots {
+            regex_string.push_str(&format!("(?:{element}{separator})*"));
+        }
+        regex_string.push_str(&pattern);
+        after_element = true;
+    }
+    if leading_dots && !after_element {
+        regex_string.push_str(&format!("(?:{element}(?:{separator}{element})*)?"));
+    }
+    regex_string
+}
+
+/// Text of a single macro argument: tokens outside of any brackets, except
+/// commas, along with string and character literals, lifetimes and bracketed
+/// groups nested up to three levels deep.
+fn macro_argument_regex() -> String {
+    // lifetimes come first, so `'a` in `&'a T` isn't read as the start of a
+    // character literal, while `'a'` still is one since quotes don't match on
+    // their own.
+    list_element_regex(
+        r#"'[A-Za-z_]\w*|"(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)'"#,
+        "\"'",
+        &LIST_BRACKETS,
+        ',',
+    )
+}
+
+/// Compiles the arguments of a macro invocation, such as the token tree of
+/// `println!("$fmt", $arg)`, into a regex over the text of the arguments.
+///
+/// The arguments are split at top-level commas. An argument made of a single
+/// metavariable binds a whole argument of the target, `$_` matches one and
+/// `$...` any number of them. Other arguments must match token by token,
+/// except for the metavariables they contain, which bind the shortest text
+/// that lets the tokens match. Whitespace is ignored between tokens, but not
+/// within nested brackets.
+///
+/// Returns `None` if the arguments are a single metavariable, like
+/// `println!($args)`, which keeps binding all of them, or if they can't be
+/// matched this way, such as when they contain `$...` within an argument.
+/// Named spreads, like `$...args`, aren't supported within macro arguments.
+fn macro_arguments_regex<Q: QueryContext>(
+    node: &NodeWithSource,
+    context_range: ByteRange,
+    range_map: &HashMap<ByteRange, ByteRange>,
+    context: &mut NodeCompilationContext,
+) -> Result<Option<RegexPattern<Q>>> {
+    let children = node.children().collect::<Vec<_>>();
+    let [open, tokens @ .., close] = children.as_slice() else {
+        return Ok(None);
+    };
+    if let [token] = tokens {
+        if context.compilation.lang.is_metavariable(token) {
+            return Ok(None);
+        }
+    }
+    let mut arguments: Vec<Vec<&NodeWithSource>> = vec![vec![]];
+    for token in tokens {
+        if !token.node.is_named() && token.text()? == "," {
+            arguments.push(vec![]);
+        } else {
+            arguments.last_mut().unwrap().push(token);
+        }
+    }
+    // a trailing comma doesn't start another argument
+    if arguments.len() > 1 && arguments.last().is_some_and(Vec::is_empty) {
+        arguments.pop();
+    }
+
+    let argument = macro_argument_regex();
+    let mut variables = vec![];
+    let mut elements = vec![];
+    for tokens in arguments {
+        let element = match tokens.as_slice() {
+            [] => continue,
+            [token] if context.compilation.lang.is_metavariable(token) => {
+                let name = token.text()?;
+                let value =
+                    text_to_var(&name, token.byte_range(), context_range, range_map, context)?;
+                metavariable_list_element(value, &argument, &mut variables)
+            }
+            tokens => {
+                let mut pattern = String::new();
+                let mut previous: Option<&NodeWithSource> = None;
+                for token in tokens {
+                    if let Some(previous) = previous {
+                        let gap = &node.source
+                            [previous.node.end_byte() as usize..token.node.start_byte() as usize];
+                        pattern.push_str(if gap.is_empty() { r"\s*" } else { r"\s+" });
+                    }
+                    let Some((token_regex, token_variables)) = text_metavariable_regex(
+                        token,
+                        ("(.*?)", ".*?"),
+                        context_range,
+                        range_map,
+                        context,
+                    )?
+                    else {
+                        return Ok(None);
+                    };
+                    pattern.push_str(&token_regex);
+                    variables.extend(token_variables);
+                    previous = Some(token);
+                }
+                ListElementRegex::Element(pattern)
+            }
+        };
+        elements.push(element);
+    }
+    let regex_string = format!(
+        r"{}\s*{}(?:\s*,)?\s*{}",
+        regex::escape(&open.text()?),
+        separated_list_regex(elements, &argument, r"\s*,\s*"),
+        regex::escape(&close.text()?)
+    );
+    Ok(Some(RegexPattern::new(
+        RegexLike::Regex(regex_string),
+        variables,
+    )))
+}
+
+/// Matches a single member of a union of types, which may itself hold
+/// unions within brackets, such as `Array<A | B>`.
+fn union_member_regex() -> String {
+    list_element_regex(
+        r#""(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*'|=>"#,
+        "\"'",
+        &TYPE_LIST_BRACKETS,
+        '|',
+    )
+}
+
+/// Compiles a union of types, such as `$a | $b` in TypeScript, into a regex
+/// matching the union member by member, since the members aren't held in a
+/// field. Nested unions are flattened, the way `A | B | C` reads, and `$...`
+/// matches any number of members.
+fn union_members_regex<Q: QueryContext>(
+    node: &NodeWithSource,
+    context_range: ByteRange,
+    range_map: &HashMap<ByteRange, ByteRange>,
+    context: &mut NodeCompilationContext,
+) -> Result<Option<RegexPattern<Q>>> {
+    let mut members = Vec::new();
+    flatten_union_members(node.clone(), context.compilation.lang, &mut members);
+
+    let member = union_member_regex();
+    let mut variables = vec![];
+    let mut elements = vec![];
+    for node in members {
+        let element = if context.compilation.lang.is_metavariable(&node) {
+            let name = node.text()?;
+            let value = text_to_var(&name, node.byte_range(), context_range, range_map, context)?;
+            metavariable_list_element(value, &member, &mut variables)
+        } else {
+            let Some((member_regex, member_variables)) = text_metavariable_regex(
+                &node,
+                ("(.*?)", ".*?"),
+                context_range,
+                range_map,
+                context,
+            )?
+            else {
+                return Ok(None);
+            };
+            variables.extend(member_variables);
+            ListElementRegex::Element(member_regex)
+        };
+        elements.push(element);
+    }
+    if !elements
+        .iter()
+        .any(|element| matches!(element, ListElementRegex::Element(_)))
+    {
+        return Ok(None);
+    }
+    // a union spanning lines may start with a separator
+    let regex_string = format!(
+        r"(?:\|\s*)?{}",
+        separated_list_regex(elements, &member, r"\s*\|\s*")
+    );
+    Ok(Some(RegexPattern::new(
+        RegexLike::Regex(regex_string),
+        variables,
+    )))
+}
+
+/// Matches a single type parameter, such as `K extends keyof T = never`,
+/// which may hold commas within brackets, such as `M extends Map<K, V>`.
+fn type_parameter_regex() -> String {
+    list_element_regex(r#""(?:[^"\\]|\\.)*"|=>"#, "\"", &TYPE_LIST_BRACKETS, ',')
+}
+
+/// Compiles a list of type parameters, such as `<$T, $U>`, into a regex
+/// matching the list parameter by parameter, since the parameters aren't
+/// held in a field. A parameter made of a single metavariable binds a whole
+/// parameter of the target, along with its constraint and default, `$_`
+/// matches one and `$...` any number of them.
+///
+/// Also returns whether every parameter is a metavariable, in which case the
+/// list is optional.
+fn type_parameters_regex<Q: QueryContext>(
+    node: &NodeWithSource,
+    context_range: ByteRange,
+    range_map: &HashMap<ByteRange, ByteRange>,
+    context: &mut NodeCompilationContext,
+) -> Result<Option<(RegexPattern<Q>, bool)>> {
+    let lang = context.compilation.lang;
+    let parameter = type_parameter_regex();
+    let mut variables = vec![];
+    let mut elements = vec![];
+    let mut only_metavariables = true;
+    for node in node.named_children() {
+        if lang.is_comment_sort(node.node.kind_id()) {
+            continue;
+        }
+        let text = node.text()?;
+        let element = if lang.exact_replaced_variable_regex().is_match(&text) {
+            let value = text_to_var(&text, node.byte_range(), context_range, range_map, context)?;
+            metavariable_list_element(value, &parameter, &mut variables)
+        } else {
+            only_metavariables = false;
+            let Some((parameter_regex, parameter_variables)) = text_metavariable_regex(
+                &node,
+                ("(.*?)", ".*?"),
+                context_range,
+                range_map,
+                context,
+            )?
+            else {
+                return Ok(None);
+            };
+            variables.extend(parameter_variables);
+            ListElementRegex::Element(parameter_regex)
+        };
+        elements.push(element);
+    }
+    let regex_string = format!(
+        r"<\s*{}(?:\s*,)?\s*>",
+        separated_list_regex(elements, &parameter, r"\s*,\s*")
+    );
+    Ok(Some((
+        RegexPattern::new(RegexLike::Regex(regex_string), variables),
+        only_metavariables,
+    )))
+}
+
+fn flatten_union_members<'a>(
+    node: NodeWithSource<'a>,
+    lang: &TargetLanguage,
+    members: &mut Vec<NodeWithSource<'a>>,
+) {
+    for child in node.named_children() {
+        if lang.is_union_type_sort(child.node.kind_id()) {
+            flatten_union_members(child, lang, members);
+        } else if !lang.is_comment_sort(child.node.kind_id()) {
+            members.push(child);
+        }
+    }
+}
+
+/// The operator and operands of a chain of an associative operator on the
+/// left-hand side, if one of its operands is `$...` or a named spread, in which
+/// case the chain is matched as a flat list of operands.
+fn spread_operator_chain<'a>(
+    node: &NodeWithSource<'a>,
+    context: &NodeCompilationContext,
+    is_rhs: bool,
+) -> Option<(&'a str, Vec<NodeWithSource<'a>>)> {
+    if is_rhs {
+        return None;
+    }
+    let lang = context.compilation.lang;
+    let operator = lang.associative_operator(node)?;
+    let mut operands = Vec::new();
+    flatten_operator_chain(node.clone(), operator, lang, &mut operands);
+    operands
+        .iter()
+        .any(|operand| is_spread_operand(operand, context))
+        .then_some((operator, operands))
+}
+
+fn is_spread_operand(operand: &NodeWithSource, context: &NodeCompilationContext) -> bool {
+    let lang = context.compilation.lang;
+    let mut operand = operand.clone();
+    while !lang.is_metavariable(&operand) {
+        let mut children = operand.children();
+        let (Some(child), None) = (children.next(), children.next()) else {
+            return false;
+        };
+        operand = child;
+    }
+    let Ok(text) = operand.text() else {
+        return false;
+    };
+    match lang.snippet_metavariable_to_grit_metavariable(&text) {
+        Some(GritMetaValue::Dots) => true,
+        Some(GritMetaValue::Variable(name)) => is_named_spread_placeholder(&name, lang),
+        Some(GritMetaValue::Underscore) | None => false,
     }
-    let regex = regex_string.to_string();
-    let regex = RegexLike::Regex(regex);
-    Ok(Some(RegexPattern::new(regex, variables)))
 }
 
 fn metavariable_descendent<Q: QueryContext>(
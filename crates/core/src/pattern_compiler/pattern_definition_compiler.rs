@@ -1,5 +1,8 @@
 use super::{
-    and_compiler::AndCompiler, compiler::NodeCompilationContext, node_compiler::NodeCompiler,
+    and_compiler::AndCompiler,
+    compiler::{CompilationContext, NodeCompilationContext},
+    node_compiler::NodeCompiler,
+    snippet_compiler::scoped_language,
 };
 use crate::{problem::MarzanoQueryContext, variables::get_variables};
 use anyhow::{anyhow, Result};
@@ -8,6 +11,12 @@ use grit_util::AstNode;
 use marzano_util::node_with_source::NodeWithSource;
 use std::collections::BTreeMap;
 
+/// Compiles a pattern definition, such as `pattern name($a) { ... }`.
+///
+/// A definition can declare the language of its body, as in
+/// `pattern print_call() language python { ... }`, which then applies to every
+/// snippet in it in place of the language of the file. Snippets that name
+/// their own language, such as `js"..."`, take precedence over both.
 pub(crate) struct PatternDefinitionCompiler;
 
 impl NodeCompiler for PatternDefinitionCompiler {
@@ -41,7 +50,41 @@ impl NodeCompiler for PatternDefinitionCompiler {
         let body = node
             .child_by_field_name("body")
             .ok_or_else(|| anyhow!("missing body of patternDefinition"))?;
-        let body = AndCompiler::from_node(&body, &mut context)?;
+        let body_lang = node
+            .child_by_field_name("language")
+            .map(|langdecl| -> Result<_> {
+                let lang_name = langdecl
+                    .child_by_field_name("name")
+                    .ok_or_else(|| anyhow!("missing name of langdecl"))?;
+                let flavor = langdecl
+                    .child_by_field_name("flavor")
+                    .map(|flavor| flavor.text().map(|text| text.trim().to_string()))
+                    .transpose()?;
+                scoped_language(
+                    lang_name.text()?.trim(),
+                    flavor.as_deref(),
+                    context.compilation.lang,
+                )
+            })
+            .transpose()?
+            .flatten();
+        let body = if let Some(body_lang) = &body_lang {
+            let compilation = CompilationContext {
+                lang: body_lang,
+                ..*context.compilation
+            };
+            let mut body_context = NodeCompilationContext {
+                compilation: &compilation,
+                vars: context.vars,
+                vars_array: context.vars_array,
+                scope_index: context.scope_index,
+                global_vars: context.global_vars,
+                logs: context.logs,
+            };
+            AndCompiler::from_node(&body, &mut body_context)?
+        } else {
+            AndCompiler::from_node(&body, &mut context)?
+        };
         let pattern_def = PatternDefinition::new(
             name.to_owned(),
             scope_index,
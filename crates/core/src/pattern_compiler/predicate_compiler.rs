@@ -1,10 +1,19 @@
 use super::{
-    accumulate_compiler::AccumulateCompiler, and_compiler::PrAndCompiler,
-    any_compiler::PrAnyCompiler, assignment_compiler::AssignmentCompiler,
-    call_compiler::PrCallCompiler, compiler::NodeCompilationContext, equal_compiler::EqualCompiler,
-    if_compiler::PrIfCompiler, log_compiler::LogCompiler, match_compiler::MatchCompiler,
-    maybe_compiler::PrMaybeCompiler, node_compiler::NodeCompiler, not_compiler::PrNotCompiler,
-    or_compiler::PrOrCompiler, predicate_return_compiler::PredicateReturnCompiler,
+    accumulate_compiler::AccumulateCompiler,
+    and_compiler::PrAndCompiler,
+    any_compiler::PrAnyCompiler,
+    assignment_compiler::AssignmentCompiler,
+    call_compiler::PrCallCompiler,
+    compiler::NodeCompilationContext,
+    equal_compiler::EqualCompiler,
+    if_compiler::PrIfCompiler,
+    log_compiler::{debug_bindings_log, is_call_to, LogCompiler, DEBUG_BINDINGS},
+    match_compiler::MatchCompiler,
+    maybe_compiler::PrMaybeCompiler,
+    node_compiler::NodeCompiler,
+    not_compiler::PrNotCompiler,
+    or_compiler::PrOrCompiler,
+    predicate_return_compiler::PredicateReturnCompiler,
     rewrite_compiler::RewriteCompiler,
 };
 use crate::problem::MarzanoQueryContext;
@@ -47,6 +56,9 @@ impl NodeCompiler for PredicateCompiler {
             "predicateEqual" => Ok(Predicate::Equal(Box::new(EqualCompiler::from_node(
                 node, context,
             )?))),
+            "predicateCall" if is_call_to(node, DEBUG_BINDINGS)? => {
+                Ok(debug_bindings_log(node, context)?.map_or(Predicate::True, Predicate::Log))
+            }
             "predicateCall" => Ok(Predicate::Call(Box::new(PrCallCompiler::from_node(
                 node, context,
             )?))),
@@ -3,8 +3,11 @@ use super::{
     pattern_compiler::PatternCompiler,
 };
 use crate::{marzano_code_snippet::MarzanoCodeSnippet, problem::MarzanoQueryContext};
-use anyhow::{anyhow, Result};
-use grit_pattern_matcher::pattern::{DynamicPattern, Pattern, Rewrite};
+use anyhow::{anyhow, bail, Result};
+use grit_pattern_matcher::{
+    effects::EffectKind,
+    pattern::{CallBuiltIn, DynamicPattern, DynamicSnippet, DynamicSnippetPart, Pattern, Rewrite},
+};
 use grit_util::{AnalysisLogBuilder, AstNode};
 use marzano_util::node_with_source::NodeWithSource;
 
@@ -55,6 +58,10 @@ impl NodeCompiler for RewriteCompiler {
             }
             (_, _) => {}
         }
+        let (right, kind) = match right {
+            Pattern::CallBuiltIn(call) => insertion_from_call(*call, context)?,
+            right => (right, EffectKind::Rewrite),
+        };
         let right = match right {
             Pattern::Dynamic(r) => r,
             Pattern::CodeSnippet(MarzanoCodeSnippet {
@@ -67,6 +74,7 @@ impl NodeCompiler for RewriteCompiler {
             Pattern::CallBuiltIn(c) => DynamicPattern::CallBuiltIn(*c),
             Pattern::CallFunction(c) => DynamicPattern::CallFunction(*c),
             Pattern::CallForeignFunction(c) => DynamicPattern::CallForeignFunction(*c),
+            Pattern::Assignment(a) => DynamicPattern::Assignment(a),
             Pattern::AstNode(_)
                 | Pattern::List(_)
                 | Pattern::Map(_)
@@ -76,7 +84,6 @@ impl NodeCompiler for RewriteCompiler {
                 | Pattern::Files(_)
                 | Pattern::Bubble(_)
                 | Pattern::Limit(_)
-                | Pattern::Assignment(_)
                 | Pattern::Accumulate(_)
                 | Pattern::And(_)
                 | Pattern::Or(_)
@@ -112,6 +119,7 @@ impl NodeCompiler for RewriteCompiler {
                 | Pattern::Modulo(_)
                 | Pattern::Like(_)
                 | Pattern::Dots
+                | Pattern::Spread(_)
                 | Pattern::Sequential(_) => Err(anyhow!(
                 "right hand side of rewrite must be a code snippet or function call, but found: {:?}",
                 right
@@ -122,6 +130,32 @@ impl NodeCompiler for RewriteCompiler {
             Ok(t) => Some(t.trim().to_string()),
             Err(_) => None,
         });
-        Ok(Rewrite::new(left, right, annotation))
+        Ok(Rewrite::new(left, right, annotation).with_kind(kind))
     }
 }
+
+/// Unwraps `insert_before(text)` and `insert_after(text)`, which insert their
+/// text next to the match instead of replacing it. Other calls are kept as is.
+fn insertion_from_call(
+    call: CallBuiltIn<MarzanoQueryContext>,
+    context: &NodeCompilationContext,
+) -> Result<(Pattern<MarzanoQueryContext>, EffectKind)> {
+    let name = context.compilation.built_ins.get_built_ins()[call.index].name;
+    let kind = match name {
+        "insert_before" => EffectKind::InsertBefore,
+        "insert_after" => EffectKind::Insert,
+        _ => return Ok((Pattern::CallBuiltIn(Box::new(call)), EffectKind::Rewrite)),
+    };
+    let Some(Some(text)) = call.args.into_iter().next() else {
+        bail!("{name} takes 1 argument: text");
+    };
+    let text = match text {
+        Pattern::StringConstant(constant) => {
+            Pattern::Dynamic(DynamicPattern::Snippet(DynamicSnippet {
+                parts: vec![DynamicSnippetPart::String(constant.text)],
+            }))
+        }
+        text => text,
+    };
+    Ok((text, kind))
+}
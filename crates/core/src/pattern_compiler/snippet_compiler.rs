@@ -1,24 +1,76 @@
 use super::{
     back_tick_compiler::{BackTickCompiler, RawBackTickCompiler},
     pattern_compiler::PatternCompiler,
+    snippet_preprocessors::{
+        apply_ancestor_constraints, compile_alternation_snippet, compile_declaration_kind_snippet,
+        compile_documented_snippet, compile_flag_snippet, compile_member_modifiers_snippet,
+        compile_unexported_snippet, edit_distance_constraints, extract_ancestor_constraints,
+        extract_content_regexes, extract_fuzzy_identifiers, extract_sibling_constraints,
+        leading_doc_comment_end, mangle_literal_metavariables, mangle_named_spreads,
+        restore_literal_metavariables, restore_named_spreads, SnippetParses,
+    },
     NodeCompiler,
 };
 use crate::{
-    marzano_code_snippet::MarzanoCodeSnippet, problem::MarzanoQueryContext,
-    variables::register_variable,
+    ast_node::ASTNode,
+    marzano_code_snippet::{ContentConstraint, MarzanoCodeSnippet, SiblingConstraint},
+    problem::MarzanoQueryContext,
+    snippet_escapes::process_snippet_escapes,
+    validate_snippet::snippet_complexity,
+    variables::{register_variable, variable_from_name},
+};
+use crate::{
+    pattern_compiler::compiler::{
+        CompilationContext, NodeCompilationContext, SnippetCompileTiming, SnippetFallbackLint,
+        SourceOffset,
+    },
+    split_snippet::split_snippet,
 };
-use crate::{pattern_compiler::compiler::NodeCompilationContext, split_snippet::split_snippet};
 use anyhow::{anyhow, bail, Result};
 use grit_pattern_matcher::{
     constants::{DEFAULT_FILE_NAME, GLOBAL_VARS_SCOPE_INDEX},
-    pattern::{DynamicPattern, DynamicSnippet, DynamicSnippetPart, Pattern, Variable},
+    pattern::{
+        And, Contains, DynamicPattern, DynamicSnippet, DynamicSnippetPart, List, Not, Or, Pattern,
+        PatternOrPredicate, PrAnd, Predicate, Variable, Where,
+    },
 };
-use grit_util::{AstNode, ByteRange, Language};
+use grit_util::{AnalysisLogBuilder, AstNode, ByteRange, Language, Position, Range};
+use itertools::Itertools;
 use marzano_language::{
-    language::{nodes_from_indices, MarzanoLanguage, SortId},
-    target_language::TargetLanguage,
+    language::{nodes_from_indices, MarzanoLanguage, NodeTypes, SortId},
+    target_language::{PatternLanguage, TargetLanguage},
 };
 use marzano_util::node_with_source::NodeWithSource;
+use regex::Regex;
+use std::{borrow::Cow, collections::BTreeSet, time::Instant};
+
+/// Suffix of a bracketed metavariable, such as `$[item.index]`, which refers to
+/// the position of the bound list element rather than its content.
+const LIST_INDEX_ACCESSOR: &str = ".index";
+
+/// Suffix of a bracketed metavariable, such as `$[text.quote]`, which refers to
+/// the quotes delimiting the bound string literal.
+const STRING_QUOTE_ACCESSOR: &str = ".quote";
+
+/// Suffix of a bracketed metavariable, such as `$[args.text]`, which refers to
+/// the source of the binding as written, without applying any rewrites.
+const VERBATIM_TEXT_ACCESSOR: &str = ".text";
+
+/// Suffix of a bracketed metavariable, such as `$[stmt.indent]`, which refers
+/// to the indentation of the line the binding starts on.
+const INDENT_ACCESSOR: &str = ".indent";
+
+/// Suffix of a bracketed metavariable, such as `$[value.kind]`, which refers
+/// to the kind of the bound node.
+const NODE_KIND_ACCESSOR: &str = ".kind";
+
+/// Suffix of a bracketed metavariable, such as `$[stmt.prev]`, which refers
+/// to the source of the sibling preceding the bound node.
+const PREVIOUS_SIBLING_ACCESSOR: &str = ".prev";
+
+/// Suffix of a bracketed metavariable, such as `$[stmt.next]`, which refers
+/// to the source of the sibling following the bound node.
+const NEXT_SIBLING_ACCESSOR: &str = ".next";
 
 pub(crate) struct CodeSnippetCompiler;
 
@@ -60,8 +112,20 @@ impl NodeCompiler for LanguageSpecificSnippetCompiler {
             .child_by_field_name("language")
             .ok_or_else(|| anyhow!("missing language of languageSpecificSnippet"))?;
         let lang_name = lang_node.text()?.trim().to_string();
-        let _snippet_lang = TargetLanguage::from_string(&lang_name, None)
-            .ok_or_else(|| anyhow!("invalid language: {lang_name}"))?;
+        let flavor = node
+            .child_by_field_name("flavor")
+            .map(|flavor| flavor.text().map(|text| text.trim().to_string()))
+            .transpose()?;
+        // An invalid language falls back to the one in scope, so the rest of
+        // the snippet can still be checked.
+        let snippet_lang =
+            match scoped_language(&lang_name, flavor.as_deref(), context.compilation.lang) {
+                Ok(snippet_lang) => snippet_lang,
+                Err(error) => {
+                    context.recover(error, lang_node.range())?;
+                    None
+                }
+            };
         let snippet_node = node
             .child_by_field_name("snippet")
             .ok_or_else(|| anyhow!("missing snippet of languageSpecificSnippet"))?;
@@ -74,22 +138,65 @@ impl NodeCompiler for LanguageSpecificSnippetCompiler {
             .strip_suffix('"')
             .ok_or_else(|| anyhow!("Unable to extract content from raw snippet: {source}"))?;
 
-        parse_snippet_content(content, range.into(), context, is_rhs)
+        let Some(snippet_lang) = snippet_lang else {
+            return parse_snippet_content(content, range, context, is_rhs);
+        };
+        let compilation = CompilationContext {
+            lang: &snippet_lang,
+            ..*context.compilation
+        };
+        let mut snippet_context = NodeCompilationContext {
+            compilation: &compilation,
+            vars: context.vars,
+            vars_array: context.vars_array,
+            scope_index: context.scope_index,
+            global_vars: context.global_vars,
+            logs: context.logs,
+        };
+        parse_snippet_content(content, range, &mut snippet_context, is_rhs)
     }
 }
 
+/// Resolves the language named by a language-specific snippet, such as
+/// `python"..."`, or by the `language` declaration of a pattern definition.
+///
+/// Returns `None` when the name refers to the language already in scope and
+/// no flavor is given, in which case its grammar is kept: `js"..."` in a
+/// `language js(typescript)` file still compiles to TypeScript sorts, which
+/// line up with the files being matched.
+pub(crate) fn scoped_language(
+    name: &str,
+    flavor: Option<&str>,
+    current: &TargetLanguage,
+) -> Result<Option<TargetLanguage>> {
+    if let Some(flavor) = flavor {
+        let flavors = PatternLanguage::flavors(name);
+        let name = name.to_ascii_lowercase();
+        if !flavors.contains(&flavor) {
+            if flavors.is_empty() {
+                bail!("language {name} does not have any flavors, found: {flavor}");
+            }
+            bail!(
+                "invalid flavor for language {name}: {flavor}, expected one of: {}",
+                flavors.join(", ")
+            );
+        }
+    }
+    let language = PatternLanguage::from_string(name, flavor)
+        .ok_or_else(|| anyhow!("invalid language: {name}"))?;
+    if flavor.is_none() && language.to_string() == current.to_module_language().to_string() {
+        return Ok(None);
+    }
+    Ok(Some(language.try_into()?))
+}
+
 pub(crate) fn dynamic_snippet_from_source(
     raw_source: &str,
-    source_range: ByteRange,
+    snippet_range: Range,
     context: &mut NodeCompilationContext,
 ) -> Result<DynamicSnippet> {
-    let source_string = raw_source
-        .replace("\\n", "\n")
-        .replace("\\$", "$")
-        .replace("\\^", "^")
-        .replace("\\`", "`")
-        .replace("\\\"", "\"")
-        .replace("\\\\", "\\");
+    let source_range: ByteRange = snippet_range.into();
+    let (source_string, escapes) = process_snippet_escapes(raw_source);
     let source = source_string.as_str();
     let metavariables = split_snippet(source, context.compilation.lang);
     let mut parts = Vec::with_capacity(2 * metavariables.len() + 1);
@@ -99,33 +206,55 @@ pub(crate) fn dynamic_snippet_from_source(
         parts.push(DynamicSnippetPart::String(
             source[last..byte_range.start].to_string(),
         ));
+        let (var, to_part): (&str, fn(Variable) -> DynamicSnippetPart) =
+            if let Some(name) = var.strip_suffix(LIST_INDEX_ACCESSOR) {
+                (name, DynamicSnippetPart::Index)
+            } else if let Some(name) = var.strip_suffix(STRING_QUOTE_ACCESSOR) {
+                (name, DynamicSnippetPart::Quote)
+            } else if let Some(name) = var.strip_suffix(VERBATIM_TEXT_ACCESSOR) {
+                (name, DynamicSnippetPart::Text)
+            } else if let Some(name) = var.strip_suffix(INDENT_ACCESSOR) {
+                (name, DynamicSnippetPart::Indent)
+            } else if let Some(name) = var.strip_suffix(NODE_KIND_ACCESSOR) {
+                (name, DynamicSnippetPart::Kind)
+            } else if let Some(name) = var.strip_suffix(PREVIOUS_SIBLING_ACCESSOR) {
+                (name, DynamicSnippetPart::PreviousSibling)
+            } else if let Some(name) = var.strip_suffix(NEXT_SIBLING_ACCESSOR) {
+                (name, DynamicSnippetPart::NextSibling)
+            } else {
+                (var.as_ref(), DynamicSnippetPart::Variable)
+            };
         let range = ByteRange::new(
-            source_range.start + byte_range.start,
-            source_range.start + byte_range.start + var.len(),
+            source_range.start + escapes.raw_offset(byte_range.start),
+            source_range.start + escapes.raw_offset(byte_range.start + var.len()),
         );
-        if let Some(var) = context.vars.get(var.as_ref()) {
+        if let Some(var) = context.vars.get(var) {
             context.vars_array[context.scope_index][*var]
                 .locations
                 .insert(range);
-            parts.push(DynamicSnippetPart::Variable(Variable::new(
-                context.scope_index,
-                *var,
-            )));
-        } else if let Some(var) = context.global_vars.get(var.as_ref()) {
+            parts.push(to_part(Variable::new(context.scope_index, *var)));
+        } else if let Some(var) = context.global_vars.get(var) {
             if context.compilation.file == DEFAULT_FILE_NAME {
                 context.vars_array[GLOBAL_VARS_SCOPE_INDEX][*var]
                     .locations
                     .insert(range);
             }
-            parts.push(DynamicSnippetPart::Variable(Variable::new(
-                GLOBAL_VARS_SCOPE_INDEX,
-                *var,
-            )));
+            parts.push(to_part(Variable::new(GLOBAL_VARS_SCOPE_INDEX, *var)));
         } else if var.starts_with("$GLOBAL_") {
-            let variable = register_variable(&var, range, context)?;
-            parts.push(DynamicSnippetPart::Variable(variable));
+            let variable = register_variable(var, range, context)?;
+            parts.push(to_part(variable));
         } else {
-            bail!("Could not find variable {var} in this context, for snippet {source}");
+            let position = snippet_position(
+                snippet_range,
+                raw_source,
+                escapes.raw_offset(byte_range.start),
+                context,
+            );
+            bail!(
+                "Could not find variable {var} in this context, for snippet {source} (at line {}, column {})",
+                position.line,
+                position.column
+            );
         }
         last = byte_range.end;
     }
@@ -135,10 +264,196 @@ pub(crate) fn dynamic_snippet_from_source(
 
 pub(crate) fn parse_snippet_content(
     source: &str,
-    range: ByteRange,
+    snippet_range: Range,
     context: &mut NodeCompilationContext,
     is_rhs: bool,
 ) -> Result<Pattern<MarzanoQueryContext>> {
+    if source.trim().is_empty() {
+        if !is_rhs {
+            bail!("empty snippet, use `.` to match an absent node");
+        }
+        // A blank right-hand side inserts nothing, deleting what it rewrites.
+        return Ok(Pattern::Dynamic(DynamicPattern::Snippet(DynamicSnippet {
+            parts: vec![DynamicSnippetPart::String(String::new())],
+        })));
+    }
+    // Whitespace on the right-hand side ends up in the rewritten code.
+    if is_rhs || !context.compilation.options.collapse_snippet_whitespace {
+        return timed_snippet_content(source, snippet_range, context, is_rhs);
+    }
+    let Some(collapsed) = CollapsedWhitespace::new(source) else {
+        return timed_snippet_content(source, snippet_range, context, is_rhs);
+    };
+    let scopes = if context.scope_index == GLOBAL_VARS_SCOPE_INDEX {
+        vec![GLOBAL_VARS_SCOPE_INDEX]
+    } else {
+        vec![context.scope_index, GLOBAL_VARS_SCOPE_INDEX]
+    };
+    let previous_locations: Vec<Vec<BTreeSet<ByteRange>>> = scopes
+        .iter()
+        .map(|scope| {
+            context.vars_array[*scope]
+                .iter()
+                .map(|variable| variable.locations.clone())
+                .collect()
+        })
+        .collect();
+    let pattern = timed_snippet_content(&collapsed.source, snippet_range, context, is_rhs);
+    let snippet_start = ByteRange::from(snippet_range).start;
+    for (scope, previous) in scopes.into_iter().zip(previous_locations) {
+        for (index, variable) in context.vars_array[scope].iter_mut().enumerate() {
+            let previous = previous.get(index);
+            variable.locations = std::mem::take(&mut variable.locations)
+                .into_iter()
+                .map(|location| {
+                    if previous.is_some_and(|previous| previous.contains(&location)) {
+                        location
+                    } else {
+                        collapsed.original_range(location, snippet_start)
+                    }
+                })
+                .collect();
+        }
+    }
+    pattern
+}
+
+/// A snippet with its runs of whitespace collapsed, along with what's needed
+/// to map offsets back into the original snippet.
+struct CollapsedWhitespace {
+    source: String,
+    /// Offsets in the collapsed and in the original snippet just past each
+    /// run of whitespace that was shortened, in ascending order.
+    anchors: Vec<(usize, usize)>,
+}
+
+impl CollapsedWhitespace {
+    /// Collapses every run of whitespace outside of string literals into a
+    /// single space, or, if the run spans lines, into a single line break
+    /// followed by the indentation of the next line.
+    ///
+    /// Returns `None` if there is nothing to collapse.
+    fn new(source: &str) -> Option<Self> {
+        let mut collapsed = String::with_capacity(source.len());
+        let mut anchors = Vec::new();
+        let mut quote = None;
+        let mut chars = source.char_indices().peekable();
+        while let Some((start, c)) = chars.next() {
+            if let Some(q) = quote {
+                collapsed.push(c);
+                if c == '\\' {
+                    if let Some((_, escaped)) = chars.next() {
+                        collapsed.push(escaped);
+                    }
+                } else if c == q {
+                    quote = None;
+                }
+                continue;
+            }
+            if !c.is_whitespace() {
+                if matches!(c, '"' | '\'' | '`') {
+                    quote = Some(c);
+                }
+                collapsed.push(c);
+                continue;
+            }
+            let mut end = start + c.len_utf8();
+            while let Some((index, c)) = chars.next_if(|(_, c)| c.is_whitespace()) {
+                end = index + c.len_utf8();
+            }
+            let run = &source[start..end];
+            let replacement = run.rfind('\n').map_or(" ", |newline| &run[newline..]);
+            collapsed.push_str(replacement);
+            if replacement.len() != run.len() {
+                anchors.push((collapsed.len(), end));
+            }
+        }
+        (!anchors.is_empty()).then_some(Self {
+            source: collapsed,
+            anchors,
+        })
+    }
+
+    fn original_offset(&self, offset: usize) -> usize {
+        let index = self
+            .anchors
+            .partition_point(|(collapsed, _)| *collapsed <= offset);
+        match index.checked_sub(1) {
+            Some(index) => {
+                let (collapsed, original) = self.anchors[index];
+                original + offset - collapsed
+            }
+            None => offset,
+        }
+    }
+
+    /// Maps a range within the collapsed snippet starting at `snippet_start`
+    /// back into the original snippet. Ranges elsewhere are left untouched.
+    fn original_range(&self, range: ByteRange, snippet_start: usize) -> ByteRange {
+        if range.start < snippet_start || range.end > snippet_start + self.source.len() {
+            return range;
+        }
+        ByteRange::new(
+            snippet_start + self.original_offset(range.start - snippet_start),
+            snippet_start + self.original_offset(range.end - snippet_start),
+        )
+    }
+}
+
+fn timed_snippet_content(
+    source: &str,
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+    is_rhs: bool,
+) -> Result<Pattern<MarzanoQueryContext>> {
+    let mut parses = SnippetParses::new(context.compilation.lang);
+    let Some(timings) = context.compilation.snippet_timings else {
+        return compile_snippet_content(source, snippet_range, context, is_rhs, &mut parses);
+    };
+    let start = Instant::now();
+    let pattern = compile_snippet_content(source, snippet_range, context, is_rhs, &mut parses);
+    timings.borrow_mut().push(SnippetCompileTiming {
+        source: source.to_string(),
+        range: context
+            .compilation
+            .options
+            .source_offset
+            .range(snippet_range),
+        duration: start.elapsed(),
+        parse_contexts: parses.parse_contexts(),
+    });
+    pattern
+}
+
+pub(crate) fn compile_snippet_content(
+    source: &str,
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+    is_rhs: bool,
+    parses: &mut SnippetParses,
+) -> Result<Pattern<MarzanoQueryContext>> {
+    let range: ByteRange = snippet_range.into();
+    if let Some(max) = context.compilation.options.max_snippet_metavariables {
+        let complexity = snippet_complexity(source, context.compilation.lang);
+        if complexity.metavariables > max {
+            context.recover(
+                anyhow!(
+                    "snippet `{source}` has {} distinct metavariables, more than the limit of {max}",
+                    complexity.metavariables
+                ),
+                snippet_range,
+            )?;
+        }
+    }
+    if !is_rhs {
+        let (mangled, literals) = mangle_literal_metavariables(source, context.compilation.lang);
+        if !literals.is_empty() {
+            let mut pattern =
+                compile_snippet_content(&mangled, snippet_range, context, is_rhs, parses)?;
+            restore_literal_metavariables(&mut pattern, source, &literals, snippet_range, context)?;
+            return Ok(pattern);
+        }
+    }
     // we check for CURLY_VAR_REGEX in the content, and if found
     // compile into a DynamicPattern, rather than a CodeSnippet.
     // This is because the syntax should only ever be necessary
@@ -154,13 +469,18 @@ pub(crate) fn parse_snippet_content(
         .metavariable_bracket_regex()
         .is_match(source)
     {
-        if is_rhs {
-            Ok(Pattern::Dynamic(
-                dynamic_snippet_from_source(source, range, context).map(DynamicPattern::Snippet)?,
-            ))
-        } else {
-            bail!("bracketed metavariables are only allowed on the rhs of a snippet");
+        if !is_rhs {
+            // When recovering, the snippet is matched as text, as it would be
+            // on the right-hand side.
+            context.recover(
+                anyhow!("bracketed metavariables are only allowed on the rhs of a snippet"),
+                snippet_range,
+            )?;
         }
+        Ok(Pattern::Dynamic(
+            dynamic_snippet_from_source(source, snippet_range, context)
+                .map(DynamicPattern::Snippet)?,
+        ))
     } else {
         if context
             .compilation
@@ -177,18 +497,109 @@ pub(crate) fn parse_snippet_content(
                 }
             }
         }
-        let snippet_trees = context.compilation.lang.parse_snippet_contexts(source);
-        let snippet_nodes = nodes_from_indices(&snippet_trees);
+        if !is_rhs {
+            if let Some(alternation) =
+                compile_alternation_snippet(source, snippet_range, context, parses)?
+            {
+                return Ok(alternation);
+            }
+            if let Some(comment_end) = leading_doc_comment_end(source, context.compilation.lang) {
+                return compile_documented_snippet(
+                    source,
+                    comment_end,
+                    snippet_range,
+                    context,
+                    parses,
+                );
+            }
+            if let Some(unexported) =
+                compile_unexported_snippet(source, snippet_range, context, parses)?
+            {
+                return Ok(unexported);
+            }
+            if let Some(declaration) =
+                compile_declaration_kind_snippet(source, snippet_range, context, parses)?
+            {
+                return Ok(declaration);
+            }
+            if let Some(member) =
+                compile_member_modifiers_snippet(source, snippet_range, context, parses)?
+            {
+                return Ok(member);
+            }
+            if let Some(flagged) = compile_flag_snippet(source, snippet_range, context, parses)? {
+                return Ok(flagged);
+            }
+        }
+        let (stripped, content_regexes) = if is_rhs {
+            (Cow::Borrowed(source), Vec::new())
+        } else {
+            extract_content_regexes(source, context.compilation.lang)?
+        };
+        let source: &str = &stripped;
+        let (stripped, fuzzy_identifiers) = if is_rhs {
+            (Cow::Borrowed(source), Vec::new())
+        } else {
+            extract_fuzzy_identifiers(source, range.start, context.compilation.lang)?
+        };
+        let source: &str = &stripped;
+        let (stripped, sibling_constraints) = if is_rhs {
+            (Cow::Borrowed(source), Vec::new())
+        } else {
+            extract_sibling_constraints(source, context.compilation.lang)?
+        };
+        let source: &str = &stripped;
+        let (stripped, ancestor_constraints) = if is_rhs {
+            (Cow::Borrowed(source), Vec::new())
+        } else {
+            extract_ancestor_constraints(source, context.compilation.lang)?
+        };
+        let source: &str = &stripped;
+        let (snippet_source, spreads) = if is_rhs {
+            (Cow::Borrowed(source), Vec::new())
+        } else {
+            mangle_named_spreads(source, context.compilation.lang)
+        };
+        if context.compilation.options.is_cancelled() {
+            bail!("compilation of snippet `{source}` was cancelled");
+        }
+        let snippet_nodes = nodes_from_indices(parses.trees(&snippet_source));
         if snippet_nodes.is_empty() {
+            if !content_regexes.is_empty() {
+                bail!(
+                    "snippet `{source}` must parse as {} to constrain its string contents",
+                    context.compilation.lang.language_name()
+                );
+            }
+            if !fuzzy_identifiers.is_empty() {
+                bail!(
+                    "snippet `{source}` must parse as {} to match identifiers by edit distance",
+                    context.compilation.lang.language_name()
+                );
+            }
+            if !ancestor_constraints.is_empty() {
+                bail!(
+                    "snippet `{source}` must parse as {} to constrain its ancestors",
+                    context.compilation.lang.language_name()
+                );
+            }
+            if !sibling_constraints.is_empty() {
+                bail!(
+                    "snippet `{source}` must parse as {} to constrain sibling positions",
+                    context.compilation.lang.language_name()
+                );
+            }
+            lint_snippet_fallback(source, snippet_range, context)?;
             // not checking if is_rhs. So could potentially
             // be harder to find bugs where we expect the pattern
             // to parse. unfortunately got rid of check to support
             // passing non-node snippets as args.
             return Ok(Pattern::Dynamic(
-                dynamic_snippet_from_source(source, range, context).map(DynamicPattern::Snippet)?,
+                dynamic_snippet_from_source(source, snippet_range, context)
+                    .map(DynamicPattern::Snippet)?,
             ));
         }
-        let snippet_patterns: Vec<(SortId, Pattern<MarzanoQueryContext>)> = snippet_nodes
+        let mut snippet_patterns: Vec<(SortId, Pattern<MarzanoQueryContext>)> = snippet_nodes
             .into_iter()
             .map(|node| {
                 Ok((
@@ -197,12 +608,439 @@ pub(crate) fn parse_snippet_content(
                 ))
             })
             .collect::<Result<Vec<(SortId, Pattern<MarzanoQueryContext>)>>>()?;
-        let dynamic_snippet = dynamic_snippet_from_source(source, range, context)
+        if let Some(max) = context.compilation.options.max_snippet_candidates {
+            limit_snippet_candidates(&mut snippet_patterns, max, source, snippet_range, context)?;
+        }
+        if !spreads.is_empty() {
+            restore_named_spreads(&mut snippet_patterns, &spreads, range, context)?;
+        }
+        if !is_rhs {
+            for (_, pattern) in snippet_patterns.iter_mut() {
+                match_entries_as_subset(pattern, context.compilation.lang);
+                match_keyword_arguments_by_name(pattern, context.compilation.lang);
+                match_member_chain_wildcards(pattern, context.compilation.lang);
+                match_inheritance_clauses_optionally(pattern, context.compilation.lang);
+            }
+        }
+        let content_constraints = content_regexes
+            .into_iter()
+            .map(|(name, regex)| {
+                let regex = Regex::new(&format!("^(?:{regex})$"))
+                    .map_err(|e| anyhow!("invalid content regex for {name}: {e}"))?;
+                Ok(ContentConstraint {
+                    variable: variable_from_name(&name, context)?,
+                    regex,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let sibling_constraints = sibling_constraints
+            .into_iter()
+            .map(|(name, position)| {
+                Ok(SiblingConstraint {
+                    variable: name
+                        .map(|name| variable_from_name(&name, context))
+                        .transpose()?,
+                    position,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let dynamic_snippet = dynamic_snippet_from_source(source, snippet_range, context)
             .map_or(None, |s| Some(DynamicPattern::Snippet(s)));
-        Ok(Pattern::CodeSnippet(MarzanoCodeSnippet::new(
-            snippet_patterns,
-            dynamic_snippet,
-            source,
-        )))
+        let mut snippet = Pattern::CodeSnippet(
+            MarzanoCodeSnippet::new(snippet_patterns, dynamic_snippet, source)
+                .with_content_constraints(content_constraints)
+                .with_sibling_constraints(sibling_constraints)
+                .with_range(range),
+        );
+        if !fuzzy_identifiers.is_empty() {
+            let constraints = edit_distance_constraints(fuzzy_identifiers, range, context)?;
+            snippet = Pattern::Where(Box::new(Where::new(
+                snippet,
+                Predicate::And(Box::new(PrAnd::new(constraints))),
+            )));
+        }
+        if !ancestor_constraints.is_empty() {
+            snippet = apply_ancestor_constraints(snippet, ancestor_constraints, context)?;
+        }
+        Ok(snippet)
     }
 }
+
+/// Lets a collection snippet, such as `{ name: $n }`, match collections which
+/// contain its entries in any order, alongside any other entries.
+///
+/// Each entry is tried against the entries of the collection in order and the
+/// first one that matches is bound, so constraints in a `where` clause apply
+/// to that entry rather than selecting among them: use `contains` on the
+/// collection to consider every entry. Collections with `$...` among their
+/// entries are matched as written.
+fn match_entries_as_subset(pattern: &mut Pattern<MarzanoQueryContext>, lang: &TargetLanguage) {
+    let Pattern::AstNode(node) = pattern else {
+        return;
+    };
+    let is_collection = lang.is_keyed_collection_sort(node.sort);
+    for (_, is_list, arg) in node.args.iter_mut() {
+        let Pattern::List(list) = arg else {
+            match_entries_as_subset(arg, lang);
+            continue;
+        };
+        for element in list.patterns.iter_mut() {
+            match_entries_as_subset(element, lang);
+        }
+        if !is_collection
+            || !*is_list
+            || list.patterns.is_empty()
+            || list
+                .patterns
+                .iter()
+                .any(|entry| matches!(entry, Pattern::Dots | Pattern::Spread(_)))
+        {
+            continue;
+        }
+        let mut entries: Vec<_> = list
+            .patterns
+            .drain(..)
+            .map(|entry| {
+                Pattern::List(Box::new(List::new(vec![
+                    Pattern::Dots,
+                    entry,
+                    Pattern::Dots,
+                ])))
+            })
+            .collect();
+        *arg = if entries.len() == 1 {
+            entries.remove(0)
+        } else {
+            Pattern::And(Box::new(And::new(entries)))
+        };
+    }
+}
+
+/// The maximum number of keyword arguments reordered by
+/// [`match_keyword_arguments_by_name`]. Every ordering of the arguments is
+/// tried, so this bounds the number of alternatives at 5! = 120.
+const MAX_REORDERED_KEYWORD_ARGUMENTS: usize = 5;
+
+/// Lets the keyword arguments of a snippet, such as `key=$v` in
+/// `foo($x, key=$v)`, match keyword arguments written in any order.
+///
+/// Positional arguments are still matched in order, and only keyword
+/// arguments that follow all of them are reordered. Every ordering is tried,
+/// so calls with more than `MAX_REORDERED_KEYWORD_ARGUMENTS` keyword arguments
+/// keep the written order.
+fn match_keyword_arguments_by_name(
+    pattern: &mut Pattern<MarzanoQueryContext>,
+    lang: &TargetLanguage,
+) {
+    let Pattern::AstNode(node) = pattern else {
+        return;
+    };
+    let sort = node.sort;
+    for (_, is_list, arg) in node.args.iter_mut() {
+        let Pattern::List(list) = arg else {
+            match_keyword_arguments_by_name(arg, lang);
+            continue;
+        };
+        for element in list.patterns.iter_mut() {
+            match_keyword_arguments_by_name(element, lang);
+        }
+        if !*is_list {
+            continue;
+        }
+        let is_keyword = |pattern: &Pattern<MarzanoQueryContext>| {
+            let Pattern::AstNode(element) = pattern else {
+                return false;
+            };
+            lang.is_keyword_argument(sort, element.sort)
+        };
+        let positional = list
+            .patterns
+            .iter()
+            .rposition(|pattern| !is_keyword(pattern))
+            .map_or(0, |index| index + 1);
+        let keywords = list.patterns.len() - positional;
+        if keywords < 2
+            || keywords > MAX_REORDERED_KEYWORD_ARGUMENTS
+            || list.patterns[..positional].iter().any(is_keyword)
+        {
+            continue;
+        }
+        let (positional, keywords) = list.patterns.split_at(positional);
+        let orderings = keywords
+            .iter()
+            .cloned()
+            .permutations(keywords.len())
+            .map(|keywords| {
+                let patterns = positional.iter().cloned().chain(keywords).collect();
+                Pattern::List(Box::new(List::new(patterns)))
+            })
+            .collect();
+        *arg = Pattern::Or(Box::new(Or::new(orderings)));
+    }
+}
+
+/// Lets a `$_` member in the middle of a chain, as in `$obj.foo().$_.bar()`,
+/// stand for any number of chained member accesses and calls, including none.
+///
+/// The rest of the chain is matched against the part before the wildcard with
+/// `contains`, which only descends through the links of the chain, so call
+/// arguments and other subexpressions are never searched.
+fn match_member_chain_wildcards(pattern: &mut Pattern<MarzanoQueryContext>, lang: &TargetLanguage) {
+    let Pattern::AstNode(node) = pattern else {
+        return;
+    };
+    let chain_field = lang.member_chain_field(node.sort);
+    for (field_id, _, arg) in node.args.iter_mut() {
+        if let Pattern::List(list) = arg {
+            for element in list.patterns.iter_mut() {
+                match_member_chain_wildcards(element, lang);
+            }
+            continue;
+        }
+        match_member_chain_wildcards(arg, lang);
+        if chain_field != Some(*field_id) {
+            continue;
+        }
+        if let Some(prefix) = take_chain_wildcard_prefix(arg, lang) {
+            *arg = Pattern::Contains(Box::new(Contains::new(
+                prefix,
+                Some(not_a_chain_link(lang)),
+            )));
+        }
+    }
+}
+
+/// Lets the inheritance clauses of a snippet which only bind metavariables,
+/// like `extends $base` in `class $c extends $base {}`, also match nodes
+/// without them, leaving the metavariables unbound.
+///
+/// Clauses naming a type, like `extends Base`, still have to be present.
+fn match_inheritance_clauses_optionally(
+    pattern: &mut Pattern<MarzanoQueryContext>,
+    lang: &TargetLanguage,
+) {
+    let Pattern::AstNode(node) = pattern else {
+        return;
+    };
+    let sort = node.sort;
+    for (field_id, _, arg) in node.args.iter_mut() {
+        let optional = lang.is_inheritance_clause(sort, *field_id) && binds_only_metavariables(arg);
+        if let Pattern::List(list) = arg {
+            for element in list.patterns.iter_mut() {
+                match_inheritance_clauses_optionally(element, lang);
+            }
+        } else {
+            match_inheritance_clauses_optionally(arg, lang);
+        }
+        if optional {
+            let clause = std::mem::replace(arg, Pattern::Underscore);
+            let absent = Pattern::Dynamic(DynamicPattern::Snippet(DynamicSnippet {
+                parts: vec![DynamicSnippetPart::String(String::new())],
+            }));
+            *arg = Pattern::Or(Box::new(Or::new(vec![clause, absent])));
+        }
+    }
+}
+
+/// Whether `pattern` is made of metavariables alone, along with the nodes
+/// holding them and the optional fields the snippet leaves out.
+fn binds_only_metavariables(pattern: &Pattern<MarzanoQueryContext>) -> bool {
+    if let Pattern::AstNode(node) = pattern {
+        return node
+            .args
+            .iter()
+            .all(|(_, _, arg)| binds_only_metavariables(arg));
+    }
+    if let Pattern::List(list) = pattern {
+        return list.patterns.iter().all(binds_only_metavariables);
+    }
+    if let Pattern::Dynamic(DynamicPattern::Snippet(snippet)) = pattern {
+        let [DynamicSnippetPart::String(text)] = snippet.parts.as_slice() else {
+            return false;
+        };
+        return text.is_empty();
+    }
+    matches!(
+        pattern,
+        Pattern::Variable(_) | Pattern::Underscore | Pattern::Dots
+    )
+}
+
+/// If `pattern` is a member access of `$_`, returns the pattern for the
+/// chain it is accessed on.
+fn take_chain_wildcard_prefix(
+    pattern: &mut Pattern<MarzanoQueryContext>,
+    lang: &TargetLanguage,
+) -> Option<Pattern<MarzanoQueryContext>> {
+    let Pattern::AstNode(node) = pattern else {
+        return None;
+    };
+    let chain_field = lang.member_chain_field(node.sort)?;
+    let mut others = node
+        .args
+        .iter()
+        .filter(|(field_id, _, _)| *field_id != chain_field)
+        .peekable();
+    if others.peek().is_none()
+        || !others.all(|(_, _, pattern)| matches!(pattern, Pattern::Underscore))
+    {
+        return None;
+    }
+    let (_, _, prefix) = node
+        .args
+        .iter_mut()
+        .find(|(field_id, _, _)| *field_id == chain_field)?;
+    Some(std::mem::replace(prefix, Pattern::Underscore))
+}
+
+/// Matches any node that can't continue a member chain, which `contains`
+/// doesn't descend into.
+fn not_a_chain_link(lang: &TargetLanguage) -> Pattern<MarzanoQueryContext> {
+    let links = (0..lang.node_types().len() as SortId)
+        .filter(|sort| lang.member_chain_field(*sort).is_some())
+        .map(|sort| Pattern::AstNode(Box::new(ASTNode::new(sort, Vec::new()))))
+        .collect();
+    Pattern::Not(Box::new(Not::new(Pattern::Or(Box::new(Or::new(links))))))
+}
+
+/// Returns the position in the host document of the byte at `index` within a
+/// snippet's `source`.
+fn snippet_position(
+    snippet_range: Range,
+    source: &str,
+    index: usize,
+    context: &NodeCompilationContext,
+) -> Position {
+    let snippet_start = SourceOffset::new(snippet_range.start_byte, snippet_range.start);
+    let position = snippet_start.position(Position::from_byte_index(source, index));
+    context.compilation.options.source_offset.position(position)
+}
+
+/// Keeps only the `max` most specific parses of an ambiguous snippet.
+///
+/// A candidate is more specific the fewer nodes its pattern is made of: for
+/// `foo()`, the call expression ranks above the expression statement wrapping
+/// it, since it matches the snippet itself rather than a context it happens to
+/// be parsed in. Candidates of the same size keep the order of the parse
+/// contexts that produced them, so the selection is deterministic.
+fn limit_snippet_candidates(
+    candidates: &mut Vec<(SortId, Pattern<MarzanoQueryContext>)>,
+    max: usize,
+    source: &str,
+    range: Range,
+    context: &mut NodeCompilationContext,
+) -> Result<()> {
+    // A snippet without any candidate would never match.
+    let max = max.max(1);
+    if candidates.len() <= max {
+        return Ok(());
+    }
+    let found = candidates.len();
+    candidates.sort_by_cached_key(|(_, pattern)| {
+        pattern
+            .iter()
+            .filter(|child| {
+                matches!(
+                    child,
+                    PatternOrPredicate::Pattern(Pattern::AstNode(_) | Pattern::AstLeafNode(_))
+                )
+            })
+            .count()
+    });
+    let lang = context.compilation.lang.get_ts_language();
+    let dropped = candidates
+        .split_off(max)
+        .into_iter()
+        .filter_map(|(sort, _)| lang.node_kind_for_id(sort))
+        .join(", ");
+    let range = context.compilation.options.source_offset.range(range);
+    let log = AnalysisLogBuilder::default()
+        .level(441_u16)
+        .file(context.compilation.file)
+        .position(range.start)
+        .range(range)
+        .message(format!(
+            "Warning: snippet `{source}` has {found} candidate parses, only the {max} most specific are kept, dropping: {dropped}"
+        ))
+        .build()?;
+    context.logs.push(log);
+    Ok(())
+}
+
+/// Reports a snippet which could not be parsed into any node, according to the
+/// configured `SnippetFallbackLint`.
+fn lint_snippet_fallback(
+    source: &str,
+    range: Range,
+    context: &mut NodeCompilationContext,
+) -> Result<()> {
+    let lint = context.compilation.options.snippet_fallback;
+    if lint == SnippetFallbackLint::Allow {
+        return Ok(());
+    }
+    let range = context.compilation.options.source_offset.range(range);
+    let message = format!(
+        "snippet `{source}` could not be parsed as {}, so it will only be matched as text",
+        context.compilation.lang.language_name()
+    );
+    if lint == SnippetFallbackLint::Deny {
+        bail!(
+            "{message} (at line {}, column {})",
+            range.start.line,
+            range.start.column
+        );
+    }
+    let log = AnalysisLogBuilder::default()
+        .level(441_u16)
+        .file(context.compilation.file)
+        .position(range.start)
+        .range(range)
+        .message(format!("Warning: {message}"))
+        .build()?;
+    context.logs.push(log);
+    Ok(())
+}
+
+/// Warns about a top-level snippet, or the left-hand side of a top-level
+/// rewrite, which is nothing but a metavariable, such as `` `$x` ``. It
+/// matches every node, flooding the results, which is rarely intended: a
+/// pattern meant to match every node can be the metavariable on its own,
+/// written without backticks.
+pub(crate) fn lint_broad_snippet(
+    node: &NodeWithSource,
+    pattern: &Pattern<MarzanoQueryContext>,
+    context: &mut NodeCompilationContext,
+) -> Result<()> {
+    let (snippet, pattern) = match (node.node.kind().as_ref(), pattern) {
+        ("codeSnippet", pattern) => (node.clone(), pattern),
+        ("rewrite", Pattern::Rewrite(rewrite)) => match node.child_by_field_name("left") {
+            Some(left) if left.node.kind() == "codeSnippet" => (left, &rewrite.left),
+            _ => return Ok(()),
+        },
+        _ => return Ok(()),
+    };
+    if !matches!(pattern, Pattern::Variable(_) | Pattern::Underscore) {
+        return Ok(());
+    }
+    let source = snippet
+        .child_by_field_name("source")
+        .map(|source| source.text().map(|text| text.into_owned()))
+        .transpose()?
+        .unwrap_or_default();
+    let range = context
+        .compilation
+        .options
+        .source_offset
+        .range(snippet.range());
+    let log = AnalysisLogBuilder::default()
+        .level(441_u16)
+        .file(context.compilation.file)
+        .position(range.start)
+        .range(range)
+        .message(format!(
+            "Warning: snippet {source} matches every node, write the metavariable without backticks if that is intended"
+        ))
+        .build()?;
+    context.logs.push(log);
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use super::SnippetParses;
+use crate::{
+    pattern_compiler::{
+        compiler::NodeCompilationContext, snippet_compiler::compile_snippet_content,
+    },
+    problem::MarzanoQueryContext,
+};
+use anyhow::Result;
+use grit_pattern_matcher::pattern::{Or, Pattern};
+use grit_util::{Language, Range};
+use marzano_language::target_language::TargetLanguage;
+
+/// Alternative tokens written inline in a snippet, such as `$(foo|bar)` in
+/// `$(foo|bar)($x)`.
+struct InlineAlternation<'a> {
+    start: usize,
+    end: usize,
+    alternatives: Vec<&'a str>,
+}
+
+impl<'a> InlineAlternation<'a> {
+    /// Finds the first alternation outside of string literals.
+    ///
+    /// An alternation starts with the metavariable prefix of the language,
+    /// which must not follow a letter, digit or underscore, just like a
+    /// metavariable name.
+    fn find(source: &'a str, lang: &TargetLanguage) -> Option<Self> {
+        let prefix = lang.metavariable_prefix();
+        let mut quote = None;
+        let mut previous: Option<char> = None;
+        let mut chars = source.char_indices();
+        while let Some((start, c)) = chars.next() {
+            if let Some(q) = quote {
+                if c == '\\' {
+                    chars.next();
+                } else if c == q {
+                    quote = None;
+                }
+            } else if matches!(c, '"' | '\'' | '`') {
+                quote = Some(c);
+            } else if source[start..].starts_with(prefix)
+                && source[start + prefix.len()..].starts_with('(')
+                && !previous.is_some_and(|p| p.is_alphanumeric() || p == '_')
+            {
+                if let Some(alternation) = Self::parse(source, start, start + prefix.len()) {
+                    return Some(alternation);
+                }
+            }
+            previous = Some(c);
+        }
+        None
+    }
+
+    fn parse(source: &'a str, start: usize, open: usize) -> Option<Self> {
+        let end = open + source[open..].find(')')? + 1;
+        let alternatives = source[open + 1..end - 1].split('|').collect::<Vec<_>>();
+        let is_token = |alternative: &&str| {
+            !alternative.is_empty() && alternative.chars().all(|c| c.is_alphanumeric() || c == '_')
+        };
+        (alternatives.len() > 1 && alternatives.iter().all(is_token)).then_some(Self {
+            start,
+            end,
+            alternatives,
+        })
+    }
+
+    /// Writes `alternative` in place of the alternation, padded to its width
+    /// so the rest of the snippet keeps its offsets.
+    fn substitute(&self, source: &str, alternative: &str) -> String {
+        format!(
+            "{}{alternative:<width$}{}",
+            &source[..self.start],
+            &source[self.end..],
+            width = self.end - self.start
+        )
+    }
+}
+
+/// Compiles a snippet with inline alternatives, such as `$(foo|bar)($x)`, into
+/// the snippets written with each alternative in place, which share their
+/// metavariables.
+///
+/// Alternatives are limited to single tokens made of letters, digits and
+/// underscores, written without whitespace, so they stand for one node of the
+/// snippet. Every alternation of a snippet is expanded, multiplying the
+/// alternatives, and each of them must parse on its own. Like a metavariable,
+/// the marker takes precedence over what it means in the target language, so
+/// to match a JavaScript call such as `$(a|b)` literally, write it with spaces
+/// around the operator. Returns `None` if the snippet has no alternation.
+pub(crate) fn compile_alternation_snippet(
+    source: &str,
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+    parses: &mut SnippetParses,
+) -> Result<Option<Pattern<MarzanoQueryContext>>> {
+    let Some(alternation) = InlineAlternation::find(source, context.compilation.lang) else {
+        return Ok(None);
+    };
+    let alternatives = alternation
+        .alternatives
+        .iter()
+        .map(|alternative| {
+            let variant = alternation.substitute(source, alternative);
+            compile_snippet_content(&variant, snippet_range, context, false, parses)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(Pattern::Or(Box::new(Or::new(alternatives)))))
+}
@@ -0,0 +1,123 @@
+use super::is_inside_quotes;
+use crate::{
+    ast_node::ASTNode, pattern_compiler::compiler::NodeCompilationContext,
+    problem::MarzanoQueryContext, variables::variable_from_name,
+};
+use anyhow::{bail, Result};
+use grit_pattern_matcher::pattern::{
+    And, Container, Match, Pattern, PrAnd, Predicate, Where, Within,
+};
+use grit_util::Language;
+use marzano_language::{language::MarzanoLanguage, target_language::TargetLanguage};
+use std::borrow::Cow;
+
+/// An inline constraint on the kind of an ancestor, as in
+/// `$x @ancestor(switch_statement)`.
+pub(crate) struct AncestorConstraint {
+    /// The metavariable the constraint follows, or `None` if it follows the
+    /// rest of the snippet.
+    variable: Option<String>,
+    kind: String,
+}
+
+/// Blanks out the inline ancestor constraints of a snippet, outside of string
+/// literals, and returns them.
+///
+/// A constraint applies to the metavariable it directly follows, or to the
+/// whole snippet when it comes last. Constraints are replaced by spaces, so
+/// offsets into the snippet remain valid.
+pub(crate) fn extract_ancestor_constraints<'a>(
+    source: &'a str,
+    lang: &TargetLanguage,
+) -> Result<(Cow<'a, str>, Vec<AncestorConstraint>)> {
+    const MARKER: &str = "@ancestor(";
+    if !source.contains(MARKER) {
+        return Ok((Cow::Borrowed(source), Vec::new()));
+    }
+    let mut blanked = source.to_string();
+    let mut constraints = Vec::new();
+    for (start, _) in source.match_indices(MARKER) {
+        if is_inside_quotes(source, start) {
+            continue;
+        }
+        let kind_start = start + MARKER.len();
+        let Some(kind_len) = source[kind_start..].find(')') else {
+            bail!("unterminated ancestor constraint in snippet `{source}`");
+        };
+        let end = kind_start + kind_len + 1;
+        let kind = source[kind_start..end - 1].trim();
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            bail!("invalid node kind `{kind}` in ancestor constraint of snippet `{source}`");
+        }
+        let preceding = source[..start].trim_end();
+        let variable = lang
+            .metavariable_regex()
+            .find_iter(preceding)
+            .last()
+            .filter(|m| m.end() == preceding.len())
+            .map(|m| m.as_str().to_string());
+        let rest = source[end..].trim_start();
+        if variable.is_none() && !rest.is_empty() && !rest.starts_with(MARKER) {
+            bail!("ancestor constraints must follow a metavariable or end the snippet `{source}`");
+        }
+        blanked.replace_range(start..end, &" ".repeat(end - start));
+        constraints.push(AncestorConstraint {
+            variable,
+            kind: kind.to_string(),
+        });
+    }
+    if constraints.is_empty() {
+        return Ok((Cow::Borrowed(source), constraints));
+    }
+    Ok((Cow::Owned(blanked), constraints))
+}
+
+/// Requires the nodes bound by a snippet, or by its metavariables, to be
+/// within a node of the kind named by each constraint.
+pub(crate) fn apply_ancestor_constraints(
+    snippet: Pattern<MarzanoQueryContext>,
+    constraints: Vec<AncestorConstraint>,
+    context: &mut NodeCompilationContext,
+) -> Result<Pattern<MarzanoQueryContext>> {
+    let mut snippet_constraints = Vec::new();
+    let mut variable_constraints = Vec::new();
+    for constraint in constraints {
+        let sort = context
+            .compilation
+            .lang
+            .get_ts_language()
+            .id_for_node_kind(&constraint.kind, true);
+        if sort == 0 {
+            bail!(
+                "unknown node kind `{}` in ancestor constraint",
+                constraint.kind
+            );
+        }
+        let within = Pattern::Within(Box::new(Within::new(Pattern::AstNode(Box::new(
+            ASTNode::new(sort, Vec::new()),
+        )))));
+        match constraint.variable {
+            Some(name) => {
+                let variable = variable_from_name(&name, context)?;
+                variable_constraints.push(Predicate::Match(Box::new(Match::new(
+                    Container::Variable(variable),
+                    Some(within),
+                ))));
+            }
+            None => snippet_constraints.push(within),
+        }
+    }
+    let mut pattern = if snippet_constraints.is_empty() {
+        snippet
+    } else {
+        snippet_constraints.insert(0, snippet);
+        Pattern::And(Box::new(And::new(snippet_constraints)))
+    };
+    if !variable_constraints.is_empty() {
+        pattern = Pattern::Where(Box::new(Where::new(
+            pattern,
+            Predicate::And(Box::new(PrAnd::new(variable_constraints))),
+        )));
+    }
+    Ok(pattern)
+}
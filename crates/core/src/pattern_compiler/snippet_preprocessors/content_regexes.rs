@@ -0,0 +1,63 @@
+use super::is_inside_quotes;
+use anyhow::{bail, Result};
+use grit_util::Language;
+use marzano_language::target_language::TargetLanguage;
+use std::borrow::Cow;
+
+/// Strips the content regexes attached to metavariables inside string
+/// literals, as in `"$url~/https?:.*/"`, and returns them along with the name
+/// of their metavariable.
+///
+/// Within a content regex, `\/` stands for a literal slash.
+pub(crate) fn extract_content_regexes<'a>(
+    source: &'a str,
+    lang: &TargetLanguage,
+) -> Result<(Cow<'a, str>, Vec<(String, String)>)> {
+    const MARKER: &str = "~/";
+    if !source.contains(MARKER) {
+        return Ok((Cow::Borrowed(source), Vec::new()));
+    }
+    let mut stripped = String::with_capacity(source.len());
+    let mut regexes = Vec::new();
+    let mut last = 0;
+    for m in lang.metavariable_regex().find_iter(source) {
+        if m.start() < last
+            || !source[m.end()..].starts_with(MARKER)
+            || !is_inside_quotes(source, m.start())
+        {
+            continue;
+        }
+        let body_start = m.end() + MARKER.len();
+        let mut regex = String::new();
+        let mut body = source[body_start..].char_indices();
+        let body_end = loop {
+            match body.next() {
+                Some((_, '\\')) => match body.next() {
+                    Some((_, '/')) => regex.push('/'),
+                    Some((_, c)) => {
+                        regex.push('\\');
+                        regex.push(c);
+                    }
+                    None => break None,
+                },
+                Some((index, '/')) => break Some(body_start + index),
+                Some((_, c)) => regex.push(c),
+                None => break None,
+            }
+        };
+        let Some(body_end) = body_end else {
+            bail!(
+                "unterminated content regex for {} in snippet `{source}`",
+                m.as_str()
+            );
+        };
+        stripped.push_str(&source[last..m.end()]);
+        regexes.push((m.as_str().to_string(), regex));
+        last = body_end + 1;
+    }
+    if regexes.is_empty() {
+        return Ok((Cow::Borrowed(source), regexes));
+    }
+    stripped.push_str(&source[last..]);
+    Ok((Cow::Owned(stripped), regexes))
+}
@@ -0,0 +1,65 @@
+use super::{
+    leading_metavariable::{bound_node, LeadingMetavariable},
+    SnippetParses,
+};
+use crate::{
+    ast_node::ASTNode,
+    pattern_compiler::{
+        compiler::NodeCompilationContext, snippet_compiler::compile_snippet_content,
+    },
+    problem::MarzanoQueryContext,
+};
+use anyhow::Result;
+use grit_pattern_matcher::pattern::{And, Contains, Or, Pattern};
+use grit_util::Range;
+use marzano_language::language::MarzanoLanguage;
+
+/// Compiles a snippet whose declaration keyword is a metavariable, such as
+/// `$kind $x = $v`, into the alternatives written with each keyword of the
+/// language, binding the metavariable to the keyword of the declaration.
+///
+/// Returns `None` if the snippet parses as is, or doesn't parse with any
+/// keyword either.
+pub(crate) fn compile_declaration_kind_snippet(
+    source: &str,
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+    parses: &mut SnippetParses,
+) -> Result<Option<Pattern<MarzanoQueryContext>>> {
+    let lang = context.compilation.lang;
+    let Some(keywords) = lang.declaration_keywords() else {
+        return Ok(None);
+    };
+    let Some(kind) = LeadingMetavariable::find(source, lang, parses) else {
+        return Ok(None);
+    };
+
+    let mut alternatives = Vec::new();
+    for (keyword, sort) in keywords.keywords.iter() {
+        let (variant, variant_range) = kind.substitute(source, keyword, snippet_range);
+        if !parses.parses(&variant) {
+            continue;
+        }
+        let declaration = compile_snippet_content(&variant, variant_range, context, false, parses)?;
+        alternatives.push((declaration, *sort));
+    }
+    if alternatives.is_empty() {
+        return Ok(None);
+    }
+
+    let var = kind.register(snippet_range, context)?;
+    let until = Pattern::AstNode(Box::new(ASTNode::new(keywords.declarator_sort, Vec::new())));
+    let alternatives = alternatives
+        .into_iter()
+        .map(|(declaration, sort)| {
+            Pattern::And(Box::new(And::new(vec![
+                declaration,
+                Pattern::Contains(Box::new(Contains::new(
+                    bound_node(sort, &var),
+                    Some(until.clone()),
+                ))),
+            ])))
+        })
+        .collect();
+    Ok(Some(Pattern::Or(Box::new(Or::new(alternatives)))))
+}
@@ -0,0 +1,70 @@
+use super::SnippetParses;
+use crate::{
+    pattern_compiler::{
+        back_tick_compiler::text_regex_pattern,
+        compiler::{NodeCompilationContext, SourceOffset},
+        snippet_compiler::compile_snippet_content,
+    },
+    problem::MarzanoQueryContext,
+};
+use anyhow::Result;
+use grit_pattern_matcher::pattern::{After, And, Pattern};
+use grit_util::{ByteRange, Language, Position, Range};
+use marzano_language::target_language::TargetLanguage;
+
+/// Returns the offset just past the doc comment a snippet starts with, if it
+/// is followed by anything else.
+pub(crate) fn leading_doc_comment_end(source: &str, lang: &TargetLanguage) -> Option<usize> {
+    let (open, close) = lang.doc_comment_delimiters()?;
+    let trimmed = source.trim_start();
+    if !trimmed.starts_with(open) {
+        return None;
+    }
+    let start = source.len() - trimmed.len() + open.len();
+    let end = start + source[start..].find(close)? + close.len();
+    // a doc comment on its own is just a comment snippet
+    (!source[end..].trim().is_empty()).then_some(end)
+}
+
+/// Compiles a snippet starting with a doc comment into a pattern matching the
+/// declaration that follows it, provided the comment precedes that
+/// declaration in the target.
+///
+/// Since comments are not part of the tree of the declarations they document,
+/// the comment is matched as text, ignoring differences in whitespace.
+/// A metavariable in the comment followed by a stop set, as in `{$name~[^}]}`,
+/// only captures text without any of the characters of the set.
+pub(crate) fn compile_documented_snippet(
+    source: &str,
+    comment_end: usize,
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+    parses: &mut SnippetParses,
+) -> Result<Pattern<MarzanoQueryContext>> {
+    let comment = source[..comment_end].trim_start();
+    let comment_start = comment_end - comment.len();
+    let comment_range = ByteRange::new(
+        snippet_range.start_byte as usize + comment_start,
+        snippet_range.start_byte as usize + comment_end,
+    );
+    let comment = text_regex_pattern(comment, comment_range, true, context)?;
+
+    let snippet_start = SourceOffset::new(snippet_range.start_byte, snippet_range.start);
+    let declaration_range = Range::new(
+        snippet_start.position(Position::from_byte_index(source, comment_end)),
+        snippet_range.end,
+        snippet_range.start_byte + comment_end as u32,
+        snippet_range.end_byte,
+    );
+    let declaration = compile_snippet_content(
+        &source[comment_end..],
+        declaration_range,
+        context,
+        false,
+        parses,
+    )?;
+    Ok(Pattern::And(Box::new(And::new(vec![
+        declaration,
+        Pattern::After(Box::new(After::new(Pattern::Regex(Box::new(comment))))),
+    ]))))
+}
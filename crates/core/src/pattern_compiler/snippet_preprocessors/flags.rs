@@ -0,0 +1,77 @@
+use super::{leading_metavariable::LeadingMetavariable, SnippetParses};
+use crate::{
+    pattern_compiler::{
+        compiler::NodeCompilationContext, snippet_compiler::compile_snippet_content,
+    },
+    problem::MarzanoQueryContext,
+};
+use anyhow::Result;
+use grit_pattern_matcher::pattern::{DynamicPattern, DynamicSnippet, DynamicSnippetPart, Pattern};
+use grit_util::Range;
+use marzano_language::language::MarzanoLanguage;
+
+/// Compiles a snippet starting with a negated flag keyword, such as
+/// `!async function $f() {}`, which only matches nodes without the keyword, or
+/// with a metavariable in its place, such as `$async function $f() {}`, which
+/// matches either way and binds the keyword if there is one.
+///
+/// Returns `None` if the snippet starts with neither.
+pub(crate) fn compile_flag_snippet(
+    source: &str,
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+    parses: &mut SnippetParses,
+) -> Result<Option<Pattern<MarzanoQueryContext>>> {
+    let lang = context.compilation.lang;
+    let flags = lang.flag_fields();
+    if flags.is_empty() {
+        return Ok(None);
+    }
+    let start = source.len() - source.trim_start().len();
+    let negated = flags.iter().find(|(keyword, _)| {
+        source[start..]
+            .strip_prefix('!')
+            .and_then(|rest| rest.strip_prefix(keyword))
+            .is_some_and(|rest| rest.starts_with(char::is_whitespace))
+    });
+    let (variant, variant_range, field, replacement) = if let Some((_, field)) = negated {
+        // Blanking the `!` keeps the offsets of the rest of the snippet.
+        let variant = format!("{} {}", &source[..start], &source[start + 1..]);
+        let absent = Pattern::Dynamic(DynamicPattern::Snippet(DynamicSnippet {
+            parts: vec![DynamicSnippetPart::String(String::new())],
+        }));
+        (variant, snippet_range, *field, absent)
+    } else {
+        let Some(flag) = LeadingMetavariable::find(source, lang, parses) else {
+            return Ok(None);
+        };
+        let Some((variant, variant_range, field)) = flags.iter().find_map(|(keyword, field)| {
+            let (variant, variant_range) = flag.substitute(source, keyword, snippet_range);
+            parses
+                .parses(&variant)
+                .then_some((variant, variant_range, *field))
+        }) else {
+            return Ok(None);
+        };
+        let bound = match flag.register(snippet_range, context)? {
+            Some(var) => Pattern::Variable(var),
+            None => Pattern::Underscore,
+        };
+        (variant, variant_range, field, bound)
+    };
+    let mut pattern = compile_snippet_content(&variant, variant_range, context, false, parses)?;
+    // The keyword only makes the snippet parse, the replacement decides
+    // whether it has to be there.
+    if let Pattern::CodeSnippet(snippet) = &mut pattern {
+        for (_, candidate) in snippet.patterns.iter_mut() {
+            if let Pattern::AstNode(node) = candidate {
+                for (field_id, _, arg) in node.args.iter_mut() {
+                    if *field_id == field {
+                        *arg = replacement.clone();
+                    }
+                }
+            }
+        }
+    }
+    Ok(Some(pattern))
+}
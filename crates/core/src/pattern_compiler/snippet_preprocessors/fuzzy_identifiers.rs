@@ -0,0 +1,139 @@
+use super::is_inside_quotes;
+use crate::{
+    pattern_compiler::compiler::NodeCompilationContext, problem::MarzanoQueryContext,
+    variables::register_variable,
+};
+use anyhow::{anyhow, bail, Result};
+use grit_pattern_matcher::pattern::{EditDistance, Pattern, Predicate, StringConstant, Variable};
+use grit_util::{ByteRange, Language};
+use marzano_language::target_language::TargetLanguage;
+use std::borrow::Cow;
+
+/// What an identifier followed by an edit distance in a snippet, such as
+/// `getUser~1` or `$name~2`, is compared to.
+enum FuzzyTarget {
+    /// A literal identifier.
+    Identifier(String),
+    /// The text bound to a metavariable, wherever it is bound.
+    Variable(String),
+}
+
+pub(crate) struct FuzzyIdentifier {
+    /// The placeholder variable the identifier was replaced with for parsing.
+    placeholder: String,
+    target: FuzzyTarget,
+    max_distance: usize,
+    /// Range of the identifier, without its edit distance, within the snippet.
+    range: ByteRange,
+}
+
+/// Replaces identifiers and metavariables followed by `~` and an edit
+/// distance, outside of string literals, with placeholder metavariables.
+/// Once compiled, `edit_distance_constraints()` checks the text bound to each
+/// placeholder against what it replaced.
+///
+/// Placeholders are named after the start of the snippet, so that several
+/// snippets in the same scope don't share them.
+pub(crate) fn extract_fuzzy_identifiers<'a>(
+    source: &'a str,
+    snippet_start: usize,
+    lang: &TargetLanguage,
+) -> Result<(Cow<'a, str>, Vec<FuzzyIdentifier>)> {
+    let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_';
+    if !source.contains('~') {
+        return Ok((Cow::Borrowed(source), Vec::new()));
+    }
+    let prefix = lang.metavariable_prefix();
+    let metavariables: Vec<_> = lang.metavariable_regex().find_iter(source).collect();
+    let mut stripped = String::with_capacity(source.len());
+    let mut identifiers = Vec::new();
+    let mut last = 0;
+    for (tilde, _) in source.match_indices('~') {
+        let digits_start = tilde + 1;
+        let digits_end = source[digits_start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(source.len(), |len| digits_start + len);
+        if digits_end == digits_start
+            || source[digits_end..].starts_with(is_identifier_char)
+            || is_inside_quotes(source, tilde)
+        {
+            continue;
+        }
+        let (start, target) = match metavariables.iter().find(|m| m.end() == tilde) {
+            Some(m) if m.as_str() == format!("{prefix}_") => continue,
+            Some(m) => (m.start(), FuzzyTarget::Variable(m.as_str().to_string())),
+            None => {
+                let start = source[..tilde]
+                    .char_indices()
+                    .rev()
+                    .find(|(_, c)| !is_identifier_char(*c))
+                    .map_or(0, |(index, c)| index + c.len_utf8());
+                let identifier = &source[start..tilde];
+                if !identifier.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+                    continue;
+                }
+                (start, FuzzyTarget::Identifier(identifier.to_string()))
+            }
+        };
+        let max_distance = source[digits_start..digits_end].parse().map_err(|_| {
+            anyhow!(
+                "invalid edit distance for {} in snippet `{source}`",
+                &source[start..tilde]
+            )
+        })?;
+        let placeholder = format!("{prefix}__fuzzy_{snippet_start}_{}", identifiers.len());
+        stripped.push_str(&source[last..start]);
+        stripped.push_str(&placeholder);
+        identifiers.push(FuzzyIdentifier {
+            placeholder,
+            target,
+            max_distance,
+            range: ByteRange::new(start, tilde),
+        });
+        last = digits_end;
+    }
+    if identifiers.is_empty() {
+        return Ok((Cow::Borrowed(source), identifiers));
+    }
+    stripped.push_str(&source[last..]);
+    Ok((Cow::Owned(stripped), identifiers))
+}
+
+/// Builds the edit distance predicates for the placeholders left by
+/// `extract_fuzzy_identifiers()`.
+pub(crate) fn edit_distance_constraints(
+    identifiers: Vec<FuzzyIdentifier>,
+    snippet_range: ByteRange,
+    context: &mut NodeCompilationContext,
+) -> Result<Vec<Predicate<MarzanoQueryContext>>> {
+    identifiers
+        .into_iter()
+        .map(|identifier| {
+            let Some(index) = context.vars.get(&identifier.placeholder).copied() else {
+                bail!(
+                    "{} must be a node of the snippet to be matched by edit distance",
+                    identifier.placeholder
+                );
+            };
+            // The placeholder is an implementation detail, so don't report it.
+            context.vars_array[context.scope_index][index]
+                .locations
+                .clear();
+            let target = match identifier.target {
+                FuzzyTarget::Identifier(text) => Pattern::StringConstant(StringConstant::new(text)),
+                FuzzyTarget::Variable(name) => {
+                    let range = ByteRange::new(
+                        snippet_range.start + identifier.range.start,
+                        snippet_range.start + identifier.range.end,
+                    );
+                    Pattern::Variable(register_variable(&name, range, context)?)
+                }
+            };
+            Ok(Predicate::EditDistance(Box::new(EditDistance::new(
+                Variable::new(context.scope_index, index),
+                target,
+                identifier.max_distance,
+            ))))
+        })
+        .collect()
+}
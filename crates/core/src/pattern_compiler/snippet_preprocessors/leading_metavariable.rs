@@ -0,0 +1,106 @@
+use super::SnippetParses;
+use crate::{
+    ast_node::ASTNode, pattern_compiler::compiler::NodeCompilationContext,
+    problem::MarzanoQueryContext, variables::register_variable,
+};
+use anyhow::Result;
+use grit_pattern_matcher::pattern::{And, Pattern, Variable};
+use grit_util::{ByteRange, Language, Range};
+use marzano_language::{language::SortId, target_language::TargetLanguage};
+
+/// A metavariable at the start of a snippet which keeps the snippet from
+/// parsing, such as `$kind` in `$kind $x = $v`.
+pub(crate) struct LeadingMetavariable<'a> {
+    name: &'a str,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> LeadingMetavariable<'a> {
+    pub(crate) fn find(
+        source: &'a str,
+        lang: &TargetLanguage,
+        parses: &mut SnippetParses,
+    ) -> Option<Self> {
+        let start = source.len() - source.trim_start().len();
+        let name = lang
+            .metavariable_regex()
+            .find(&source[start..])
+            .filter(|name| name.start() == 0)?
+            .as_str();
+        let end = start + name.len();
+        if !source[end..].starts_with([' ', '\t']) || parses.parses(source) {
+            return None;
+        }
+        Some(Self { name, start, end })
+    }
+
+    fn range(&self, snippet_range: Range) -> ByteRange {
+        ByteRange::new(
+            snippet_range.start_byte as usize + self.start,
+            snippet_range.start_byte as usize + self.end,
+        )
+    }
+
+    /// Writes `keyword` in place of the metavariable, along with the range of
+    /// the resulting snippet.
+    ///
+    /// Padding the keyword to the width of the metavariable keeps the offsets
+    /// of the rest of the snippet, unless the keyword is longer.
+    pub(crate) fn substitute(
+        &self,
+        source: &str,
+        keyword: &str,
+        snippet_range: Range,
+    ) -> (String, Range) {
+        let variant = format!(
+            "{}{keyword:<width$}{}",
+            &source[..self.start],
+            &source[self.end..],
+            width = self.name.len()
+        );
+        let shift = keyword.len().saturating_sub(self.name.len()) as u32;
+        let mut variant_range = snippet_range;
+        variant_range.start_byte = variant_range.start_byte.saturating_sub(shift);
+        variant_range.start.column = variant_range.start.column.saturating_sub(shift).max(1);
+        (variant, variant_range)
+    }
+
+    /// Removes the metavariable, along with the whitespace following it.
+    pub(crate) fn remove(&self, source: &str, snippet_range: Range) -> (String, Range) {
+        let rest = source[self.end..].trim_start();
+        let shift = (source.len() - rest.len() - self.start) as u32;
+        let variant = format!("{}{rest}", &source[..self.start]);
+        let mut variant_range = snippet_range;
+        variant_range.start_byte += shift;
+        variant_range.start.column += shift;
+        (variant, variant_range)
+    }
+
+    pub(crate) fn register(
+        &self,
+        snippet_range: Range,
+        context: &mut NodeCompilationContext,
+    ) -> Result<Option<Variable>> {
+        match self.name {
+            "$_" => Ok(None),
+            name => Ok(Some(register_variable(
+                name,
+                self.range(snippet_range),
+                context,
+            )?)),
+        }
+    }
+}
+
+/// Matches the node of the given sort, binding it to `var` if there is one.
+pub(crate) fn bound_node(sort: SortId, var: &Option<Variable>) -> Pattern<MarzanoQueryContext> {
+    let node = Pattern::AstNode(Box::new(ASTNode::new(sort, Vec::new())));
+    match var {
+        Some(var) => Pattern::And(Box::new(And::new(vec![
+            node,
+            Pattern::Variable(var.clone()),
+        ]))),
+        None => node,
+    }
+}
@@ -0,0 +1,318 @@
+use crate::{
+    pattern_compiler::{
+        compiler::NodeCompilationContext, snippet_compiler::dynamic_snippet_from_source,
+    },
+    problem::MarzanoQueryContext,
+    variables::register_variable,
+};
+use anyhow::{bail, Result};
+use grit_pattern_matcher::pattern::{
+    AstLeafNodePattern, DynamicPattern, Pattern, RegexLike, RegexPattern,
+};
+use grit_util::{ByteRange, Language, Range};
+use marzano_language::{
+    language::{LiteralPart, MarzanoLanguage},
+    target_language::TargetLanguage,
+};
+use std::borrow::Cow;
+
+/// A metavariable in a part of a literal which doesn't accept one, see
+/// `MarzanoLanguage::literal_parts()`.
+pub(crate) struct LiteralMetavariable {
+    /// The name of the metavariable, such as `$flags`.
+    name: String,
+    part: LiteralPart,
+    /// The text parsed in place of the metavariable.
+    placeholder: String,
+    /// Range of the metavariable within the snippet, including the brackets
+    /// of a bracketed metavariable.
+    range: ByteRange,
+}
+
+/// Text a number followed by a suffix starts with, as in `0x1fn`.
+const NUMBER_DIGITS_REGEX: &str = "[0-9][0-9a-fA-FxXoObB_]*";
+
+/// Replaces metavariables in parts of literals which only accept certain
+/// characters, such as the flags of `/$pattern/$flags` or the digits of the
+/// BigInt `$[n]n`, with literal text of the same length, so the snippet parses
+/// and offsets remain valid. Once compiled, `restore_literal_metavariables()`
+/// turns the placeholders back into the metavariables.
+pub(crate) fn mangle_literal_metavariables<'a>(
+    source: &'a str,
+    lang: &TargetLanguage,
+) -> (Cow<'a, str>, Vec<LiteralMetavariable>) {
+    let prefix = lang.metavariable_prefix();
+    let mut literals = Vec::new();
+    for part in lang.literal_parts() {
+        let found: Vec<(ByteRange, String)> = match part {
+            LiteralPart::RegexFlags => regex_flag_metavariables(source, prefix),
+            LiteralPart::NumberWithSuffix(suffix) => lang
+                .metavariable_bracket_regex()
+                .captures_iter(source)
+                .filter(|captures| captures.get(2).is_none())
+                .filter_map(|captures| {
+                    let whole = captures.get(0)?;
+                    let rest = source[whole.end()..].strip_prefix(suffix)?;
+                    if rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_') {
+                        return None;
+                    }
+                    Some((
+                        ByteRange::new(whole.start(), whole.end()),
+                        format!("{prefix}{}", &captures[1]),
+                    ))
+                })
+                .collect(),
+        };
+        for (range, name) in found {
+            // Flags are lowercase letters, and `z` isn't a flag.
+            let (digits, padding) = match part {
+                LiteralPart::RegexFlags => ("abcdefghijklmnopqrstuvwxy", 'z'),
+                LiteralPart::NumberWithSuffix(_) => ("012345678", '9'),
+            };
+            let placeholder =
+                literal_placeholder(literals.len(), range.end - range.start, digits, padding);
+            let Some(placeholder) = placeholder else {
+                continue;
+            };
+            literals.push(LiteralMetavariable {
+                name,
+                part: *part,
+                placeholder,
+                range,
+            });
+        }
+    }
+    if literals.is_empty() {
+        return (Cow::Borrowed(source), literals);
+    }
+    let mut mangled = source.to_string();
+    for literal in &literals {
+        mangled.replace_range(literal.range.start..literal.range.end, &literal.placeholder);
+    }
+    (Cow::Owned(mangled), literals)
+}
+
+/// The metavariables directly following a regex literal, as its flags, such
+/// as `$flags` in `/$pattern/$flags`.
+///
+/// A `/` starts a regex literal where an operand is expected, that is at the
+/// start of the snippet or after an operator or an opening bracket.
+fn regex_flag_metavariables(source: &str, prefix: &str) -> Vec<(ByteRange, String)> {
+    let bytes = source.as_bytes();
+    let mut found = Vec::new();
+    let mut quote = None;
+    let mut previous = None;
+    let mut index = 0;
+    while index < bytes.len() {
+        let c = bytes[index];
+        if let Some(q) = quote {
+            if c == b'\\' {
+                index += 1;
+            } else if c == q {
+                quote = None;
+                previous = Some(c);
+            }
+            index += 1;
+            continue;
+        }
+        match c {
+            b'"' | b'\'' | b'`' => quote = Some(c),
+            b'/' if previous.map_or(true, |p| b"(,=:[!&|?{};".contains(&p)) => {
+                if let Some(end) = regex_literal_end(&source[index..]) {
+                    let flags_start = index + end;
+                    let name_len = metavariable_name_len(&source[flags_start..], prefix);
+                    if name_len > 0 {
+                        let name_end = flags_start + name_len;
+                        found.push((
+                            ByteRange::new(flags_start, name_end),
+                            source[flags_start..name_end].to_string(),
+                        ));
+                    }
+                    index = flags_start + name_len;
+                    previous = Some(b'/');
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        if !c.is_ascii_whitespace() {
+            previous = Some(c);
+        }
+        index += 1;
+    }
+    found
+}
+
+/// The length of the regex literal `source` starts with, up to and including
+/// its closing `/`.
+fn regex_literal_end(source: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut in_class = false;
+    let mut index = 1;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 1,
+            b'\n' => return None,
+            b'[' => in_class = true,
+            b']' => in_class = false,
+            b'/' if !in_class => return (index > 1).then_some(index + 1),
+            _ => {}
+        }
+        index += 1;
+    }
+    None
+}
+
+/// The length of the metavariable `source` starts with, or 0 if it doesn't.
+fn metavariable_name_len(source: &str, prefix: &str) -> usize {
+    let Some(rest) = source.strip_prefix(prefix) else {
+        return 0;
+    };
+    if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return 0;
+    }
+    let len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    prefix.len() + len
+}
+
+/// Text of `width` characters standing for the literal metavariable at
+/// `index`: the index written with `digits`, padded on the left with
+/// `padding`, which isn't one of them. `None` if the index doesn't fit.
+fn literal_placeholder(index: usize, width: usize, digits: &str, padding: char) -> Option<String> {
+    let digits = digits.as_bytes();
+    let mut encoded = Vec::new();
+    let mut rest = index;
+    loop {
+        encoded.push(digits[rest % digits.len()] as char);
+        rest /= digits.len();
+        if rest == 0 {
+            break;
+        }
+    }
+    if encoded.len() >= width {
+        return None;
+    }
+    let padded = std::iter::repeat(padding)
+        .take(width - encoded.len())
+        .chain(encoded.into_iter().rev())
+        .collect();
+    Some(padded)
+}
+
+/// Turns the placeholders left by `mangle_literal_metavariables()` back into
+/// the metavariables they stand for, binding the part of the literal each
+/// replaced, and gives the snippet back its source.
+pub(crate) fn restore_literal_metavariables(
+    pattern: &mut Pattern<MarzanoQueryContext>,
+    source: &str,
+    literals: &[LiteralMetavariable],
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+) -> Result<()> {
+    if let Pattern::Where(where_) = pattern {
+        return restore_literal_metavariables(
+            &mut where_.pattern,
+            source,
+            literals,
+            snippet_range,
+            context,
+        );
+    }
+    let lang = context.compilation.lang;
+    let Pattern::CodeSnippet(snippet) = pattern else {
+        bail!(
+            "snippet `{source}` must parse as {} to bind the parts of its literals",
+            lang.language_name()
+        );
+    };
+    let range: ByteRange = snippet_range.into();
+    let underscore = format!("{}_", lang.metavariable_prefix());
+    let mut replacements = Vec::with_capacity(literals.len());
+    for literal in literals {
+        let variable = if literal.name == underscore {
+            None
+        } else {
+            let range = ByteRange::new(
+                range.start + literal.range.start,
+                range.start + literal.range.end,
+            );
+            Some(register_variable(&literal.name, range, context)?)
+        };
+        replacements.push(match literal.part {
+            LiteralPart::RegexFlags => (
+                literal.placeholder.clone(),
+                variable.map_or(Pattern::Underscore, Pattern::Variable),
+            ),
+            LiteralPart::NumberWithSuffix(suffix) => {
+                let digits = if variable.is_some() {
+                    format!("({NUMBER_DIGITS_REGEX})")
+                } else {
+                    NUMBER_DIGITS_REGEX.to_string()
+                };
+                let regex = format!("{digits}{}", regex::escape(suffix));
+                (
+                    format!("{}{suffix}", literal.placeholder),
+                    Pattern::Regex(Box::new(RegexPattern::new(
+                        RegexLike::Regex(regex),
+                        variable.into_iter().collect(),
+                    ))),
+                )
+            }
+        });
+    }
+    let mut replaced = vec![false; replacements.len()];
+    for (_, pattern) in snippet.patterns.iter_mut() {
+        replace_literal_placeholders(pattern, &replacements, &mut replaced);
+    }
+    if let Some(literal) = replaced
+        .iter()
+        .zip(literals)
+        .find_map(|(replaced, literal)| (!replaced).then_some(literal))
+    {
+        bail!(
+            "{} in snippet `{source}` must be part of a literal",
+            literal.name
+        );
+    }
+    snippet.source = source.to_string();
+    snippet.dynamic_snippet = dynamic_snippet_from_source(source, snippet_range, context)
+        .map_or(None, |s| Some(DynamicPattern::Snippet(s)));
+    Ok(())
+}
+
+fn replace_literal_placeholders(
+    pattern: &mut Pattern<MarzanoQueryContext>,
+    replacements: &[(String, Pattern<MarzanoQueryContext>)],
+    replaced: &mut [bool],
+) {
+    if let Pattern::AstLeafNode(leaf) = pattern {
+        let text = leaf.text();
+        if let Some(index) = replacements
+            .iter()
+            .position(|(placeholder, _)| text == Some(placeholder.as_str()))
+        {
+            replaced[index] = true;
+            *pattern = replacements[index].1.clone();
+        }
+    } else if let Pattern::AstNode(node) = pattern {
+        for (_, _, arg) in node.args.iter_mut() {
+            replace_literal_placeholders(arg, replacements, replaced);
+        }
+        if let Some(chain) = node.operator_chain.as_mut() {
+            replace_literal_placeholders(&mut chain.operands, replacements, replaced);
+        }
+        if let Some(child) = node.unfielded_child.as_mut() {
+            replace_literal_placeholders(child, replacements, replaced);
+        }
+    } else if let Pattern::List(list) = pattern {
+        for element in list.patterns.iter_mut() {
+            replace_literal_placeholders(element, replacements, replaced);
+        }
+    } else if let Pattern::And(and) = pattern {
+        for conjunct in and.patterns.iter_mut() {
+            replace_literal_placeholders(conjunct, replacements, replaced);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use super::{
+    leading_metavariable::{bound_node, LeadingMetavariable},
+    SnippetParses,
+};
+use crate::{
+    ast_node::ASTNode,
+    pattern_compiler::{
+        compiler::NodeCompilationContext, snippet_compiler::compile_snippet_content,
+    },
+    problem::MarzanoQueryContext,
+};
+use anyhow::Result;
+use grit_pattern_matcher::pattern::{And, Contains, Not, Or, Pattern};
+use grit_util::Range;
+use itertools::Itertools;
+use marzano_language::language::{nodes_from_indices, MarzanoLanguage};
+
+/// Compiles a snippet whose member modifiers are a metavariable, such as
+/// `$mod $name() {}`, binding the metavariable to the modifiers of the member.
+///
+/// Members without modifiers match as well, leaving the metavariable unbound.
+/// Returns `None` if the snippet parses as is, or doesn't parse as a member
+/// either.
+pub(crate) fn compile_member_modifiers_snippet(
+    source: &str,
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+    parses: &mut SnippetParses,
+) -> Result<Option<Pattern<MarzanoQueryContext>>> {
+    let lang = context.compilation.lang;
+    let Some(modifiers) = lang.member_modifiers().copied() else {
+        return Ok(None);
+    };
+    let Some(modifier) = LeadingMetavariable::find(source, lang, parses) else {
+        return Ok(None);
+    };
+    let (variant, variant_range) =
+        modifier.substitute(source, modifiers.placeholder, snippet_range);
+    let member_sorts = nodes_from_indices(parses.trees(&variant))
+        .iter()
+        .map(|node| node.node.kind_id())
+        .unique()
+        .map(|sort| Pattern::AstNode(Box::new(ASTNode::new(sort, Vec::new()))))
+        .collect_vec();
+    if member_sorts.is_empty() {
+        return Ok(None);
+    }
+    let mut member = compile_snippet_content(&variant, variant_range, context, false, parses)?;
+    // The placeholder only makes the snippet parse, it shouldn't constrain
+    // which modifiers match.
+    if let Pattern::CodeSnippet(snippet) = &mut member {
+        for (_, pattern) in snippet.patterns.iter_mut() {
+            if let Pattern::AstNode(node) = pattern {
+                node.args.retain(|(_, _, arg)| {
+                    !matches!(arg, Pattern::AstNode(arg) if arg.sort == modifiers.sort)
+                });
+            }
+        }
+    }
+
+    let var = modifier.register(snippet_range, context)?;
+    // Only look at the children of the member itself.
+    let until = Pattern::Not(Box::new(Not::new(Pattern::Or(Box::new(Or::new(
+        member_sorts,
+    ))))));
+    let mut alternatives = vec![Pattern::And(Box::new(And::new(vec![
+        member,
+        Pattern::Contains(Box::new(Contains::new(
+            bound_node(modifiers.sort, &var),
+            Some(until.clone()),
+        ))),
+    ])))];
+    let (bare, bare_range) = modifier.remove(source, snippet_range);
+    if parses.parses(&bare) {
+        let bare = compile_snippet_content(&bare, bare_range, context, false, parses)?;
+        let unmodified = Pattern::Not(Box::new(Not::new(Pattern::Contains(Box::new(
+            Contains::new(
+                Pattern::AstNode(Box::new(ASTNode::new(modifiers.sort, Vec::new()))),
+                Some(until),
+            ),
+        )))));
+        alternatives.push(Pattern::And(Box::new(And::new(vec![bare, unmodified]))));
+    }
+    Ok(Some(Pattern::Or(Box::new(Or::new(alternatives)))))
+}
@@ -0,0 +1,95 @@
+//! Rewrites of the source of a snippet, applied before it is parsed, which let
+//! snippets be written in ways the grammar of their language doesn't allow.
+
+mod alternation;
+mod ancestors;
+mod content_regexes;
+mod declaration_kind;
+mod documented;
+mod flags;
+mod fuzzy_identifiers;
+mod leading_metavariable;
+mod literal_metavariables;
+mod member_modifiers;
+mod named_spreads;
+mod siblings;
+mod unexported;
+
+pub(crate) use alternation::compile_alternation_snippet;
+pub(crate) use ancestors::{apply_ancestor_constraints, extract_ancestor_constraints};
+pub(crate) use content_regexes::extract_content_regexes;
+pub(crate) use declaration_kind::compile_declaration_kind_snippet;
+pub(crate) use documented::{compile_documented_snippet, leading_doc_comment_end};
+pub(crate) use flags::compile_flag_snippet;
+pub(crate) use fuzzy_identifiers::{edit_distance_constraints, extract_fuzzy_identifiers};
+pub(crate) use literal_metavariables::{
+    mangle_literal_metavariables, restore_literal_metavariables,
+};
+pub(crate) use member_modifiers::compile_member_modifiers_snippet;
+pub(crate) use named_spreads::{
+    is_named_spread_placeholder, mangle_named_spreads, restore_named_spreads,
+};
+pub(crate) use siblings::extract_sibling_constraints;
+pub(crate) use unexported::compile_unexported_snippet;
+
+use grit_util::{Language, SnippetTree};
+use marzano_language::{
+    language::{nodes_from_indices, MarzanoLanguage, Tree},
+    target_language::TargetLanguage,
+};
+use std::collections::HashMap;
+
+/// The parses of the sources a snippet goes through while it is compiled.
+///
+/// Preprocessors check whether the source they rewrite a snippet into parses
+/// before committing to it, and compiling that source parses it once more.
+/// Each distinct source is only parsed once, in every snippet context of the
+/// language, and every later request reuses the trees.
+pub(crate) struct SnippetParses<'a> {
+    lang: &'a TargetLanguage,
+    trees: HashMap<String, Vec<SnippetTree<Tree>>>,
+}
+
+impl<'a> SnippetParses<'a> {
+    pub(crate) fn new(lang: &'a TargetLanguage) -> Self {
+        Self {
+            lang,
+            trees: HashMap::new(),
+        }
+    }
+
+    /// The trees of `source` in each snippet context it parses in.
+    pub(crate) fn trees(&mut self, source: &str) -> &[SnippetTree<Tree>] {
+        let lang = self.lang;
+        self.trees
+            .entry(source.to_string())
+            .or_insert_with(|| lang.parse_snippet_contexts(source))
+    }
+
+    /// Whether `source` parses into any node.
+    pub(crate) fn parses(&mut self, source: &str) -> bool {
+        !nodes_from_indices(self.trees(source)).is_empty()
+    }
+
+    /// The number of snippet contexts sources have been parsed in so far.
+    pub(crate) fn parse_contexts(&self) -> usize {
+        self.trees.len() * self.lang.snippet_context_strings().len()
+    }
+}
+
+fn is_inside_quotes(source: &str, index: usize) -> bool {
+    let mut quote = None;
+    let mut chars = source[..index].chars();
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+            }
+        } else if matches!(c, '"' | '\'' | '`') {
+            quote = Some(c);
+        }
+    }
+    quote.is_some()
+}
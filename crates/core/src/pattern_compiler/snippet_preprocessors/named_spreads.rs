@@ -0,0 +1,201 @@
+use crate::{
+    pattern_compiler::compiler::{NodeCompilationContext, SpreadPreference},
+    problem::MarzanoQueryContext,
+    variables::register_variable,
+};
+use anyhow::{bail, Result};
+use grit_pattern_matcher::pattern::{Pattern, Spread, SpreadArity, Variable};
+use grit_util::{ByteRange, Language};
+use marzano_language::{language::SortId, target_language::TargetLanguage};
+use std::borrow::Cow;
+
+/// A named spread (`$...name`, or `$...name?` when lazy) found in a snippet.
+pub(crate) struct NamedSpread {
+    /// The placeholder variable the spread was replaced with for parsing.
+    placeholder: String,
+    name: String,
+    lazy: bool,
+    /// The bounds written after the name, such as `#2+`.
+    arity: Option<SpreadArity>,
+    /// Range of `$...name` within the snippet.
+    range: ByteRange,
+    /// Length of the `?` and arity following the name.
+    suffix_len: usize,
+}
+
+/// Parses the arity written after the name of a spread: `#2` for exactly
+/// two elements, `#2+` for at least two and `#2-` for at most two, along with
+/// its length.
+fn spread_arity(suffix: &str) -> Option<(SpreadArity, usize)> {
+    let digits = suffix.strip_prefix('#')?;
+    let count_len = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    let count = digits[..count_len].parse().ok()?;
+    let (arity, bound_len) = match digits[count_len..].chars().next() {
+        Some('+') => (
+            SpreadArity {
+                min: count,
+                max: None,
+            },
+            1,
+        ),
+        Some('-') => (
+            SpreadArity {
+                min: 0,
+                max: Some(count),
+            },
+            1,
+        ),
+        _ => (
+            SpreadArity {
+                min: count,
+                max: Some(count),
+            },
+            0,
+        ),
+    };
+    Some((arity, 1 + count_len + bound_len))
+}
+
+/// What the name of a named spread is prefixed with in its placeholder.
+const SPREAD_PLACEHOLDER_MARKER: &str = "___";
+
+/// Replaces named spreads with placeholder metavariables of the same length,
+/// so the snippet can be parsed like any other and offsets remain valid.
+///
+/// `$...name` becomes `$___name`, and the `?` marking a lazy spread as well as
+/// its arity are blanked out. Once compiled, `restore_named_spreads()` turns
+/// the placeholders back into spreads.
+pub(crate) fn mangle_named_spreads<'a>(
+    source: &'a str,
+    lang: &TargetLanguage,
+) -> (Cow<'a, str>, Vec<NamedSpread>) {
+    let prefix = lang.metavariable_prefix();
+    let marker = format!("{prefix}...");
+    let mut spreads = Vec::new();
+    for (start, _) in source.match_indices(&marker) {
+        let name_start = start + marker.len();
+        let name_len = source[name_start..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(source.len() - name_start);
+        let name = &source[name_start..name_start + name_len];
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            continue;
+        }
+        let end = name_start + name_len;
+        let lazy = source[end..].starts_with('?');
+        let arity_start = end + usize::from(lazy);
+        let (arity, arity_len) = match spread_arity(&source[arity_start..]) {
+            Some((arity, len)) => (Some(arity), len),
+            None => (None, 0),
+        };
+        spreads.push(NamedSpread {
+            placeholder: format!("{prefix}{SPREAD_PLACEHOLDER_MARKER}{name}"),
+            name: format!("{prefix}{name}"),
+            lazy,
+            arity,
+            range: ByteRange::new(start, end),
+            suffix_len: usize::from(lazy) + arity_len,
+        });
+    }
+    if spreads.is_empty() {
+        return (Cow::Borrowed(source), spreads);
+    }
+    let mut mangled = source.to_string();
+    for spread in &spreads {
+        let replacement = format!("{}{}", spread.placeholder, " ".repeat(spread.suffix_len));
+        mangled.replace_range(
+            spread.range.start..spread.range.end + spread.suffix_len,
+            &replacement,
+        );
+    }
+    (Cow::Owned(mangled), spreads)
+}
+
+/// Whether a metavariable is a placeholder left by `mangle_named_spreads()`.
+pub(crate) fn is_named_spread_placeholder(name: &str, lang: &TargetLanguage) -> bool {
+    name.strip_prefix(lang.metavariable_prefix())
+        .is_some_and(|name| name.starts_with(SPREAD_PLACEHOLDER_MARKER))
+}
+
+/// Turns the placeholders left by `mangle_named_spreads()` back into the
+/// variables they stand for: as `Pattern::Spread` when they are an element of
+/// a list, or as a plain variable binding the whole field otherwise.
+pub(crate) fn restore_named_spreads(
+    patterns: &mut [(SortId, Pattern<MarzanoQueryContext>)],
+    spreads: &[NamedSpread],
+    snippet_range: ByteRange,
+    context: &mut NodeCompilationContext,
+) -> Result<()> {
+    let mut replacements = Vec::with_capacity(spreads.len());
+    for spread in spreads {
+        let Some(index) = context.vars.get(&spread.placeholder).copied() else {
+            continue;
+        };
+        // The placeholder is an implementation detail, so don't report it.
+        context.vars_array[context.scope_index][index]
+            .locations
+            .clear();
+        let range = ByteRange::new(
+            snippet_range.start + spread.range.start,
+            snippet_range.start + spread.range.end,
+        );
+        let variable = register_variable(&spread.name, range, context)?;
+        let lazy = spread.lazy
+            || context.compilation.options.spread_preference == Some(SpreadPreference::Shortest);
+        let mut restored = Spread::new(variable, lazy);
+        if let Some(arity) = spread.arity {
+            restored = restored.with_arity(arity);
+        }
+        replacements.push((
+            Variable::new(context.scope_index, index),
+            restored,
+            spread.name.as_str(),
+        ));
+    }
+    for (_, pattern) in patterns.iter_mut() {
+        restore_spread_placeholders(pattern, &replacements)?;
+    }
+    Ok(())
+}
+
+fn restore_spread_placeholders(
+    pattern: &mut Pattern<MarzanoQueryContext>,
+    replacements: &[(Variable, Spread, &str)],
+) -> Result<()> {
+    let find = |variable: &Variable| {
+        replacements.iter().find(|(placeholder, _, _)| {
+            placeholder.scope == variable.scope && placeholder.index == variable.index
+        })
+    };
+    if let Pattern::AstNode(node) = pattern {
+        for (_, _, arg) in node.args.iter_mut() {
+            restore_spread_placeholders(arg, replacements)?;
+        }
+        if let Some(chain) = node.operator_chain.as_mut() {
+            restore_spread_placeholders(&mut chain.operands, replacements)?;
+        }
+        if let Some(child) = node.unfielded_child.as_mut() {
+            restore_spread_placeholders(child, replacements)?;
+        }
+    } else if let Pattern::List(list) = pattern {
+        for element in list.patterns.iter_mut() {
+            if let Pattern::Variable(variable) = element {
+                if let Some((_, spread, _)) = find(variable) {
+                    *element = Pattern::Spread(spread.clone());
+                    continue;
+                }
+            }
+            restore_spread_placeholders(element, replacements)?;
+        }
+    } else if let Pattern::Variable(variable) = pattern {
+        if let Some((_, spread, name)) = find(variable) {
+            if spread.arity.is_some() {
+                bail!("the arity of {name} can only be constrained among the elements of a list");
+            }
+            *variable = spread.variable;
+        }
+    }
+    Ok(())
+}
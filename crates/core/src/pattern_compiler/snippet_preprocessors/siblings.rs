@@ -0,0 +1,69 @@
+use super::is_inside_quotes;
+use crate::marzano_code_snippet::SiblingPosition;
+use anyhow::{bail, Result};
+use grit_util::Language;
+use marzano_language::target_language::TargetLanguage;
+use std::borrow::Cow;
+
+/// Blanks out the inline sibling position constraints of a snippet, `@first`,
+/// `@last` and `@nth(n)`, outside of string literals, and returns them along
+/// with the metavariable each follows.
+///
+/// As with ancestor constraints, a constraint applies to the metavariable it
+/// directly follows, or to the whole snippet when it comes last. Anywhere
+/// else the text is left alone, since it may be code such as a decorator.
+pub(crate) fn extract_sibling_constraints<'a>(
+    source: &'a str,
+    lang: &TargetLanguage,
+) -> Result<(Cow<'a, str>, Vec<(Option<String>, SiblingPosition)>)> {
+    if !source.contains('@') {
+        return Ok((Cow::Borrowed(source), Vec::new()));
+    }
+    let mut blanked = source.to_string();
+    let mut constraints = Vec::new();
+    for (start, _) in source.match_indices('@') {
+        if is_inside_quotes(source, start) {
+            continue;
+        }
+        let marker = &source[start + 1..];
+        let word_len = marker
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(marker.len());
+        let (position, marker_len) = match &marker[..word_len] {
+            "first" => (SiblingPosition::First, word_len),
+            "last" => (SiblingPosition::Last, word_len),
+            "nth" => {
+                let Some(argument_len) = marker[word_len..]
+                    .strip_prefix('(')
+                    .and_then(|arguments| arguments.find(')'))
+                else {
+                    bail!("unterminated sibling constraint in snippet `{source}`");
+                };
+                let argument = marker[word_len + 1..word_len + 1 + argument_len].trim();
+                let Some(n) = argument.parse().ok().filter(|n| *n > 0) else {
+                    bail!("invalid position `{argument}` in sibling constraint of snippet `{source}`, positions start at 1");
+                };
+                (SiblingPosition::Nth(n), word_len + argument_len + 2)
+            }
+            _ => continue,
+        };
+        let end = start + 1 + marker_len;
+        let preceding = source[..start].trim_end();
+        let variable = lang
+            .metavariable_regex()
+            .find_iter(preceding)
+            .last()
+            .filter(|m| m.end() == preceding.len())
+            .map(|m| m.as_str().to_string());
+        let rest = source[end..].trim_start();
+        if variable.is_none() && !rest.is_empty() && !rest.starts_with('@') {
+            continue;
+        }
+        blanked.replace_range(start..end, &" ".repeat(end - start));
+        constraints.push((variable, position));
+    }
+    if constraints.is_empty() {
+        return Ok((Cow::Borrowed(source), constraints));
+    }
+    Ok((Cow::Owned(blanked), constraints))
+}
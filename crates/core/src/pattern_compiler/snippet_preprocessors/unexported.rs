@@ -0,0 +1,57 @@
+use super::SnippetParses;
+use crate::{
+    ast_node::ASTNode,
+    pattern_compiler::{
+        compiler::NodeCompilationContext, snippet_compiler::compile_snippet_content,
+    },
+    problem::MarzanoQueryContext,
+};
+use anyhow::Result;
+use grit_pattern_matcher::pattern::{And, Not, Pattern, Within};
+use grit_util::Range;
+use marzano_language::language::MarzanoLanguage;
+
+/// Compiles a snippet starting with `!export`, such as
+/// `!export function $f() {}`, which only matches declarations that aren't
+/// the declaration of an export statement.
+///
+/// Returns `None` if the snippet doesn't start with `!export`.
+pub(crate) fn compile_unexported_snippet(
+    source: &str,
+    snippet_range: Range,
+    context: &mut NodeCompilationContext,
+    parses: &mut SnippetParses,
+) -> Result<Option<Pattern<MarzanoQueryContext>>> {
+    const MARKER: &str = "!export";
+    let Some((export_sort, declaration_field)) = context.compilation.lang.export_declaration()
+    else {
+        return Ok(None);
+    };
+    let start = source.len() - source.trim_start().len();
+    let is_unexported = source[start..]
+        .strip_prefix(MARKER)
+        .is_some_and(|rest| rest.starts_with(char::is_whitespace));
+    if !is_unexported {
+        return Ok(None);
+    }
+    // Blanking the marker keeps the offsets of the rest of the snippet.
+    let blanked = format!(
+        "{}{}{}",
+        &source[..start],
+        " ".repeat(MARKER.len()),
+        &source[start + MARKER.len()..]
+    );
+    let declaration = compile_snippet_content(&blanked, snippet_range, context, false, parses)?;
+    // The metavariables of the declaration are bound by the time the export
+    // statement is matched, so only an export of this very declaration matches.
+    let export = ASTNode::new(
+        export_sort,
+        vec![(declaration_field, false, declaration.clone())],
+    );
+    Ok(Some(Pattern::And(Box::new(And::new(vec![
+        declaration,
+        Pattern::Not(Box::new(Not::new(Pattern::Within(Box::new(Within::new(
+            Pattern::AstNode(Box::new(export)),
+        )))))),
+    ])))))
+}
@@ -73,6 +73,7 @@ impl NodeCompiler for StepCompiler {
             | Pattern::Divide(_)
             | Pattern::Modulo(_)
             | Pattern::Dots
+            | Pattern::Spread(_)
             | Pattern::Like(_) => {
                 let range = node.range();
                 let log = AnalysisLogBuilder::default()
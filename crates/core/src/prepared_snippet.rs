@@ -0,0 +1,342 @@
+//! Snippets prepared for matching many nodes, see [`MarzanoCodeSnippet::prepare`].
+
+use crate::{
+    marzano_code_snippet::MarzanoCodeSnippet, marzano_context::MarzanoContext,
+    marzano_resolved_pattern::MarzanoResolvedPattern, problem::MarzanoQueryContext,
+    problem::Problem,
+};
+use grit_pattern_matcher::{
+    file_owners::FileOwners,
+    pattern::{Pattern, ResolvedPattern, State},
+};
+use grit_util::{traverse, AstNode, Order};
+use marzano_language::{language::MarzanoLanguage, target_language::TargetLanguage};
+use marzano_util::{node_with_source::NodeWithSource, runtime::ExecutionContext};
+use std::collections::BTreeMap;
+
+/// The text bound to each metavariable of a match, keyed by name.
+pub type Bindings = BTreeMap<String, String>;
+
+/// A snippet with the lookups matching does for every node computed once,
+/// for matching it against many nodes in a row.
+pub struct PreparedMatcher<'a> {
+    snippet: &'a MarzanoCodeSnippet,
+    problem: &'a Problem,
+    /// The pattern for each sort, indexed by sort.
+    patterns: Vec<Option<&'a Pattern<MarzanoQueryContext>>>,
+    /// Whether each sort is transparent, indexed by sort.
+    transparent: Vec<bool>,
+    execution_context: ExecutionContext,
+}
+
+impl MarzanoCodeSnippet {
+    /// Prepares the snippet for matching nodes one at a time. The snippet must
+    /// be part of `problem`, which provides its language and metavariables.
+    pub fn prepare<'a>(&'a self, problem: &'a Problem) -> PreparedMatcher<'a> {
+        let transparent_sorts = problem.language.transparent_sorts();
+        let len = self
+            .patterns
+            .iter()
+            .map(|(sort, _)| *sort)
+            .chain(transparent_sorts.iter().copied())
+            .max()
+            .map_or(0, |sort| sort as usize + 1);
+        let mut patterns = vec![None; len];
+        for (sort, pattern) in &self.patterns {
+            patterns[*sort as usize].get_or_insert(pattern);
+        }
+        let mut transparent = vec![false; len];
+        for sort in transparent_sorts {
+            transparent[*sort as usize] = true;
+        }
+        PreparedMatcher {
+            snippet: self,
+            problem,
+            patterns,
+            transparent,
+            execution_context: ExecutionContext::default(),
+        }
+    }
+}
+
+impl<'a> PreparedMatcher<'a> {
+    /// Matches the snippet against `node` alone, the same way it is matched
+    /// while running its problem, returning the bound metavariables if it
+    /// matched.
+    ///
+    /// Conditions outside the snippet, such as a `where` clause, are not
+    /// checked.
+    pub fn matches(&self, node: &NodeWithSource) -> Option<Bindings> {
+        let owned_files = FileOwners::new();
+        let (state, context) = self
+            .problem
+            .get_context(&self.execution_context, &owned_files);
+        self.match_node(node, &state, &context)
+    }
+
+    /// Matches the snippet against `root` and each of its descendants, like
+    /// [`Self::matches`], returning the bindings of every match in document
+    /// order.
+    ///
+    /// The state matching starts from is built once, rather than for every
+    /// node.
+    pub fn matches_under(&self, root: &NodeWithSource) -> Vec<Bindings> {
+        let owned_files = FileOwners::new();
+        let (state, context) = self
+            .problem
+            .get_context(&self.execution_context, &owned_files);
+        traverse(root.walk(), Order::Pre)
+            .filter_map(|node| self.match_node(&node, &state, &context))
+            .collect()
+    }
+
+    /// Matches `node`, starting from a copy of `initial`.
+    fn match_node<'b>(
+        &'b self,
+        node: &NodeWithSource<'b>,
+        initial: &State<'b, MarzanoQueryContext>,
+        context: &'b MarzanoContext<'b>,
+    ) -> Option<Bindings> {
+        let mut state = initial.clone();
+        let resolved = MarzanoResolvedPattern::from_node_binding(node.clone());
+        let matched = self
+            .snippet
+            .execute_node(
+                node.clone(),
+                &resolved,
+                &mut state,
+                context,
+                &mut vec![].into(),
+                |sort| self.patterns.get(sort as usize).copied().flatten(),
+                |sort| {
+                    self.transparent
+                        .get(sort as usize)
+                        .copied()
+                        .unwrap_or(false)
+                },
+            )
+            .ok()?;
+        if !matched {
+            return None;
+        }
+        bound_metavariables(&state, &self.problem.language)
+    }
+}
+
+/// Matches `inner` within every node `outer` matches under `root`, the way
+/// `` `outer` as $outer where { $outer <: contains `inner` } `` would,
+/// returning the bindings of both snippets for each pair of matches.
+///
+/// A metavariable both snippets use has to bind the same text in each.
+pub fn match_within(
+    outer: &PreparedMatcher,
+    inner: &PreparedMatcher,
+    root: &NodeWithSource,
+) -> Vec<Bindings> {
+    let outer_files = FileOwners::new();
+    let (outer_state, outer_context) = outer
+        .problem
+        .get_context(&outer.execution_context, &outer_files);
+    let inner_files = FileOwners::new();
+    let (inner_state, inner_context) = inner
+        .problem
+        .get_context(&inner.execution_context, &inner_files);
+    let mut matches = Vec::new();
+    for node in traverse(root.walk(), Order::Pre) {
+        let Some(outer_bindings) = outer.match_node(&node, &outer_state, &outer_context) else {
+            continue;
+        };
+        for descendant in traverse(node.walk(), Order::Pre) {
+            let Some(inner_bindings) = inner.match_node(&descendant, &inner_state, &inner_context)
+            else {
+                continue;
+            };
+            let mut bindings = outer_bindings.clone();
+            let consistent = inner_bindings.into_iter().all(|(name, text)| {
+                match bindings.insert(name, text.clone()) {
+                    Some(previous) => previous == text,
+                    None => true,
+                }
+            });
+            if consistent {
+                matches.push(bindings);
+            }
+        }
+    }
+    matches
+}
+
+fn bound_metavariables(
+    state: &State<MarzanoQueryContext>,
+    language: &TargetLanguage,
+) -> Option<Bindings> {
+    let mut bindings = Bindings::new();
+    for contents in state.bindings.iter().filter_map(|scope| scope.last()) {
+        for content in contents {
+            if let Some(value) = &content.value {
+                let text = value.text(&state.files, language).ok()?;
+                bindings.insert(content.name.clone(), text.into_owned());
+            }
+        }
+    }
+    Some(bindings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_compiler::src_to_problem;
+    use grit_pattern_matcher::pattern::{Matcher, PatternOrPredicate};
+    use grit_util::{Ast, FileOrigin};
+
+    const SOURCE: &str = "console.log(a);\nconsole.warn(b);\nfoo(console.log(c, d));\nx.log(e);\n";
+
+    fn first_snippet(problem: &Problem) -> &MarzanoCodeSnippet {
+        problem
+            .pattern
+            .iter()
+            .find_map(|pattern| {
+                if let PatternOrPredicate::Pattern(Pattern::CodeSnippet(snippet)) = pattern {
+                    Some(snippet)
+                } else {
+                    None
+                }
+            })
+            .unwrap()
+    }
+
+    /// Matches the snippet against every node of `source` directly, as well
+    /// as with the prepared matcher.
+    fn match_both_ways(problem: &Problem, source: &str) -> (Vec<Bindings>, Vec<Bindings>) {
+        let snippet = first_snippet(problem);
+        let tree = problem
+            .language
+            .get_parser()
+            .parse_file(source, None, &mut vec![].into(), FileOrigin::Fresh)
+            .unwrap();
+        let matcher = snippet.prepare(problem);
+        let mut direct = Vec::new();
+        let mut prepared = Vec::new();
+        for node in traverse(tree.root_node().walk(), Order::Pre) {
+            let owned_files = FileOwners::new();
+            let execution_context = ExecutionContext::default();
+            let (mut state, context) = problem.get_context(&execution_context, &owned_files);
+            let resolved = MarzanoResolvedPattern::from_node_binding(node.clone());
+            if snippet
+                .execute(&resolved, &mut state, &context, &mut vec![].into())
+                .unwrap()
+            {
+                direct.extend(bound_metavariables(&state, &problem.language));
+            }
+            prepared.extend(matcher.matches(&node));
+        }
+        assert_eq!(matcher.matches_under(&tree.root_node()), prepared);
+        (direct, prepared)
+    }
+
+    #[test]
+    fn prepared_and_direct_matching_agree() {
+        let problem = src_to_problem(
+            "`console.log($message, $...)`".to_string(),
+            TargetLanguage::default(),
+        )
+        .unwrap();
+        let (direct, prepared) = match_both_ways(&problem, SOURCE);
+        assert_eq!(direct, prepared);
+        assert_eq!(
+            prepared
+                .iter()
+                .map(|bindings| bindings["$message"].as_str())
+                .collect::<Vec<_>>(),
+            ["a", "c"]
+        );
+        let matcher = first_snippet(&problem).prepare(&problem);
+        assert!(first_snippet(&problem)
+            .patterns
+            .iter()
+            .all(|(sort, _)| matcher.patterns[*sort as usize].is_some()));
+    }
+
+    #[test]
+    fn prepared_matching_binds_every_metavariable() {
+        let problem =
+            src_to_problem("`$obj.log($arg)`".to_string(), TargetLanguage::default()).unwrap();
+        let (direct, prepared) = match_both_ways(&problem, SOURCE);
+        assert_eq!(direct, prepared);
+        assert_eq!(
+            prepared,
+            [
+                Bindings::from([
+                    ("$arg".to_string(), "a".to_string()),
+                    ("$obj".to_string(), "console".to_string()),
+                ]),
+                Bindings::from([
+                    ("$arg".to_string(), "e".to_string()),
+                    ("$obj".to_string(), "x".to_string()),
+                ]),
+            ]
+        );
+    }
+
+    fn match_within_source(outer: &str, inner: &str, source: &str) -> Vec<Bindings> {
+        let outer = src_to_problem(format!("`{outer}`"), TargetLanguage::default()).unwrap();
+        let inner = src_to_problem(format!("`{inner}`"), TargetLanguage::default()).unwrap();
+        let tree = outer
+            .language
+            .get_parser()
+            .parse_file(source, None, &mut vec![].into(), FileOrigin::Fresh)
+            .unwrap();
+        match_within(
+            &first_snippet(&outer).prepare(&outer),
+            &first_snippet(&inner).prepare(&inner),
+            &tree.root_node(),
+        )
+    }
+
+    #[test]
+    fn match_within_finds_logs_only_inside_debug_functions() {
+        let source = r#"
+function debug() {
+  console.log(a);
+}
+function run() {
+  console.log(b);
+}
+function debug(level) {
+  if (level) {
+    console.log(c, d);
+  }
+}
+console.log(e);
+"#;
+        let matches = match_within_source(
+            "function debug($...) { $... }",
+            "console.log($message, $...)",
+            source,
+        );
+        assert_eq!(
+            matches
+                .iter()
+                .map(|bindings| bindings["$message"].as_str())
+                .collect::<Vec<_>>(),
+            ["a", "c"]
+        );
+    }
+
+    #[test]
+    fn match_within_combines_the_bindings_of_both_snippets() {
+        let source = "function show(x) { console.log(x); console.log(y); }\n";
+        let matches = match_within_source(
+            "function $name($param) { $... }",
+            "console.log($param)",
+            source,
+        );
+        assert_eq!(
+            matches,
+            [Bindings::from([
+                ("$name".to_string(), "show".to_string()),
+                ("$param".to_string(), "x".to_string()),
+            ])]
+        );
+    }
+}
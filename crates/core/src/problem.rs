@@ -16,7 +16,7 @@ use grit_pattern_matcher::{
     file_owners::FileOwners,
     pattern::{
         FilePtr, FileRegistry, GritFunctionDefinition, Matcher, Pattern, PatternDefinition,
-        PredicateDefinition, ResolvedPattern, State, VariableContent,
+        PatternOrPredicate, PredicateDefinition, ResolvedPattern, State, VariableContent,
     },
 };
 use grit_util::VariableMatch;
@@ -30,14 +30,18 @@ use marzano_util::{
     rich_path::{LoadableFile, RichFile, RichPath},
     runtime::ExecutionContext,
 };
+use rayon::iter::IndexedParallelIterator;
 use rayon::iter::IntoParallelIterator;
 use rayon::iter::ParallelIterator;
 use sha2::{Digest, Sha256};
 
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     path::PathBuf,
-    sync::mpsc::{self, Sender},
+    sync::{
+        mpsc::{self, Sender},
+        Mutex,
+    },
 };
 use std::{fmt::Debug, str::FromStr};
 use tracing::{event, Level};
@@ -65,6 +69,27 @@ impl Problem {
     pub fn compiled_vars(&self) -> Vec<VariableMatch> {
         self.variables.compiled_vars(&self.tree.source)
     }
+
+    /// Calls `f` with every code snippet of the problem, including the ones
+    /// in the definitions it declares, whether or not they are called.
+    ///
+    /// The right-hand sides of rewrites are templates rather than snippets,
+    /// and aren't visited.
+    pub fn walk_snippets(&self, mut f: impl FnMut(&MarzanoCodeSnippet)) {
+        let roots = std::iter::once(self.pattern.iter())
+            .chain(self.pattern_definitions.iter().map(|d| d.pattern.iter()))
+            .chain(
+                self.predicate_definitions
+                    .iter()
+                    .map(|d| d.predicate.iter()),
+            )
+            .chain(self.function_definitions.iter().map(|d| d.function.iter()));
+        for pattern in roots.flatten() {
+            if let PatternOrPredicate::Pattern(Pattern::CodeSnippet(snippet)) = pattern {
+                f(snippet);
+            }
+        }
+    }
 }
 
 enum FilePattern {
@@ -101,6 +126,26 @@ fn send(tx: &Sender<Vec<MatchResult>>, value: Vec<MatchResult>) {
     }
 }
 
+/// Holds back the results of files executed in parallel until the results of
+/// every file before them have been sent.
+#[derive(Default)]
+struct InOrderResults {
+    next: usize,
+    pending: BTreeMap<usize, Vec<MatchResult>>,
+}
+
+impl InOrderResults {
+    fn send(&mut self, tx: &Sender<Vec<MatchResult>>, index: usize, results: Vec<MatchResult>) {
+        self.pending.insert(index, results);
+        while let Some(results) = self.pending.remove(&self.next) {
+            if !results.is_empty() {
+                send(tx, results);
+            }
+            self.next += 1;
+        }
+    }
+}
+
 impl Problem {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
@@ -156,6 +201,10 @@ impl Problem {
         context: &ExecutionContext,
         cache: &impl GritCache,
     ) {
+        // Files which weren't started before matching was cancelled are skipped.
+        if context.is_cancelled() {
+            return;
+        }
         let owned_files = FileOwners::new();
         if !self.is_multifile && files.len() != 1 {
             let results = vec![MatchResult::AnalysisLog(AnalysisLog::floating_error(
@@ -166,7 +215,9 @@ impl Problem {
         }
         let mut file_pointers: Vec<FilePtr> = Vec::new();
 
-        let mut done_files: HashMap<String, DoneFile> = HashMap::new();
+        // Ordered by path, so the done files are appended in the same order
+        // however the files were found.
+        let mut done_files: BTreeMap<String, DoneFile> = BTreeMap::new();
 
         for (index, file) in files.iter().enumerate() {
             let path = file.name();
@@ -213,7 +264,7 @@ impl Problem {
         binding: FilePattern,
         owned_files: &FileOwners<Tree>,
         context: &ExecutionContext,
-        mut done_files: HashMap<String, DoneFile>,
+        mut done_files: BTreeMap<String, DoneFile>,
     ) {
         let file_names: Vec<PathBuf> = files
             .iter()
@@ -266,6 +317,29 @@ impl Problem {
         send(tx, outputs);
     }
 
+    /// Executes the pattern against all `files` together.
+    ///
+    /// For multifile patterns this is the driver for coordinated rewrites: the
+    /// steps of a `multifile` block run in order over every file, sharing one
+    /// state. Metavariables named `$GLOBAL_...` (or `^GLOBAL_...` in languages
+    /// using the `^` sigil) live in the global scope, so a value bound by a
+    /// snippet in one file is visible to the snippets matching another file,
+    /// while `bubble` keeps everything else local to each file:
+    ///
+    /// ```grit
+    /// multifile {
+    ///     bubble file($name, $body) where {
+    ///         $name <: includes "lib.js",
+    ///         $body <: contains `export function $GLOBAL_old($_) { $_ }` => ...
+    ///     },
+    ///     bubble file($name, $body) where $body <: contains `$GLOBAL_old($args)` => ...
+    /// }
+    /// ```
+    ///
+    /// Results are sorted, so they are identical across runs and platforms
+    /// regardless of the order of `files` or how their execution was scheduled.
+    /// Within a file, the ranges of a match are in the order the pattern bound
+    /// them, which for `contains` is the order of the nodes in the file.
     pub fn execute_files(
         &self,
         files: Vec<RichFile>,
@@ -282,6 +356,9 @@ impl Problem {
         results
     }
 
+    /// Sends the results of each file in the order of `files`, as soon as it
+    /// and every file before it are done, so the stream is identical across
+    /// runs even though the files are executed in parallel.
     pub fn execute_files_streaming(
         &self,
         files: Vec<RichFile>,
@@ -338,6 +415,9 @@ impl Problem {
         results
     }
 
+    /// Sends the results of each file in the order of `files`, as soon as it
+    /// and every file before it are done, so the stream is identical across
+    /// runs even though the files are executed in parallel.
     pub fn execute_paths_streaming(
         &self,
         files: Vec<PathBuf>,
@@ -364,6 +444,7 @@ impl Problem {
         if self.is_multifile {
             self.build_and_execute_resolved_pattern(&tx, files, context, &NullCache::new());
         } else {
+            let in_order = Mutex::new(InOrderResults::default());
             rayon::scope(|s| {
                 #[cfg(feature = "grit_tracing")]
                 let grouped_ctx = parent_cx;
@@ -376,16 +457,24 @@ impl Problem {
 
                     event!(Level::INFO, "spawn execute_shared_body");
 
-                    files.into_par_iter().for_each_with(tx, |sender, f| {
-                        let vec = vec![f];
-                        self.build_and_execute_resolved_pattern(sender, vec, context, cache);
-                    });
+                    files
+                        .into_par_iter()
+                        .enumerate()
+                        .for_each_with(tx, |sender, (index, f)| {
+                            let vec = vec![f];
+                            let (file_tx, file_rx) = mpsc::channel::<Vec<MatchResult>>();
+                            self.build_and_execute_resolved_pattern(&file_tx, vec, context, cache);
+                            drop(file_tx);
+                            let results = file_rx.into_iter().flatten().collect();
+                            in_order.lock().unwrap().send(sender, index, results);
+                        });
                 })
             })
         }
     }
 
-    /// Construct a context, only for testing
+    /// Construct a context without any files, for matching patterns against
+    /// nodes directly
     pub fn get_context<'a>(
         &'a self,
         context: &'a ExecutionContext,
@@ -474,7 +563,12 @@ impl Problem {
             .execute(&binding, &mut state, &context, &mut user_logs)?
         {
             for file in state.files.files() {
-                if let Some(result) = MatchResult::file_to_match_result(file, &self.language)? {
+                if let Some(mut result) = MatchResult::file_to_match_result(file, &self.language)? {
+                    if context.runtime.utf16_ranges {
+                        if let Some(original) = file.front() {
+                            result.to_utf16_ranges(&original.tree.source);
+                        }
+                    }
                     results.push(result)
                 }
             }
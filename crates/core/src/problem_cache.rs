@@ -0,0 +1,284 @@
+use crate::{
+    built_in_functions::BuiltIns, foreign_function_definition::ForeignFunctionDefinition,
+    pattern_compiler::compiler::VariableLocations, problem::MarzanoQueryContext, problem::Problem,
+};
+use anyhow::{Context, Result};
+use grit_pattern_matcher::{
+    constants::DEFAULT_FILE_NAME,
+    pattern::{GritFunctionDefinition, Pattern, PatternDefinition, PredicateDefinition},
+};
+use grit_util::Language;
+use marzano_language::{
+    grit_parser::MarzanoGritParser,
+    language::{GrammarVersion, MarzanoLanguage, Tree},
+    target_language::TargetLanguage,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// Bumped whenever the layout of the serialized pattern types changes in a
+/// way the crate version alone wouldn't reflect.
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+/// Identifies a compiled problem by everything its compilation depends on:
+/// the pattern and library sources, the target language and its grammar, and
+/// the version of the compiler itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheKey(String);
+
+impl CacheKey {
+    pub fn new(src: &str, libs: &BTreeMap<String, String>, language: &TargetLanguage) -> Self {
+        Self::for_grammar(
+            src,
+            libs,
+            language.language_name(),
+            language.grammar_version(),
+        )
+    }
+
+    fn for_grammar(
+        src: &str,
+        libs: &BTreeMap<String, String>,
+        language_name: &str,
+        grammar: GrammarVersion,
+    ) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(CACHE_FORMAT_VERSION.to_le_bytes());
+        hasher.update(env!("CARGO_PKG_VERSION"));
+        hasher.update(language_name);
+        // Sort ids are baked into compiled patterns, so any change to the
+        // grammar has to invalidate them.
+        hasher.update(grammar.to_string());
+        hasher.update(src.len().to_le_bytes());
+        hasher.update(src);
+        for (name, body) in libs {
+            hasher.update(name.len().to_le_bytes());
+            hasher.update(name);
+            hasher.update(body.len().to_le_bytes());
+            hasher.update(body);
+        }
+        let digest = hasher.finalize();
+        let mut key = String::with_capacity(digest.len() * 2);
+        for byte in digest {
+            let _ = write!(key, "{:02x}", byte);
+        }
+        Self(key)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Every entry starts with its key, so stale entries are rejected without
+/// decoding patterns whose layout may have changed.
+#[derive(Deserialize)]
+struct CachedKey {
+    key: CacheKey,
+}
+
+#[derive(Serialize)]
+struct CachedProblemRef<'a> {
+    key: &'a CacheKey,
+    pattern: &'a Pattern<MarzanoQueryContext>,
+    is_multifile: bool,
+    has_limit: bool,
+    hash: [u8; 32],
+    name: &'a Option<String>,
+    variables: &'a VariableLocations,
+    pattern_definitions: &'a [PatternDefinition<MarzanoQueryContext>],
+    predicate_definitions: &'a [PredicateDefinition<MarzanoQueryContext>],
+    function_definitions: &'a [GritFunctionDefinition<MarzanoQueryContext>],
+    foreign_function_definitions: &'a [ForeignFunctionDefinition],
+}
+
+#[derive(Deserialize)]
+struct CachedProblem {
+    key: CacheKey,
+    pattern: Pattern<MarzanoQueryContext>,
+    is_multifile: bool,
+    has_limit: bool,
+    hash: [u8; 32],
+    name: Option<String>,
+    variables: VariableLocations,
+    pattern_definitions: Vec<PatternDefinition<MarzanoQueryContext>>,
+    predicate_definitions: Vec<PredicateDefinition<MarzanoQueryContext>>,
+    function_definitions: Vec<GritFunctionDefinition<MarzanoQueryContext>>,
+    foreign_function_definitions: Vec<ForeignFunctionDefinition>,
+}
+
+/// Serializes a compiled problem, so it can later be restored with
+/// [`deserialize_problem`] without recompiling its snippets.
+///
+/// `src` and `libs` must be the sources the problem was compiled from.
+pub fn serialize_problem(
+    problem: &Problem,
+    src: &str,
+    libs: &BTreeMap<String, String>,
+) -> Result<Vec<u8>> {
+    let key = CacheKey::new(src, libs, &problem.language);
+    let cached = CachedProblemRef {
+        key: &key,
+        pattern: &problem.pattern,
+        is_multifile: problem.is_multifile,
+        has_limit: problem.has_limit,
+        hash: problem.hash,
+        name: &problem.name,
+        variables: &problem.variables,
+        pattern_definitions: &problem.pattern_definitions,
+        predicate_definitions: &problem.predicate_definitions,
+        function_definitions: &problem.function_definitions,
+        foreign_function_definitions: &problem.foreign_function_definitions,
+    };
+    Ok(bincode::serialize(&cached)?)
+}
+
+/// Restores a problem serialized by [`serialize_problem`].
+///
+/// Returns `None` if the bytes were produced from different sources, for a
+/// different language, or by a different version of the compiler, in which
+/// case the pattern needs to be compiled again.
+///
+/// Only the pattern source is parsed again, its snippets are not. Problems
+/// are restored with the default built-in functions.
+pub fn deserialize_problem(
+    bytes: &[u8],
+    src: &str,
+    libs: &BTreeMap<String, String>,
+    default_lang: TargetLanguage,
+) -> Result<Option<Problem>> {
+    let (tree, language) = parse_pattern(src, default_lang)?;
+    let key = CacheKey::new(src, libs, &language);
+    restore_problem(bytes, &key, tree, language)
+}
+
+/// Parses the pattern source, which is still needed for the problem's tree
+/// and to determine the language the pattern targets.
+fn parse_pattern(src: &str, default_lang: TargetLanguage) -> Result<(Tree, TargetLanguage)> {
+    let mut parser = MarzanoGritParser::new()?;
+    let tree = parser.parse_file(src, Some(Path::new(DEFAULT_FILE_NAME)))?;
+    let language = TargetLanguage::from_tree(&tree).unwrap_or(default_lang);
+    Ok((tree, language))
+}
+
+fn restore_problem(
+    bytes: &[u8],
+    key: &CacheKey,
+    tree: Tree,
+    language: TargetLanguage,
+) -> Result<Option<Problem>> {
+    match bincode::deserialize::<CachedKey>(bytes) {
+        Ok(cached) if cached.key == *key => {}
+        _ => return Ok(None),
+    }
+    let cached: CachedProblem = bincode::deserialize(bytes)?;
+
+    Ok(Some(Problem {
+        tree,
+        pattern: cached.pattern,
+        language,
+        built_ins: BuiltIns::get_built_in_functions(),
+        is_multifile: cached.is_multifile,
+        has_limit: cached.has_limit,
+        hash: cached.hash,
+        name: cached.name,
+        variables: cached.variables,
+        pattern_definitions: cached.pattern_definitions,
+        predicate_definitions: cached.predicate_definitions,
+        function_definitions: cached.function_definitions,
+        foreign_function_definitions: cached.foreign_function_definitions,
+    }))
+}
+
+/// A directory of serialized problems, one file per [`CacheKey`].
+///
+/// Entries are only valid for problems compiled with the default
+/// compilation options and built-ins, so callers that customize either
+/// should use a separate directory.
+#[derive(Debug, Clone)]
+pub struct ProblemCache {
+    dir: PathBuf,
+}
+
+impl ProblemCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.bin", key.as_str()))
+    }
+
+    /// Loads the problem compiled from `src` and `libs`, if it is cached and
+    /// still valid.
+    pub fn load(
+        &self,
+        src: &str,
+        libs: &BTreeMap<String, String>,
+        default_lang: TargetLanguage,
+    ) -> Result<Option<Problem>> {
+        let (tree, language) = parse_pattern(src, default_lang)?;
+        let key = CacheKey::new(src, libs, &language);
+        let Ok(bytes) = fs_err::read(self.entry_path(&key)) else {
+            return Ok(None);
+        };
+        restore_problem(&bytes, &key, tree, language)
+    }
+
+    pub fn store(
+        &self,
+        problem: &Problem,
+        src: &str,
+        libs: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        let bytes = serialize_problem(problem, src, libs)?;
+        let path = self.entry_path(&CacheKey::new(src, libs, &problem.language));
+        fs_err::create_dir_all(&self.dir)?;
+        fs_err::write(&path, bytes)
+            .with_context(|| format!("Failed to write problem cache entry {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_compiler::src_to_problem;
+    use marzano_language::target_language::PatternLanguage;
+
+    #[test]
+    fn grammar_changes_invalidate_cached_problems() {
+        let src = "`console.log($message)`";
+        let libs = BTreeMap::new();
+        let language = TargetLanguage::default();
+        let problem = src_to_problem(src.to_string(), language.clone()).unwrap();
+        let bytes = serialize_problem(&problem, src, &libs).unwrap();
+
+        let key = CacheKey::new(src, &libs, &language);
+        assert_eq!(key, CacheKey::new(src, &libs, &TargetLanguage::default()));
+        let (tree, _) = parse_pattern(src, language.clone()).unwrap();
+        assert!(restore_problem(&bytes, &key, tree, language.clone())
+            .unwrap()
+            .is_some());
+
+        // Stands in for a new version of the grammar, numbering its sorts
+        // differently.
+        let other: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+        assert_ne!(language.grammar_version(), other.grammar_version());
+        let stale_key = CacheKey::for_grammar(
+            src,
+            &libs,
+            language.language_name(),
+            other.grammar_version(),
+        );
+        assert_ne!(key, stale_key);
+        let (tree, _) = parse_pattern(src, language.clone()).unwrap();
+        assert!(restore_problem(&bytes, &stale_key, tree, language)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Node-level diffs of what a rewrite changed, for tools which review
+//! rewrites structurally rather than line by line.
+
+use crate::{api::MatchResult, problem::Problem};
+use anyhow::{anyhow, Result};
+use grit_util::{Ast, AstNode, ByteRange, FileOrigin};
+use marzano_language::language::{MarzanoLanguage, Tree};
+use marzano_util::{
+    node_with_source::NodeWithSource, rich_path::RichFile, runtime::ExecutionContext,
+};
+use std::path::Path;
+
+/// A change between the original and the rewritten tree. Ranges are in the
+/// source of the tree the node belongs to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructuralEdit {
+    /// A node only found in the rewritten tree.
+    Added { kind: String, rewritten: ByteRange },
+    /// A node only found in the original tree.
+    Removed { kind: String, original: ByteRange },
+    /// A node of the original tree which the rewrite turned into another.
+    /// `kind` is the kind of the original node.
+    Replaced {
+        kind: String,
+        original: ByteRange,
+        rewritten: ByteRange,
+    },
+}
+
+/// The result of [`Problem::rewrite_with_diff`].
+#[derive(Debug, Clone)]
+pub struct RewriteDiff {
+    /// The rewritten source of the file.
+    pub rewritten: String,
+    /// The outermost nodes that changed, in source order.
+    pub edits: Vec<StructuralEdit>,
+}
+
+impl Problem {
+    /// Rewrites `file`, returning the new source along with the nodes that
+    /// changed, or `None` if the problem doesn't rewrite the file.
+    pub fn rewrite_with_diff(
+        &self,
+        file: &RichFile,
+        context: &ExecutionContext,
+    ) -> Result<Option<RewriteDiff>> {
+        let rewritten = self
+            .execute_file(file, context)
+            .into_iter()
+            .find_map(|result| {
+                let MatchResult::Rewrite(rewrite) = result else {
+                    return None;
+                };
+                (rewrite.rewritten.source_file == file.path).then_some(rewrite.rewritten.content)
+            });
+        let Some(rewritten) = rewritten else {
+            return Ok(None);
+        };
+        let path = Path::new(&file.path);
+        let original_tree = self.parse(&file.content, path)?;
+        let rewritten_tree = self.parse(&rewritten, path)?;
+        let edits = diff_trees(&original_tree, &rewritten_tree);
+        Ok(Some(RewriteDiff { rewritten, edits }))
+    }
+
+    fn parse(&self, source: &str, path: &Path) -> Result<Tree> {
+        self.language
+            .get_parser()
+            .parse_file(source, Some(path), &mut vec![].into(), FileOrigin::Fresh)
+            .ok_or_else(|| anyhow!("failed to parse {}", path.display()))
+    }
+}
+
+/// Compares two trees top-down, reporting the outermost nodes whose source
+/// differs. Nodes of the same kind are compared child by child, trimming the
+/// children which are the same at either end, so an argument added to a call
+/// is reported as added rather than as a replaced call.
+pub fn diff_trees(original: &Tree, rewritten: &Tree) -> Vec<StructuralEdit> {
+    let mut edits = Vec::new();
+    diff_nodes(&original.root_node(), &rewritten.root_node(), &mut edits);
+    edits
+}
+
+fn diff_nodes(
+    original: &NodeWithSource,
+    rewritten: &NodeWithSource,
+    edits: &mut Vec<StructuralEdit>,
+) {
+    if same_text(original, rewritten) {
+        return;
+    }
+    let replaced = StructuralEdit::Replaced {
+        kind: original.node.kind().to_string(),
+        original: original.byte_range(),
+        rewritten: rewritten.byte_range(),
+    };
+    let old_children: Vec<_> = original.named_children().collect();
+    let new_children: Vec<_> = rewritten.named_children().collect();
+    if original.node.kind() != rewritten.node.kind()
+        || (old_children.is_empty() && new_children.is_empty())
+    {
+        edits.push(replaced);
+        return;
+    }
+
+    let prefix = old_children
+        .iter()
+        .zip(&new_children)
+        .take_while(|(old, new)| same_text(old, new))
+        .count();
+    let suffix = old_children[prefix..]
+        .iter()
+        .rev()
+        .zip(new_children[prefix..].iter().rev())
+        .take_while(|(old, new)| same_text(old, new))
+        .count();
+    let old_changed = &old_children[prefix..old_children.len() - suffix];
+    let new_changed = &new_children[prefix..new_children.len() - suffix];
+    if old_changed.is_empty() && new_changed.is_empty() {
+        // Only the tokens between the children changed, such as an operator.
+        edits.push(replaced);
+        return;
+    }
+    let paired = old_changed.len().min(new_changed.len());
+    for (old, new) in old_changed.iter().zip(new_changed) {
+        diff_nodes(old, new, edits);
+    }
+    edits.extend(
+        old_changed[paired..]
+            .iter()
+            .map(|old| StructuralEdit::Removed {
+                kind: old.node.kind().to_string(),
+                original: old.byte_range(),
+            }),
+    );
+    edits.extend(
+        new_changed[paired..]
+            .iter()
+            .map(|new| StructuralEdit::Added {
+                kind: new.node.kind().to_string(),
+                rewritten: new.byte_range(),
+            }),
+    );
+}
+
+fn same_text(original: &NodeWithSource, rewritten: &NodeWithSource) -> bool {
+    original.text().ok() == rewritten.text().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern_compiler::src_to_problem;
+    use marzano_language::target_language::TargetLanguage;
+
+    fn rewrite(pattern: &str, source: &str) -> RewriteDiff {
+        let problem = src_to_problem(pattern.to_string(), TargetLanguage::default()).unwrap();
+        let file = RichFile::new("test.js".to_string(), source.to_string());
+        problem
+            .rewrite_with_diff(&file, &ExecutionContext::default())
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn renamed_arguments_are_reported_as_replaced() {
+        let diff = rewrite("`user` => `account`", "greet(user, 1);\n");
+        assert_eq!(diff.rewritten, "greet(account, 1);\n");
+        assert_eq!(
+            diff.edits,
+            [StructuralEdit::Replaced {
+                kind: "identifier".to_string(),
+                original: ByteRange::new(6, 10),
+                rewritten: ByteRange::new(6, 13),
+            }]
+        );
+    }
+
+    #[test]
+    fn dropped_arguments_are_reported_as_removed() {
+        let diff = rewrite("`greet($a, $b)` => `greet($a)`", "greet(user, 1);\n");
+        assert_eq!(diff.rewritten, "greet(user);\n");
+        assert_eq!(
+            diff.edits,
+            [StructuralEdit::Removed {
+                kind: "number".to_string(),
+                original: ByteRange::new(12, 13),
+            }]
+        );
+    }
+
+    #[test]
+    fn files_without_rewrites_have_no_diff() {
+        let problem =
+            src_to_problem("`user` => `account`".to_string(), TargetLanguage::default()).unwrap();
+        let file = RichFile::new("test.js".to_string(), "greet(admin);\n".to_string());
+        assert!(problem
+            .rewrite_with_diff(&file, &ExecutionContext::default())
+            .unwrap()
+            .is_none());
+    }
+}
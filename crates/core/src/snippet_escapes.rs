@@ -0,0 +1,118 @@
+use grit_util::ByteRange;
+
+/// An escape sequence resolved while processing the source of a snippet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedEscape {
+    /// Range of the escape sequence, such as `\n`, in the raw source.
+    pub raw: ByteRange,
+    /// Offset of the character it stands for in the processed source.
+    pub processed: usize,
+    /// The character the escape sequence stands for.
+    pub value: char,
+}
+
+/// The escape sequences resolved in a snippet, in ascending order, which
+/// translate offsets between its raw and processed source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EscapeMap {
+    pub escapes: Vec<AppliedEscape>,
+}
+
+impl EscapeMap {
+    pub fn is_empty(&self) -> bool {
+        self.escapes.is_empty()
+    }
+
+    /// Translates an offset in the processed source into the raw source.
+    pub fn raw_offset(&self, processed: usize) -> usize {
+        let shortened = self
+            .escapes
+            .iter()
+            .take_while(|escape| escape.processed < processed)
+            .map(|escape| escape.raw.end - escape.raw.start - escape.value.len_utf8())
+            .sum::<usize>();
+        processed + shortened
+    }
+
+    /// Translates an offset in the raw source into the processed source.
+    ///
+    /// Offsets within an escape sequence map to the character it stands for.
+    pub fn processed_offset(&self, raw: usize) -> usize {
+        let mut offset = raw;
+        for escape in &self.escapes {
+            if raw >= escape.raw.end {
+                offset -= escape.raw.end - escape.raw.start - escape.value.len_utf8();
+            } else if raw > escape.raw.start {
+                return escape.processed;
+            } else {
+                break;
+            }
+        }
+        offset
+    }
+}
+
+/// Resolves the escape sequences of a snippet, such as `\$` for a literal
+/// dollar sign, in a single pass.
+///
+/// Backslashes which don't start one of these sequences are kept as is.
+pub fn process_snippet_escapes(raw: &str) -> (String, EscapeMap) {
+    let mut processed = String::with_capacity(raw.len());
+    let mut escapes = Vec::new();
+    let mut chars = raw.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let value = match (c, chars.peek()) {
+            ('\\', Some((_, 'n'))) => Some('\n'),
+            ('\\', Some((_, escaped @ ('$' | '^' | '`' | '"' | '\\')))) => Some(*escaped),
+            _ => None,
+        };
+        let Some(value) = value else {
+            processed.push(c);
+            continue;
+        };
+        chars.next();
+        escapes.push(AppliedEscape {
+            raw: ByteRange::new(index, index + 2),
+            processed: processed.len(),
+            value,
+        });
+        processed.push(value);
+    }
+    (processed, EscapeMap { escapes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_map_records_applied_escapes() {
+        let (processed, map) = process_snippet_escapes("a\\nb\\$c");
+        assert_eq!(processed, "a\nb$c");
+        assert_eq!(
+            map.escapes,
+            vec![
+                AppliedEscape {
+                    raw: ByteRange::new(1, 3),
+                    processed: 1,
+                    value: '\n',
+                },
+                AppliedEscape {
+                    raw: ByteRange::new(4, 6),
+                    processed: 3,
+                    value: '$',
+                },
+            ]
+        );
+        assert_eq!(map.raw_offset(4), 6);
+        assert_eq!(map.processed_offset(6), 4);
+        assert_eq!(map.processed_offset(5), 3);
+    }
+
+    #[test]
+    fn escaped_backslashes_are_resolved_once() {
+        let (processed, map) = process_snippet_escapes("\\\\n \\d");
+        assert_eq!(processed, "\\n \\d");
+        assert_eq!(map.escapes.len(), 1);
+    }
+}
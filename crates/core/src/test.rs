@@ -1,7 +1,14 @@
-use crate::pattern_compiler::src_to_problem_libs;
+use crate::pattern_compiler::{
+    src_to_problem_libs, src_to_problem_libs_with_options, CompilationOptions,
+    LazyMultiLangSnippet, MultiLanguageBuilder, SnippetFallbackLint, SourceOffset,
+    SpreadPreference,
+};
 use anyhow::{anyhow, Context, Result};
 use api::MatchResult;
-use grit_util::{Range, VariableMatch};
+use grit_pattern_matcher::pattern::{Pattern, PatternOrPredicate};
+use grit_util::{
+    traverse, AstNode, ByteRange, FileOrigin, Order, Parser, Position, Range, VariableMatch,
+};
 use insta::{assert_debug_snapshot, assert_snapshot, assert_yaml_snapshot};
 use lazy_static::lazy_static;
 use marzano_auth::env::ENV_VAR_GRIT_API_URL;
@@ -14,6 +21,7 @@ use problem::Problem;
 use similar::{ChangeTag, TextDiff};
 use std::collections::{BTreeMap, HashMap};
 use std::{env, path::Path, path::PathBuf};
+use test_utils::{assert_snippet_matches, assert_snippet_no_match};
 use tree_sitter::Parser as TSParser;
 use trim_margin::MarginTrimmable;
 use walkdir::WalkDir;
@@ -3563,19 +3571,12 @@ fn test_before_and_after_each_file() {
 
 #[test]
 fn std_lib_array_snippet() {
-    run_test_match({
-        TestArg {
-            pattern: r#"
-                |language js
-                |
-                |`const stdlib = [$old] as const`
-                |"#
-            .trim_margin()
-            .unwrap(),
-            source: r#"const stdlib = [foo] as const"#.to_string(),
-        }
-    })
-    .unwrap();
+    assert_snippet_matches(
+        PatternLanguage::Tsx.try_into().unwrap(),
+        "const stdlib = [$old] as const",
+        "const stdlib = [foo] as const",
+        &[("$old", "foo")],
+    );
 }
 
 #[test]
@@ -3722,19 +3723,12 @@ fn simple_yaml_rewrite_sequence_items() {
 
 #[test]
 fn std_lib_activities_snippet() {
-    run_test_match({
-        TestArg {
-            pattern: r#"
-                |language js
-                |
-                |`const stdlib = { $activities }`
-                |"#
-            .trim_margin()
-            .unwrap(),
-            source: r#"const stdlib = { foo: 7 }"#.to_string(),
-        }
-    })
-    .unwrap();
+    assert_snippet_matches(
+        PatternLanguage::Tsx.try_into().unwrap(),
+        "const stdlib = { $activities }",
+        "const stdlib = { foo: 7 }",
+        &[("$activities", "foo: 7")],
+    );
 }
 
 #[test]
@@ -3917,19 +3911,17 @@ fn js_remove_redundant_empty_statements() {
 
 #[test]
 fn array_destrcutring_snippet() {
-    run_test_match({
-        TestArg {
-            pattern: r#"
-                |language js
-                |
-                |`const [$name, $_] = useState($_)`
-                |"#
-            .trim_margin()
-            .unwrap(),
-            source: r#"const [foo, bar] = useState(5)"#.to_string(),
-        }
-    })
-    .unwrap();
+    assert_snippet_matches(
+        PatternLanguage::Tsx.try_into().unwrap(),
+        "const [$name, $_] = useState($_)",
+        "const [foo, bar] = useState(5)",
+        &[("$name", "foo")],
+    );
+    assert_snippet_no_match(
+        PatternLanguage::Tsx.try_into().unwrap(),
+        "const [$name, $_] = useState($_)",
+        "const [foo, bar] = useReducer(5)",
+    );
 }
 
 #[test]
@@ -14831,3 +14823,3905 @@ fn ruby_array_global() {
     })
     .unwrap();
 }
+
+#[test]
+fn list_element_index_accessor() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`const $name = [$items]` where {
+                |    $items <: some bubble $item => `$[item]_$[item.index]`
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const letters = [a, b, c];
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const letters = [a_0, b_1, c_2];
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn string_quote_accessor_preserves_quote_style() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`setTitle($title)` => `setHeading($[title.quote]Welcome$[title.quote])`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |setTitle('Hello');
+                |setTitle("Hello");
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |setHeading('Welcome');
+                |setHeading("Welcome");
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn string_quote_accessor_requires_a_string_literal() {
+    let pattern = r#"
+        |language js
+        |
+        |`setTitle($title)` => `setHeading($[title.quote]Welcome$[title.quote])`
+        |"#
+    .trim_margin()
+    .unwrap();
+    let problem = src_to_problem(pattern, TargetLanguage::default()).unwrap();
+    let results = problem.execute_file(
+        &RichFile::new("test.js".to_string(), "setTitle(title);".to_string()),
+        &ExecutionContext::default(),
+    );
+    assert!(results
+        .iter()
+        .any(|result| matches!(result, MatchResult::AnalysisLog(_))));
+}
+
+#[test]
+fn indent_accessor_inserts_lines_at_the_indentation_of_a_binding() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`track($event)` as $call => `$call;\n$[call.indent]flush()`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |function run() {
+                |    track('open');
+                |    if (ready) {
+                |        track('ready');
+                |    }
+                |}
+                |track('top');
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |function run() {
+                |    track('open');
+                |    flush();
+                |    if (ready) {
+                |        track('ready');
+                |        flush();
+                |    }
+                |}
+                |track('top');
+                |flush();
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn snippet_fallback_lint_accepts_structural_snippets() {
+    let pattern = "`console.log($message)` => `logger.info($message)`".to_string();
+    let libs = BTreeMap::new();
+    let default_language = PatternLanguage::Tsx.try_into().unwrap();
+    let options = CompilationOptions {
+        snippet_fallback: SnippetFallbackLint::Deny,
+        ..Default::default()
+    };
+    let pattern = src_to_problem_libs_with_options(
+        pattern,
+        &libs,
+        default_language,
+        None,
+        None,
+        None,
+        None,
+        options,
+    )
+    .unwrap();
+    assert!(pattern.compilation_warnings.is_empty())
+}
+
+#[test]
+fn snippet_fallback_lint_flags_unparseable_snippets() {
+    let pattern = "`}{)(`".to_string();
+    let libs = BTreeMap::new();
+    let default_language: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+
+    let options = CompilationOptions {
+        snippet_fallback: SnippetFallbackLint::Warn,
+        ..Default::default()
+    };
+    let warned = src_to_problem_libs_with_options(
+        pattern.clone(),
+        &libs,
+        default_language.clone(),
+        None,
+        None,
+        None,
+        None,
+        options,
+    )
+    .unwrap();
+    assert_eq!(warned.compilation_warnings.len(), 1);
+    assert!(warned.compilation_warnings[0]
+        .message
+        .contains("will only be matched as text"));
+
+    let options = CompilationOptions {
+        snippet_fallback: SnippetFallbackLint::Deny,
+        ..Default::default()
+    };
+    let denied = src_to_problem_libs_with_options(
+        pattern,
+        &libs,
+        default_language,
+        None,
+        None,
+        None,
+        None,
+        options,
+    );
+    assert!(denied.is_err());
+}
+
+#[test]
+fn rewrite_assigns_computed_snippet_for_reuse() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`const $a = $b;` where {
+                |    $a => $combined = `$[a]_$[b]`,
+                |    $b => `lookup($combined, $combined)`
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const x = y;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const x_y = lookup(x_y, x_y);
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn trailing_commas_do_not_affect_js_snippets() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "foo($a, $b,)",
+        "foo(x, y);",
+        &[("$a", "x"), ("$b", "y")],
+    );
+    assert_snippet_matches(lang.clone(), "foo($a, $b)", "foo(x, y,);", &[("$b", "y")]);
+    assert_snippet_matches(
+        lang,
+        "const $list = [$first, $second,]",
+        "const letters = [a, b];",
+        &[("$first", "a"), ("$second", "b")],
+    );
+}
+
+#[test]
+fn trailing_commas_do_not_affect_rust_snippets() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "foo($a, $b,)",
+        "fn main() { foo(x, y); }",
+        &[("$a", "x"), ("$b", "y")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "foo($a, $b)",
+        "fn main() { foo(x, y,); }",
+        &[("$a", "x")],
+    );
+    // a single trailing comma makes a tuple, so it is kept
+    assert_snippet_no_match(lang, "let $x = ($y,);", "fn main() { let a = (b); }");
+}
+
+#[test]
+fn named_spreads_are_greedy_by_default() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "[$...a, $mid, $...b]",
+        "[1, 2, 3];",
+        &[("$mid", "3")],
+    );
+    // only the first spread decides where `$mid` is anchored
+    assert_snippet_matches(
+        lang.clone(),
+        "[$...a, $mid, $...b?]",
+        "[1, 2, 3];",
+        &[("$mid", "3")],
+    );
+    assert_snippet_matches(
+        lang,
+        "[$...a, 2, $last, $...b]",
+        "[1, 2, 3, 2, 4];",
+        &[("$last", "4")],
+    );
+}
+
+#[test]
+fn lazy_named_spreads_bind_as_few_elements_as_possible() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "[$...a?, $mid, $...b]",
+        "[1, 2, 3];",
+        &[("$mid", "1")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "[$...a?, $mid, $...b?]",
+        "[1, 2, 3];",
+        &[("$mid", "1")],
+    );
+    assert_snippet_matches(
+        lang,
+        "[$...a?, 2, $last, $...b]",
+        "[1, 2, 3, 2, 4];",
+        &[("$last", "3")],
+    );
+}
+
+#[test]
+fn named_spreads_bind_the_elements_they_cover() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |or {
+                |    `const greedy = [$...a, $mid, $...b]` where {
+                |        $a <: [`1`, `2`],
+                |        $b <: [],
+                |        $mid => `0`
+                |    },
+                |    `const lazy = [$...a?, $mid, $...b]` where {
+                |        $a <: [],
+                |        $b <: [`2`, `3`],
+                |        $mid => `0`
+                |    }
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const greedy = [1, 2, 3];
+                |const lazy = [1, 2, 3];
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const greedy = [1, 2, 0];
+                |const lazy = [0, 2, 3];
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn snippets_see_through_redundant_parentheses() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "$a + $b",
+        "const x = (a) + b;",
+        &[("$a", "(a)"), ("$b", "b")],
+    );
+    assert_snippet_matches(lang.clone(), "a + b", "const x = (a) + ((b));", &[]);
+    assert_snippet_matches(lang.clone(), "foo(a.bar)", "foo((a).bar);", &[]);
+    assert_snippet_matches(lang, "a + b", "const x = a + b;", &[]);
+}
+
+#[test]
+fn explicit_parentheses_in_snippets_are_required() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "($a) + $b",
+        "const x = (a) + b;",
+        &[("$a", "a")],
+    );
+    assert_snippet_no_match(lang, "($a) + $b", "const x = a + b;");
+}
+
+#[test]
+fn list_elements_see_through_redundant_parentheses() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(lang.clone(), "foo(a.bar, b)", "foo((a.bar), b);", &[]);
+    assert_snippet_matches(lang.clone(), "foo(a, $b)", "foo(((a)), 1);", &[("$b", "1")]);
+    assert_snippet_matches(lang.clone(), "[a, b]", "const x = [(a), b];", &[]);
+    assert_snippet_matches(
+        lang.clone(),
+        "foo($a, b)",
+        "foo((a), (b));",
+        &[("$a", "(a)")],
+    );
+    assert_snippet_no_match(lang, "foo((a), b)", "foo(a, b);");
+}
+
+#[test]
+fn snippet_diagnostics_are_offset_into_the_host_document() {
+    let libs = BTreeMap::new();
+    let default_language: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    let compile = |pattern: &str| {
+        let options = CompilationOptions {
+            snippet_fallback: SnippetFallbackLint::Warn,
+            source_offset: SourceOffset::new(40, Position::new(3, 5)),
+            ..Default::default()
+        };
+        src_to_problem_libs_with_options(
+            pattern.to_string(),
+            &libs,
+            default_language.clone(),
+            None,
+            None,
+            None,
+            None,
+            options,
+        )
+    };
+
+    let first_line = compile("`}{)(`").unwrap();
+    let warning = &first_line.compilation_warnings[0];
+    assert_eq!(warning.position, Some(Position::new(3, 6)));
+    assert_eq!(warning.range.unwrap().start_byte, 41);
+
+    let later_line = compile("\n`}{)(`").unwrap();
+    let warning = &later_line.compilation_warnings[0];
+    assert_eq!(warning.position, Some(Position::new(4, 2)));
+    assert_eq!(warning.range.unwrap().start_byte, 42);
+
+    let missing_variable = compile("`foo` => `bar(${missing})`").unwrap_err();
+    assert!(missing_variable
+        .to_string()
+        .contains("(at line 3, column 19)"));
+}
+
+#[test]
+fn string_snippets_constrain_metavariable_content() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`fetch("$url~/https:\/\/.*/")` => `secureFetch("$url")`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |fetch("https://example.com");
+                |fetch("http://example.com");
+                |fetch(url);
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |secureFetch("https://example.com");
+                |fetch("http://example.com");
+                |fetch(url);
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn string_snippet_content_constraints_see_decoded_values() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`log('$message~/[^\n]*\n[^\n]*/')` => `logLines('$message')`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |log('first\nsecond');
+                |log('first second');
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |logLines('first\nsecond');
+                |log('first second');
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn empty_snippet_lists_only_match_empty_lists() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(lang.clone(), "foo()", "foo();", &[]);
+    assert_snippet_no_match(lang.clone(), "foo()", "foo(x);");
+    assert_snippet_no_match(lang.clone(), "foo()", "foo(x, y);");
+    assert_snippet_no_match(lang.clone(), "const $x = []", "const a = [1];");
+    assert_snippet_matches(lang, "const $x = []", "const a = [];", &[("$x", "a")]);
+}
+
+#[test]
+fn spread_snippet_lists_match_any_length() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(lang.clone(), "foo($...)", "foo();", &[]);
+    assert_snippet_matches(lang.clone(), "foo($...)", "foo(x);", &[]);
+    assert_snippet_matches(lang.clone(), "foo($...)", "foo(x, y);", &[]);
+    assert_snippet_matches(lang.clone(), "foo($...args)", "foo();", &[]);
+    assert_snippet_matches(lang, "foo($...args)", "foo(x, y);", &[]);
+}
+
+#[test]
+fn snippet_profiling_records_one_timing_per_snippet() {
+    let pattern = r#"
+        |`console.log($message)` => `logger.info($message)` where {
+        |    $message <: not `"debug"`
+        |}
+        |"#
+    .trim_margin()
+    .unwrap();
+    let libs = BTreeMap::new();
+    let default_language: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+
+    let unprofiled = src_to_problem_libs(
+        pattern.clone(),
+        &libs,
+        default_language.clone(),
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(unprofiled.snippet_timings.is_empty());
+
+    let options = CompilationOptions {
+        profile_snippets: true,
+        ..Default::default()
+    };
+    let profiled = src_to_problem_libs_with_options(
+        pattern,
+        &libs,
+        default_language.clone(),
+        None,
+        None,
+        None,
+        None,
+        options,
+    )
+    .unwrap();
+    let mut sources: Vec<_> = profiled
+        .snippet_timings
+        .iter()
+        .map(|timing| timing.source.as_str())
+        .collect();
+    sources.sort();
+    assert_eq!(
+        sources,
+        vec![
+            "\"debug\"",
+            "console.log($message)",
+            "logger.info($message)"
+        ]
+    );
+    let contexts = grit_util::Language::snippet_context_strings(&default_language).len();
+    assert!(profiled
+        .snippet_timings
+        .iter()
+        .all(|timing| timing.parse_contexts == contexts));
+}
+
+#[test]
+fn snippet_preprocessors_share_the_parse_of_a_source() {
+    // Each preprocessor looking for a leading metavariable checks whether the
+    // snippet parses as is, which it does, so it is only parsed once.
+    let pattern = "`$a + $b`".to_string();
+    let libs = BTreeMap::new();
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    let options = CompilationOptions {
+        profile_snippets: true,
+        ..Default::default()
+    };
+    let profiled = src_to_problem_libs_with_options(
+        pattern,
+        &libs,
+        lang.clone(),
+        None,
+        None,
+        None,
+        None,
+        options,
+    )
+    .unwrap();
+    let contexts = grit_util::Language::snippet_context_strings(&lang).len();
+    assert_eq!(profiled.snippet_timings.len(), 1);
+    assert_eq!(profiled.snippet_timings[0].parse_contexts, contexts);
+}
+
+#[test]
+fn snippets_bind_leading_jsdoc_comments() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "/** $doc */ function $name() {}",
+        "/** Does nothing. */\nfunction noop() {}",
+        &[("$doc", "Does nothing."), ("$name", "noop")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "/** $doc */ function $name($args) { $body }",
+        "/**\n * Adds two numbers.\n */\nexport function add(a, b) { return a + b; }",
+        &[("$doc", "* Adds two numbers."), ("$name", "add")],
+    );
+    assert_snippet_no_match(
+        lang.clone(),
+        "/** $doc */ function $name() {}",
+        "function noop() {}",
+    );
+    assert_snippet_no_match(
+        lang,
+        "/** $doc */ function $name() {}",
+        "// Does nothing.\nfunction noop() {}",
+    );
+}
+
+#[test]
+fn snippets_bind_python_docstrings() {
+    let lang: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert_snippet_matches(
+        lang,
+        "def $name($args):\n    \"\"\"$doc\"\"\"\n    $body",
+        "def add(a, b):\n    \"\"\"Adds two numbers.\"\"\"\n    return a + b\n",
+        &[
+            ("$name", "add"),
+            ("$doc", "Adds two numbers."),
+            ("$body", "return a + b"),
+        ],
+    );
+}
+
+#[test]
+fn snippets_bind_default_parameter_values() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "function $name($x = $default) {}",
+        "function greet(name = 'world') {}",
+        &[("$x", "name"), ("$default", "'world'")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "$x = $default",
+        "function greet(name = 'world') { return name; }",
+        &[("$x", "name"), ("$default", "'world'")],
+    );
+    assert_snippet_no_match(
+        lang.clone(),
+        "function $name($x = $default) {}",
+        "function greet(name) {}",
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "function $name($x?: $type) {}",
+        "function greet(name?: string) {}",
+        &[("$x", "name"), ("$type", "string")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "function $name($x?: $type) {}",
+        "function greet(name: string) {}",
+    );
+}
+
+#[test]
+fn javascript_snippets_bind_default_parameter_values() {
+    let lang: TargetLanguage = PatternLanguage::JavaScript.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "function $name($x = $default) {}",
+        "function greet(name = 'world') {}",
+        &[("$x", "name"), ("$default", "'world'")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "function $name($x = $default) {}",
+        "function greet(name) {}",
+    );
+}
+
+#[test]
+fn python_snippets_bind_default_parameter_values() {
+    let lang: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "$x=$default",
+        "def greet(name='world'):\n    pass\n",
+        &[("$x", "name"), ("$default", "'world'")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "def $name($x=$default):\n    $body",
+        "def greet(name='world'):\n    return name\n",
+        &[("$x", "name"), ("$default", "'world'")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "def $name($x=$default):\n    $body",
+        "def greet(name):\n    return name\n",
+    );
+}
+
+#[test]
+fn language_specific_snippets_select_a_grammar_flavor() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js(typescript)
+                |
+                |js(typescript)"<$type>$value" => js(typescript)"$value as $type"
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const count = <number>input;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const count = input as number;
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn language_specific_snippets_list_available_flavors() {
+    let libs = BTreeMap::new();
+    let default_language: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    let error = src_to_problem_libs_with_options(
+        r#"js(es3)"var $x""#.to_string(),
+        &libs,
+        default_language,
+        None,
+        None,
+        None,
+        None,
+        CompilationOptions::default(),
+    )
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("invalid flavor for language js: es3, expected one of: jsx, flow"));
+}
+
+#[test]
+fn single_entry_object_snippets_match_objects_with_more_properties() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "{ $key: $value }",
+        "const config = { debug: true, verbose: false };",
+        &[("$key", "debug"), ("$value", "true")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "{ $key: $value }",
+        "const config = { debug: true };",
+        &[("$key", "debug"), ("$value", "true")],
+    );
+    assert_snippet_no_match(lang.clone(), "{ $key: $value }", "const config = {};");
+    // entries that aren't all metavariables match by subset as well
+    assert_snippet_matches(
+        lang,
+        "{ verbose: $value }",
+        "const config = { debug: true, verbose: false };",
+        &[("$value", "false")],
+    );
+}
+
+#[test]
+fn nested_single_entry_object_snippets_bind_the_first_property() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`const $name = { $key: $value }` => `const $name = $value`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const config = { debug: true, verbose: false };
+                |const empty = {};
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const config = true;
+                |const empty = {};
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+fn explain_first_match(pattern: &str, source: &str, kind: &str) -> explain::MatchTrace {
+    let problem = src_to_problem(pattern.to_string(), TargetLanguage::default()).unwrap();
+    let snippet = problem
+        .pattern
+        .iter()
+        .find_map(|pattern| {
+            if let PatternOrPredicate::Pattern(Pattern::CodeSnippet(snippet)) = pattern {
+                Some(snippet)
+            } else {
+                None
+            }
+        })
+        .unwrap();
+    let tree = problem
+        .language
+        .get_parser()
+        .parse_file(source, None, &mut vec![].into(), FileOrigin::Fresh)
+        .unwrap();
+    let node = traverse(tree.root_node().walk(), Order::Pre)
+        .find(|node| node.node.kind() == kind)
+        .unwrap();
+    problem.explain_snippet(snippet, &node)
+}
+
+#[test]
+fn explain_snippet_identifies_the_mismatching_field() {
+    let trace = explain_first_match("`$obj.foo`", "config.bar;", "member_expression");
+    assert!(!trace.matched());
+    let mismatch = trace.mismatch_path().into_iter().last().unwrap();
+    assert_eq!(mismatch.field.as_deref(), Some("property"));
+    assert_eq!(
+        mismatch.outcome,
+        explain::MatchOutcome::Mismatched("expected `foo`, found `bar`".to_string())
+    );
+
+    let trace = explain_first_match("`$obj.foo`", "config.foo;", "member_expression");
+    assert!(trace.matched());
+}
+
+#[test]
+fn explain_snippet_reports_conflicting_metavariable_bindings() {
+    let trace = explain_first_match("`$a + $a`", "x + y;", "binary_expression");
+    let mismatch = trace.mismatch_path().into_iter().last().unwrap();
+    assert_eq!(mismatch.field.as_deref(), Some("right"));
+    assert_eq!(
+        mismatch.outcome,
+        explain::MatchOutcome::Mismatched("$a is already bound to `x`, found `y`".to_string())
+    );
+}
+
+#[test]
+fn explain_snippet_reports_applied_escapes() {
+    let trace = explain_first_match("`foo(\"\\$x\")`", "foo(\"$x\");", "call_expression");
+    assert_eq!(
+        trace.escapes.escapes,
+        vec![snippet_escapes::AppliedEscape {
+            raw: ByteRange::new(5, 7),
+            processed: 5,
+            value: '$',
+        }]
+    );
+    assert!(trace.children.iter().all(|child| child.escapes.is_empty()));
+}
+
+#[test]
+fn grit_source_recompiles_to_an_equivalent_pattern() {
+    let source = "console.log(message);\nconsole.warn(other);\nfoo(1, 2);\n";
+    let patterns = [
+        "`console.log($x)` => `logger.info($x)`",
+        "`console.$method($x)` where { $method <: or { `log`, `warn` }, $x => `redacted` }",
+        "`foo($a, $b)` => `bar($b, $a)`",
+        r#"`console.$method($_)` where { $method <: r"(l|w)(.+)"($first, $rest), $method => `$rest` }"#,
+        "`console.log($x)` where { $x <: not `other` }",
+        "`foo($a, $b)` => .",
+    ];
+    let js_lang = TargetLanguage::default();
+    for pattern in patterns {
+        let printed = src_to_problem(pattern.to_string(), js_lang.clone())
+            .unwrap()
+            .to_grit_source();
+        let recompiled = src_to_problem(printed.clone(), js_lang.clone()).unwrap();
+        assert_eq!(recompiled.to_grit_source(), printed);
+        let expected =
+            match_pattern_one_file(pattern.to_string(), "test.js", source, js_lang.clone())
+                .unwrap()
+                .the_match
+                .map(|result| result.rewrite);
+        let actual = match_pattern_one_file(printed.clone(), "test.js", source, js_lang.clone())
+            .unwrap()
+            .the_match
+            .map(|result| result.rewrite);
+        assert_eq!(actual, expected, "{pattern} printed as {printed}");
+    }
+}
+
+#[test]
+fn grit_source_prints_the_auto_wrapped_pattern() {
+    let problem = src_to_problem(
+        "`console.log($x)` => `logger.info($x)`".to_string(),
+        TargetLanguage::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        problem.to_grit_source(),
+        "sequential { file(name = top, body = contains bubble $match where { $match <: `console.log($x)` => `logger.info($x)` }) }"
+    );
+}
+
+#[test]
+fn python_snippets_match_reordered_keyword_arguments() {
+    let lang: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "foo(key=$v, other=$w)",
+        "foo(other=2, key=1)\n",
+        &[("$v", "1"), ("$w", "2")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "foo($x, key=$v, other=$w)",
+        "foo(a, other=2, key=1)\n",
+        &[("$x", "a"), ("$v", "1"), ("$w", "2")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "foo(key=$v, other=$w)",
+        "foo(other=2, key=1, extra=3)\n",
+    );
+}
+
+#[test]
+fn python_snippets_match_positional_arguments_in_order() {
+    let lang: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "foo($a, $b, flag=$f, mode=$m)",
+        "foo(1, 2, mode='r', flag=True)\n",
+        &[("$a", "1"), ("$b", "2"), ("$f", "True"), ("$m", "'r'")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "foo(1, 2, flag=$f, mode=$m)",
+        "foo(2, 1, mode='r', flag=True)\n",
+    );
+}
+
+#[test]
+fn ruby_snippets_match_reordered_keyword_arguments() {
+    let lang: TargetLanguage = PatternLanguage::Ruby.try_into().unwrap();
+    assert_snippet_matches(
+        lang,
+        "foo(^x, key: ^v, other: ^w)",
+        "foo(a, other: 2, key: 1)\n",
+        &[("^x", "a"), ("^v", "1"), ("^w", "2")],
+    );
+}
+
+#[test]
+fn snippet_candidates_are_capped_to_the_most_specific() {
+    let libs = BTreeMap::new();
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    let candidates = |options: CompilationOptions| {
+        let result = src_to_problem_libs_with_options(
+            "`foo(bar)`".to_string(),
+            &libs,
+            lang.clone(),
+            None,
+            None,
+            None,
+            None,
+            options,
+        )
+        .unwrap();
+        let sorts = result
+            .problem
+            .pattern
+            .iter()
+            .find_map(|pattern| {
+                if let PatternOrPredicate::Pattern(Pattern::CodeSnippet(snippet)) = pattern {
+                    Some(
+                        snippet
+                            .patterns
+                            .iter()
+                            .map(|(sort, _)| *sort)
+                            .collect::<Vec<_>>(),
+                    )
+                } else {
+                    None
+                }
+            })
+            .unwrap();
+        (sorts, result.compilation_warnings)
+    };
+
+    let (all, warnings) = candidates(CompilationOptions::default());
+    assert!(all.len() > 1);
+    assert!(warnings.is_empty());
+
+    let (kept, warnings) = candidates(CompilationOptions {
+        max_snippet_candidates: Some(1),
+        ..Default::default()
+    });
+    let ts_lang = lang.get_ts_language();
+    assert_eq!(
+        kept.iter()
+            .map(|sort| ts_lang.node_kind_for_id(*sort).unwrap())
+            .collect::<Vec<_>>(),
+        vec!["call_expression"]
+    );
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0]
+        .message
+        .contains(&format!("has {} candidate parses", all.len())));
+}
+
+#[test]
+fn matches_report_the_byte_ranges_of_bindings() {
+    let problem = src_to_problem("`foo($x)`".to_string(), TargetLanguage::default()).unwrap();
+    let source = "const y = foo(bar);\n";
+    let results = problem.execute_file(
+        &RichFile::new("test.js".to_string(), source.to_string()),
+        &ExecutionContext::default(),
+    );
+    let found = results
+        .iter()
+        .find_map(|result| {
+            if let MatchResult::Match(m) = result {
+                Some(m)
+            } else {
+                None
+            }
+        })
+        .unwrap();
+    let ranges = found.variable_byte_ranges("$x");
+    assert_eq!(ranges, vec![ByteRange::new(14, 17)]);
+    assert_eq!(&source[ranges[0].start..ranges[0].end], "bar");
+}
+
+#[test]
+fn typescript_snippets_bind_type_annotations_and_values_separately() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "const $x: $T = $v",
+        "const count: number = 5;",
+        &[("$x", "count"), ("$T", "number"), ("$v", "5")],
+    );
+    assert_snippet_no_match(lang, "let $x: $T = $v", "let count: number;");
+}
+
+#[test]
+fn typescript_snippets_without_a_value_match_annotated_declarations() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "const $x: $T",
+        "const count: number = 5;",
+        &[("$x", "count"), ("$T", "number")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "let $x: $T",
+        "let count: number;",
+        &[("$T", "number")],
+    );
+    assert_snippet_no_match(lang, "const $x: $T", "const count = 5;");
+}
+
+#[test]
+fn typescript_snippets_without_a_type_match_any_annotation() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "const $x = $v",
+        "const count: number = 5;",
+        &[("$x", "count"), ("$v", "5")],
+    );
+    assert_snippet_matches(
+        lang,
+        "const $x = $v",
+        "const count = 5;",
+        &[("$x", "count"), ("$v", "5")],
+    );
+}
+
+#[test]
+fn serialized_problems_match_like_the_original() {
+    let src = r#"
+        |language js
+        |
+        |`console.$method($x)` where {
+        |    $method <: or { `log`, `warn` },
+        |    $x <: not `"keep"`,
+        |    $x => redact()
+        |}
+        |"#
+    .trim_margin()
+    .unwrap();
+    let libs = BTreeMap::from([(
+        "redact.grit".to_string(),
+        "function redact() { return `\"redacted\"` }".to_string(),
+    )]);
+    let problem = src_to_problem_libs(
+        src.clone(),
+        &libs,
+        TargetLanguage::default(),
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+    .problem;
+
+    let bytes = problem_cache::serialize_problem(&problem, &src, &libs).unwrap();
+    let restored =
+        problem_cache::deserialize_problem(&bytes, &src, &libs, TargetLanguage::default())
+            .unwrap()
+            .unwrap();
+    assert_eq!(restored.hash, problem.hash);
+
+    let file = RichFile::new(
+        "test.js".to_string(),
+        "console.log(secret);\nconsole.warn(\"keep\");\nconsole.error(other);\n".to_string(),
+    );
+    let context = ExecutionContext::default();
+    assert_eq!(
+        restored.execute_file(&file, &context),
+        problem.execute_file(&file, &context)
+    );
+
+    let mut changed_libs = libs.clone();
+    changed_libs.insert(
+        "redact.grit".to_string(),
+        "function redact() { return `\"hidden\"` }".to_string(),
+    );
+    assert!(problem_cache::deserialize_problem(
+        &bytes,
+        &src,
+        &changed_libs,
+        TargetLanguage::default()
+    )
+    .unwrap()
+    .is_none());
+}
+
+#[test]
+fn chain_wildcards_match_any_number_of_links() {
+    let lang = TargetLanguage::default();
+    let snippet = "$obj.foo().$_.bar($arg)";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "client.foo().bar(1);",
+        &[("$obj", "client"), ("$arg", "1")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "client.foo().where(x).limit.bar(2);",
+        &[("$obj", "client"), ("$arg", "2")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "this.db.foo().select().where(y).orderBy(z).bar(3);",
+        &[("$obj", "this.db"), ("$arg", "3")],
+    );
+    assert_snippet_no_match(lang, snippet, "client.foo();\nclient.bar(1);");
+}
+
+#[test]
+fn chain_wildcards_only_follow_the_chain() {
+    let lang = TargetLanguage::default();
+    let snippet = "$obj.foo().$_.bar()";
+    assert_snippet_no_match(lang.clone(), snippet, "wrap(client.foo()).bar();");
+    assert_snippet_no_match(lang.clone(), snippet, "client.baz(x.foo()).bar();");
+    assert_snippet_matches(
+        lang,
+        "$obj.$_.bar()",
+        "client.foo().baz.bar();",
+        &[("$obj", "client.foo().baz")],
+    );
+}
+
+fn definition_snippet_sorts(problem: &Problem, name: &str) -> Vec<Vec<u16>> {
+    let definition = problem
+        .pattern_definitions
+        .iter()
+        .find(|definition| definition.name == name)
+        .unwrap();
+    definition
+        .pattern
+        .iter()
+        .filter_map(|pattern| {
+            if let PatternOrPredicate::Pattern(Pattern::CodeSnippet(snippet)) = pattern {
+                Some(snippet.patterns.iter().map(|(sort, _)| *sort).collect())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn pattern_definitions_switch_the_language_of_their_snippets() {
+    let python: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    let js = TargetLanguage::default();
+    let problem = src_to_problem(
+        r#"
+            |language js
+            |
+            |pattern python_print() language python {
+            |    `print($x)`
+            |}
+            |
+            |pattern js_log() {
+            |    `console.log($x)`
+            |}
+            |
+            |or { python_print(), js_log() }
+            |"#
+        .trim_margin()
+        .unwrap(),
+        js.clone(),
+    )
+    .unwrap();
+    let python_call = python.get_ts_language().id_for_node_kind("call", true);
+    let js_call = js
+        .get_ts_language()
+        .id_for_node_kind("call_expression", true);
+    assert!(definition_snippet_sorts(&problem, "python_print")[0].contains(&python_call));
+    assert!(definition_snippet_sorts(&problem, "js_log")[0].contains(&js_call));
+}
+
+#[test]
+fn language_specific_snippets_override_the_language_of_a_definition() {
+    let js = TargetLanguage::default();
+    let problem = src_to_problem(
+        r#"
+            |language js
+            |
+            |pattern mixed() language python {
+            |    or { `print($x)`, js"console.log($x)" }
+            |}
+            |
+            |mixed()
+            |"#
+        .trim_margin()
+        .unwrap(),
+        js.clone(),
+    )
+    .unwrap();
+    let js_call = js
+        .get_ts_language()
+        .id_for_node_kind("call_expression", true);
+    let sorts = definition_snippet_sorts(&problem, "mixed");
+    assert!(!sorts[0].contains(&js_call));
+    assert!(sorts[1].contains(&js_call));
+}
+
+#[test]
+fn definitions_in_the_language_of_the_file_keep_its_flavor() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |pattern logs() language js {
+                |    `console.log($x)` => `logger.info($x)`
+                |}
+                |
+                |logs()
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |console.log(message);
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |logger.info(message);
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn identifiers_match_within_an_edit_distance() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(lang.clone(), "getUser~1()", "getUserr();", &[]);
+    assert_snippet_matches(lang.clone(), "getUser~1()", "getUser();", &[]);
+    assert_snippet_matches(
+        lang.clone(),
+        "getUser~1($id)",
+        "getUsr(id);",
+        &[("$id", "id")],
+    );
+    assert_snippet_no_match(lang.clone(), "getUser~1()", "getUsers2();");
+    assert_snippet_no_match(lang, "getUser~1()", "fetchUser();");
+}
+
+#[test]
+fn metavariables_match_within_an_edit_distance_of_their_binding() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "$f($f~1)",
+        "getUser(getUserr);",
+        &[("$f", "getUser")],
+    );
+    assert_snippet_no_match(lang, "$f($f~1)", "getUser(fetchUser);");
+}
+
+#[test]
+fn inline_ancestor_constraints_apply_to_the_snippet() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    let snippet = "break @ancestor(switch_statement)";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "switch (x) { case 1: foo(); break; }",
+        &[],
+    );
+    assert_snippet_no_match(lang, snippet, "for (const x of xs) { foo(); break; }");
+}
+
+#[test]
+fn inline_ancestor_constraints_apply_to_metavariables() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    let snippet = "console.log($message @ancestor(switch_statement))";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "switch (x) { case 1: console.log(done); break; }",
+        &[("$message", "done")],
+    );
+    assert_snippet_no_match(lang, snippet, "while (x) { console.log(done); break; }");
+}
+
+#[test]
+fn inline_ancestor_constraints_reject_unknown_kinds() {
+    let pattern = "`break @ancestor(not_a_node_kind)`".to_string();
+    assert!(src_to_problem(pattern, TargetLanguage::default()).is_err());
+}
+
+#[test]
+fn collapsed_snippet_whitespace_keeps_variable_locations() {
+    let pattern = "`console.log(  $message,\t\n\n     $other   )`".to_string();
+    let options = CompilationOptions {
+        collapse_snippet_whitespace: true,
+        ..Default::default()
+    };
+    let problem = src_to_problem_libs_with_options(
+        pattern.clone(),
+        &BTreeMap::new(),
+        TargetLanguage::default(),
+        None,
+        None,
+        None,
+        None,
+        options,
+    )
+    .unwrap()
+    .problem;
+    const SNIPPET_VARIABLES: [&str; 2] = ["$message", "$other"];
+    let variables = problem.compiled_vars();
+    for name in SNIPPET_VARIABLES {
+        let variable = variables.iter().find(|v| v.name == name).unwrap();
+        assert!(!variable.ranges.is_empty());
+        for range in &variable.ranges {
+            assert_eq!(
+                &pattern[range.start_byte as usize..range.end_byte as usize],
+                name
+            );
+        }
+    }
+    let results = problem.execute_file(
+        &RichFile::new(
+            "test.js".to_string(),
+            "console.log(first,\n    second);".to_string(),
+        ),
+        &ExecutionContext::default(),
+    );
+    assert!(results.iter().any(|r| r.is_match()));
+}
+
+#[test]
+fn collapsed_snippet_whitespace_leaves_strings_alone() {
+    let pattern = "`foo(\"a    b\",   $x)`".to_string();
+    let options = CompilationOptions {
+        collapse_snippet_whitespace: true,
+        ..Default::default()
+    };
+    let problem = src_to_problem_libs_with_options(
+        pattern,
+        &BTreeMap::new(),
+        TargetLanguage::default(),
+        None,
+        None,
+        None,
+        None,
+        options,
+    )
+    .unwrap()
+    .problem;
+    let matches = |source: &str| {
+        problem
+            .execute_file(
+                &RichFile::new("test.js".to_string(), source.to_string()),
+                &ExecutionContext::default(),
+            )
+            .iter()
+            .any(|r| r.is_match())
+    };
+    assert!(matches("foo(\"a    b\", c);"));
+    assert!(!matches("foo(\"a b\", c);"));
+}
+
+#[test]
+fn object_destructuring_snippets_bind_destructured_names() {
+    for language in [
+        PatternLanguage::JavaScript,
+        PatternLanguage::TypeScript,
+        PatternLanguage::Tsx,
+    ] {
+        let lang: TargetLanguage = language.try_into().unwrap();
+        assert_snippet_matches(
+            lang.clone(),
+            "const { $a, $b } = $obj",
+            "const { first, second } = config;",
+            &[("$a", "first"), ("$b", "second"), ("$obj", "config")],
+        );
+        assert_snippet_matches(
+            lang.clone(),
+            "const { name: $local } = $obj",
+            "const { name: userName } = user;",
+            &[("$local", "userName"), ("$obj", "user")],
+        );
+        assert_snippet_no_match(
+            lang,
+            "const { name: $local } = $obj",
+            "const { id: userName } = user;",
+        );
+    }
+}
+
+#[test]
+fn array_destructuring_snippets_bind_destructured_names() {
+    for language in [
+        PatternLanguage::JavaScript,
+        PatternLanguage::TypeScript,
+        PatternLanguage::Tsx,
+    ] {
+        let lang: TargetLanguage = language.try_into().unwrap();
+        assert_snippet_matches(
+            lang.clone(),
+            "const [$first, $second] = $list",
+            "const [head, tail] = items;",
+            &[("$first", "head"), ("$second", "tail"), ("$list", "items")],
+        );
+        assert_snippet_matches(
+            lang.clone(),
+            "const [$first, { id: $id }] = $rows",
+            "const [row, { id: rowId }] = rows;",
+            &[("$first", "row"), ("$id", "rowId")],
+        );
+        assert_snippet_no_match(
+            lang,
+            "const [$first, $second] = $list",
+            "const [only] = items;",
+        );
+    }
+}
+
+#[test]
+fn declaration_kind_snippets_bind_the_keyword() {
+    let lang: TargetLanguage = PatternLanguage::JavaScript.try_into().unwrap();
+    for kind in ["var", "let", "const"] {
+        assert_snippet_matches(
+            lang.clone(),
+            "$kind $x = $v",
+            &format!("{kind} a = 1;"),
+            &[("$kind", kind), ("$x", "a"), ("$v", "1")],
+        );
+    }
+    assert_snippet_no_match(lang, "$kind $x = $v", "a = 1;");
+}
+
+#[test]
+fn declaration_kind_snippets_rewrite_a_specific_kind() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`$kind $x = $v` where { $kind <: `var`, $kind => `const` }
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |var a = 1;
+                |let b = 2;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const a = 1;
+                |let b = 2;
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn snippets_see_through_transparent_wrappers() {
+    let python: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert_snippet_matches(python, "$y = foo($x)", "y = (foo(a))", &[("$x", "a")]);
+    let rust: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    assert_snippet_matches(
+        rust,
+        "let $y = foo($x);",
+        "let y = (foo(a));",
+        &[("$x", "a")],
+    );
+}
+
+#[test]
+fn python_snippets_match_metavariables_through_transparent_wrappers() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language python
+                |
+                |`$y = $v` where { $v <: `foo($x)` => `bar($x)` }
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |y = (foo(a))
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |y = bar(a)
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn rust_snippets_match_metavariables_through_transparent_wrappers() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language rust
+                |
+                |`let $y = $v;` where { $v <: `foo($x)` => `bar($x)` }
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |let y = (foo(a));
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |let y = bar(a);
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn member_modifier_snippets_rewrite_typescript_visibility() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js(typescript)
+                |
+                |`$mod $name() {}` where { $mod <: "private", $mod => `public` }
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |class Account {
+                |  private reset() {}
+                |  close() {}
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |class Account {
+                |  public reset() {}
+                |  close() {}
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn member_modifier_snippets_rewrite_java_visibility() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language java
+                |
+                |`$mod void $name() {}` where { $mod <: "public", $mod => `private` }
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |class Account {
+                |    public void reset() {}
+                |    void close() {}
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |class Account {
+                |    private void reset() {}
+                |    void close() {}
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn member_modifier_snippets_match_members_without_modifiers() {
+    let typescript: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    assert_snippet_matches(
+        typescript,
+        "$mod $name() {}",
+        "class Account { close() {} }",
+        &[("$name", "close")],
+    );
+    let java: TargetLanguage = PatternLanguage::Java.try_into().unwrap();
+    assert_snippet_matches(
+        java,
+        "$mod void $name() {}",
+        "class Account { void close() {} }",
+        &[("$name", "close")],
+    );
+}
+
+#[test]
+fn export_snippets_match_exported_and_default_exported_declarations() {
+    for language in ["js", "js(typescript)"] {
+        run_test_expected({
+            TestArgExpected {
+                pattern: format!(
+                    r#"
+                    |language {language}
+                    |
+                    |`export $decl` => `$decl`
+                    |"#
+                )
+                .as_str()
+                .trim_margin()
+                .unwrap(),
+                source: r#"
+                    |export function load() {}
+                    |export default class Store {}
+                    |function helper() {}
+                    |"#
+                .trim_margin()
+                .unwrap(),
+                expected: r#"
+                    |function load() {}
+                    |class Store {}
+                    |function helper() {}
+                    |"#
+                .trim_margin()
+                .unwrap(),
+            }
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn export_default_snippets_only_match_default_exports() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`export default $decl` => `export $decl`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |export function load() {}
+                |export default function save() {}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |export function load() {}
+                |export function save() {}
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn unexported_snippets_only_match_bare_declarations() {
+    for language in ["js", "js(typescript)"] {
+        run_test_expected({
+            TestArgExpected {
+                pattern: format!(
+                    r#"
+                    |language {language}
+                    |
+                    |`!export function $f() {{}}` => `export function $f() {{}}`
+                    |"#
+                )
+                .as_str()
+                .trim_margin()
+                .unwrap(),
+                source: r#"
+                    |function helper() {}
+                    |export function load() {}
+                    |"#
+                .trim_margin()
+                .unwrap(),
+                expected: r#"
+                    |export function helper() {}
+                    |export function load() {}
+                    |"#
+                .trim_margin()
+                .unwrap(),
+            }
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn rewrites_remove_doubled_semicolons() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`foo($x);` as $call => `bar($x);
+                |$call;`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |foo(a);
+                |for (;;) {}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |bar(a);
+                |foo(a);
+                |for (;;) {}
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn ternary_snippets_bind_their_branches() {
+    for language in [
+        PatternLanguage::JavaScript,
+        PatternLanguage::TypeScript,
+        PatternLanguage::Tsx,
+    ] {
+        let lang: TargetLanguage = language.try_into().unwrap();
+        assert_snippet_matches(
+            lang.clone(),
+            "$cond ? $then : $else",
+            "const label = isAdmin ? admin : guest;",
+            &[("$cond", "isAdmin"), ("$then", "admin"), ("$else", "guest")],
+        );
+        assert_snippet_matches(
+            lang.clone(),
+            "$cond ? $then : $else",
+            "render(isAdmin ? admin : guest);",
+            &[("$cond", "isAdmin"), ("$then", "admin"), ("$else", "guest")],
+        );
+        assert_snippet_matches(
+            lang,
+            "$a ? $b : $c ? $d : $e",
+            "const size = small ? 1 : medium ? 2 : 3;",
+            &[
+                ("$a", "small"),
+                ("$b", "1"),
+                ("$c", "medium"),
+                ("$d", "2"),
+                ("$e", "3"),
+            ],
+        );
+    }
+}
+
+#[test]
+fn ternary_snippets_rewrite_swapped_branches() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`!$cond ? $then : $else` => `$cond ? $else : $then`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const label = !isAdmin ? guest : admin;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const label = isAdmin ? admin : guest;
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn multi_language_snippets_dispatch_on_the_file_language() {
+    let problem = MultiLanguageBuilder::new()
+        .snippet(
+            PatternLanguage::JavaScript.try_into().unwrap(),
+            "$x === null",
+        )
+        .snippet(PatternLanguage::Python.try_into().unwrap(), "$x is None")
+        .compile()
+        .unwrap();
+    let matches = |path: &str, source: &str| {
+        problem
+            .execute_file(
+                &RichFile::new(path.to_string(), source.to_string()),
+                &ExecutionContext::default(),
+            )
+            .iter()
+            .any(|r| r.is_match())
+    };
+    assert!(matches("check.js", "if (user === null) {}"));
+    assert!(!matches("check.js", "if (user === undefined) {}"));
+    assert!(matches("check.py", "if user is None:\n    pass\n"));
+    assert!(!matches("check.rb", "user.nil?"));
+}
+
+#[test]
+fn lazy_multi_language_snippets_skip_languages_they_do_not_compile_for() {
+    let mut snippet = LazyMultiLangSnippet::new("$x === null");
+    let context = ExecutionContext::default();
+    let mut matches = |path: &str, source: &str| {
+        snippet
+            .execute_file(
+                &RichFile::new(path.to_string(), source.to_string()),
+                &context,
+            )
+            .iter()
+            .any(|r| r.is_match())
+    };
+    assert!(matches("check.js", "if (user === null) {}"));
+    assert!(!matches("check.js", "if (user === undefined) {}"));
+    assert!(!matches("check.py", "if user is None:\n    pass\n"));
+    assert!(!matches("other.py", "x = None\n"));
+
+    let python: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert!(snippet.pattern(&python).is_none());
+    assert!(snippet.pattern(&TargetLanguage::default()).is_some());
+    // Each language is only compiled, and warned about, once.
+    assert_eq!(snippet.warnings().len(), 1);
+    assert!(snippet.warnings()[0]
+        .message
+        .contains("skipping Python files"));
+}
+
+#[test]
+fn bracketed_metavariables_keep_adjacent_literal_braces() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`const $name = $value` => `const $[name]Map = {$[name]: $value}`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const theme = dark;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const themeMap = {theme: dark};
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn try_snippets_bind_their_clauses() {
+    for lang in [PatternLanguage::JavaScript, PatternLanguage::TypeScript] {
+        let lang: TargetLanguage = lang.try_into().unwrap();
+        let snippet = "try { $body } catch ($e) { $handler }";
+        assert_snippet_matches(
+            lang.clone(),
+            snippet,
+            "try { load(); } catch (err) { report(err); }",
+            &[
+                ("$body", "load();"),
+                ("$e", "err"),
+                ("$handler", "report(err);"),
+            ],
+        );
+        assert_snippet_matches(
+            lang.clone(),
+            snippet,
+            "try { load(); } catch (err) { report(err); } finally { close(); }",
+            &[("$e", "err")],
+        );
+        assert_snippet_no_match(
+            lang.clone(),
+            snippet,
+            "try { load(); } finally { close(); }",
+        );
+        assert_snippet_matches(
+            lang,
+            "try { $body } finally { $cleanup }",
+            "try { load(); } finally { close(); }",
+            &[("$body", "load();"), ("$cleanup", "close();")],
+        );
+    }
+}
+
+#[test]
+fn java_try_snippets_bind_their_clauses() {
+    let lang: TargetLanguage = PatternLanguage::Java.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "try { $body } catch ($T $e) { $handler }",
+        "try { load(); } catch (IOException e) { log(e); } finally { close(); }",
+        &[("$T", "IOException"), ("$e", "e"), ("$handler", "log(e);")],
+    );
+    assert_snippet_matches(
+        lang,
+        "try { $body } finally { $cleanup }",
+        "try { load(); } finally { close(); }",
+        &[("$cleanup", "close();")],
+    );
+}
+
+#[test]
+fn python_try_snippets_bind_their_clauses() {
+    let lang: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "try:\n    $body\nexcept $error:\n    $handler",
+        "try:\n    load()\nexcept ValueError:\n    report()\nfinally:\n    close()\n",
+        &[
+            ("$body", "load()"),
+            ("$error", "ValueError"),
+            ("$handler", "report()"),
+        ],
+    );
+    assert_snippet_matches(
+        lang,
+        "try:\n    $body\nfinally:\n    $cleanup",
+        "try:\n    load()\nfinally:\n    close()\n",
+        &[("$cleanup", "close()")],
+    );
+}
+
+#[test]
+fn snippets_with_too_many_metavariables_are_rejected() {
+    let compile = |max| {
+        src_to_problem_libs_with_options(
+            "`$fn($a, $b, $a)`".to_string(),
+            &BTreeMap::new(),
+            TargetLanguage::default(),
+            None,
+            None,
+            None,
+            None,
+            CompilationOptions {
+                max_snippet_metavariables: Some(max),
+                ..Default::default()
+            },
+        )
+    };
+    assert!(compile(3).is_ok());
+    let error = compile(2).unwrap_err();
+    assert!(format!("{error:?}").contains(
+        "snippet `$fn($a, $b, $a)` has 3 distinct metavariables, more than the limit of 2"
+    ));
+}
+
+#[test]
+fn async_flags_constrain_function_snippets() {
+    let lang: TargetLanguage = PatternLanguage::JavaScript.try_into().unwrap();
+    let plain = "function load() { return fetch(url); }";
+    let asynchronous = "async function load() { return fetch(url); }";
+    assert_snippet_matches(
+        lang.clone(),
+        "async function $f() { $body }",
+        asynchronous,
+        &[("$f", "load")],
+    );
+    assert_snippet_no_match(lang.clone(), "async function $f() { $body }", plain);
+    assert_snippet_matches(
+        lang.clone(),
+        "function $f() { $body }",
+        plain,
+        &[("$f", "load")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "function $f() { $body }",
+        asynchronous,
+        &[("$f", "load")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "!async function $f() { $body }",
+        plain,
+        &[("$f", "load")],
+    );
+    assert_snippet_no_match(lang.clone(), "!async function $f() { $body }", asynchronous);
+    assert_snippet_matches(
+        lang.clone(),
+        "$async function $f() { $body }",
+        asynchronous,
+        &[("$async", "async"), ("$f", "load")],
+    );
+    assert_snippet_matches(
+        lang,
+        "$async function $f() { $body }",
+        plain,
+        &[("$f", "load")],
+    );
+}
+
+#[test]
+fn bound_async_flags_are_kept_in_rewrites() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`$async function $f() { $body }` => `$async function $[f]Task() { $body }`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |async function load() { return fetch(url); }
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |async function loadTask() { return fetch(url); }
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn case_insensitive_identifiers_match_any_casing() {
+    let compile = |pattern: &str, case_insensitive_identifiers| {
+        src_to_problem_libs_with_options(
+            pattern.to_string(),
+            &BTreeMap::new(),
+            TargetLanguage::default(),
+            None,
+            None,
+            None,
+            None,
+            CompilationOptions {
+                case_insensitive_identifiers,
+                ..Default::default()
+            },
+        )
+    };
+    let matches = |problem: &Problem, source: &str| {
+        problem
+            .execute_file(
+                &RichFile::new("test.js".to_string(), source.to_string()),
+                &ExecutionContext::default(),
+            )
+            .iter()
+            .any(|r| r.is_match())
+    };
+    let search = compile("`getuser($id)`", true).unwrap().problem;
+    assert!(matches(&search, "getUser(1);"));
+    assert!(matches(&search, "GETUSER(1);"));
+    assert!(!matches(&search, "getUsers(1);"));
+    let exact = compile("`getuser($id)`", false).unwrap().problem;
+    assert!(!matches(&exact, "getUser(1);"));
+    let error = compile("`getuser($id)` => `fetchUser($id)`", true).unwrap_err();
+    assert!(error.to_string().contains(
+        "case-insensitive identifiers are only supported by patterns which don't rewrite code"
+    ));
+}
+
+#[test]
+fn text_accessor_splices_arguments_verbatim() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`foo($args)` => `bar($[args.text])`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |foo( a,
+                |  /* second */ b , );
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |bar( a,
+                |  /* second */ b , );
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn matches_report_utf16_ranges_when_requested() {
+    let problem = src_to_problem("`foo($x)`".to_string(), TargetLanguage::default()).unwrap();
+    let file = RichFile::new(
+        "test.js".to_string(),
+        "const s = \"😀\";\nfoo(bar);".to_string(),
+    );
+    let mut context = ExecutionContext::default();
+    context.utf16_ranges = true;
+    let results = problem.execute_file(&file, &context);
+    let Some(MatchResult::Match(found)) = results.iter().find(|r| r.is_match()) else {
+        panic!("expected a match");
+    };
+    let range = found.ranges[0];
+    assert_eq!(range.start_byte, 16);
+    assert_eq!((range.start.line, range.start.column), (2, 1));
+    assert_eq!(found.variable_byte_ranges("$x"), [ByteRange::new(20, 23)]);
+
+    let results = problem.execute_file(&file, &ExecutionContext::default());
+    let Some(MatchResult::Match(found)) = results.iter().find(|r| r.is_match()) else {
+        panic!("expected a match");
+    };
+    assert_eq!(found.variable_byte_ranges("$x"), [ByteRange::new(22, 25)]);
+}
+
+#[test]
+fn rust_cfg_attribute_snippets_bind_their_condition() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "#[cfg($condition)]",
+        "#[cfg(test)]\nmod tests {}\n",
+        &[("$condition", "test")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "#[cfg($condition)]",
+        "#[cfg(all(unix, feature = \"serde\"))]\nfn save() {}\n",
+        &[("$condition", "all(unix, feature = \"serde\")")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "#[cfg(feature = $name)]",
+        "#[cfg(feature = \"serde\")]\nfn save() {}\n",
+        &[("$name", "\"serde\"")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "#[cfg($condition)]",
+        "#[derive(Debug)]\nstruct Point;\n",
+    );
+}
+
+#[test]
+fn insert_before_adds_a_line_before_the_match() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`foo($x)` => insert_before(`log($x);`)
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |function run() {
+                |  foo(1);
+                |  bar(2);
+                |}
+                |foo(3);
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |function run() {
+                |  log(1);
+                |  foo(1);
+                |  bar(2);
+                |}
+                |log(3);
+                |foo(3);
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn insert_after_keeps_the_match() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`foo($x);` => insert_after("log('done');")
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |foo(1);
+                |bar(2);
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |foo(1);
+                |log('done');
+                |bar(2);
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn template_string_snippets_bind_quasis_and_interpolations() {
+    assert_snippet_matches(
+        PatternLanguage::Tsx.try_into().unwrap(),
+        r#"\`$start${$first}$middle${$second}$end\`"#,
+        r#"const s = `a-${x}-b-${y}-c`;"#,
+        &[
+            ("$start", "a-"),
+            ("$first", "x"),
+            ("$middle", "-b-"),
+            ("$second", "y"),
+            ("$end", "-c"),
+        ],
+    );
+    assert_snippet_no_match(
+        PatternLanguage::Tsx.try_into().unwrap(),
+        r#"\`$start${$first}$end\`"#,
+        r#"const s = `a-${x}-b-${y}-c`;"#,
+    );
+}
+
+#[test]
+fn template_string_quasis_can_be_rewritten() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`\`$greeting${$name}$end\`` where { $greeting => `Bye-` }
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const s = `Hello-${name}!`;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const s = `Bye-${name}!`;
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn template_string_quasis_are_reassembled_on_the_rhs() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`\`$start${$value}$end\`` => `\`$end${$value}$start\``
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const s = `a-${x}-b`;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const s = `-b${x}a-`;
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn match_order_does_not_depend_on_the_order_of_files() {
+    let pattern = r#"
+        |language js
+        |
+        |`console.log($x)`
+        |"#
+    .trim_margin()
+    .unwrap();
+    let problem = src_to_problem(pattern, TargetLanguage::default()).unwrap();
+    let files = [
+        ("b.js", "console.log(b1);\nconsole.log(b2);"),
+        ("a.js", "if (x) { console.log(a1); }\nconsole.log(a2);"),
+        ("c.js", "console.log(c1);"),
+    ];
+    let execute = |order: &[usize]| {
+        let files = order
+            .iter()
+            .map(|&i| RichFile::new(files[i].0.to_string(), files[i].1.to_string()))
+            .collect();
+        problem.execute_files(files, &ExecutionContext::default())
+    };
+    let results = execute(&[0, 1, 2]);
+    assert_eq!(results, execute(&[2, 1, 0]));
+    assert_eq!(results, execute(&[1, 2, 0]));
+    assert_eq!(results, execute(&[0, 1, 2]));
+
+    let a = results
+        .iter()
+        .find_map(|result| match result {
+            MatchResult::Match(m) if m.source_file == "a.js" => Some(m),
+            _ => None,
+        })
+        .unwrap();
+    let starts = a
+        .ranges
+        .iter()
+        .map(|range| range.start_byte)
+        .collect::<Vec<_>>();
+    assert_eq!(starts, [9, 28]);
+}
+
+#[test]
+fn streamed_results_follow_the_order_of_files() {
+    let pattern = r#"
+        |language js
+        |
+        |`console.log($x)`
+        |"#
+    .trim_margin()
+    .unwrap();
+    let problem = src_to_problem(pattern, TargetLanguage::default()).unwrap();
+    // The first file takes the longest, so the others finish before it.
+    let files = vec![
+        RichFile::new("z.js".to_string(), "console.log(z);\n".repeat(2000)),
+        RichFile::new("a.js".to_string(), "console.log(a);".to_string()),
+        RichFile::new("m.js".to_string(), "console.log(m);".to_string()),
+    ];
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+    pool.install(|| {
+        problem.execute_files_streaming(
+            files,
+            &ExecutionContext::default(),
+            tx,
+            &marzano_util::cache::NullCache::new(),
+        )
+    });
+    let mut names = rx
+        .iter()
+        .flatten()
+        .filter_map(|result| result.file_name().map(str::to_string))
+        .collect::<Vec<_>>();
+    names.dedup();
+    assert_eq!(names, ["z.js", "a.js", "m.js"]);
+}
+
+fn matches_with_spread_preference(
+    pattern: &str,
+    source: &str,
+    spread_preference: Option<SpreadPreference>,
+) -> bool {
+    let libs = BTreeMap::new();
+    let problem = src_to_problem_libs_with_options(
+        pattern.trim_margin().unwrap(),
+        &libs,
+        PatternLanguage::Tsx.try_into().unwrap(),
+        None,
+        None,
+        None,
+        None,
+        CompilationOptions {
+            spread_preference,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .problem;
+    problem
+        .execute_file(
+            &RichFile::new("test.js".to_string(), source.to_string()),
+            &ExecutionContext::default(),
+        )
+        .iter()
+        .any(|result| result.is_match())
+}
+
+#[test]
+fn spread_preference_chooses_how_many_elements_spreads_bind() {
+    let source = "const xs = [1, 2, 3, 2, 4];";
+    let longest = r#"
+        |language js
+        |
+        |`const xs = [$...a, 2, $...b]` where { $a <: [`1`, `2`, `3`] }
+        |"#;
+    let shortest = r#"
+        |language js
+        |
+        |`const xs = [$...a, 2, $...b]` where { $a <: [`1`] }
+        |"#;
+    assert!(matches_with_spread_preference(longest, source, None));
+    assert!(matches_with_spread_preference(
+        longest,
+        source,
+        Some(SpreadPreference::Longest)
+    ));
+    assert!(!matches_with_spread_preference(
+        longest,
+        source,
+        Some(SpreadPreference::Shortest)
+    ));
+    assert!(matches_with_spread_preference(
+        shortest,
+        source,
+        Some(SpreadPreference::Shortest)
+    ));
+    assert!(!matches_with_spread_preference(
+        shortest,
+        source,
+        Some(SpreadPreference::Longest)
+    ));
+}
+
+#[test]
+fn spread_preference_chooses_how_much_text_metavariables_bind() {
+    let source = "/** x-y-z */\nfunction f() {}";
+    let longest = r#"
+        |language js
+        |
+        |`/** $a-$b */ function $f() {}` where { $a <: r"x-y" }
+        |"#;
+    let shortest = r#"
+        |language js
+        |
+        |`/** $a-$b */ function $f() {}` where { $a <: r"x" }
+        |"#;
+    assert!(matches_with_spread_preference(shortest, source, None));
+    assert!(!matches_with_spread_preference(longest, source, None));
+    assert!(matches_with_spread_preference(
+        longest,
+        source,
+        Some(SpreadPreference::Longest)
+    ));
+    assert!(!matches_with_spread_preference(
+        shortest,
+        source,
+        Some(SpreadPreference::Longest)
+    ));
+
+    let literal_source = "const s = 'x-y-z';";
+    let literal_longest = r#"
+        |language js
+        |
+        |`'$a-$b'` where { $a <: r"x-y" }
+        |"#;
+    assert!(matches_with_spread_preference(
+        literal_longest,
+        literal_source,
+        None
+    ));
+    assert!(!matches_with_spread_preference(
+        literal_longest,
+        literal_source,
+        Some(SpreadPreference::Shortest)
+    ));
+}
+
+#[test]
+fn rust_macro_snippets_bind_whole_arguments() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        r#"println!("$fmt", $name, $value)"#,
+        "fn main() {\n    println!(\"{}: {}\", name, value.len());\n}\n",
+        &[
+            ("$fmt", "{}: {}"),
+            ("$name", "name"),
+            ("$value", "value.len()"),
+        ],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        r#"println!("$fmt", $arg)"#,
+        "fn main() {\n    println!(\"{:?}\",foo(1, [2, 3]));\n}\n",
+        &[("$fmt", "{:?}"), ("$arg", "foo(1, [2, 3])")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "vec![$first, $...]",
+        "fn main() {\n    let v = vec![foo(1, 2), 3, 4];\n}\n",
+        &[("$first", "foo(1, 2)")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "vec![$a, $b]",
+        "fn main() {\n    let v = vec![\",\", 'x',];\n}\n",
+        &[("$a", "\",\""), ("$b", "'x'")],
+    );
+    assert_snippet_no_match(
+        lang.clone(),
+        "vec![$a, $b]",
+        "fn main() {\n    let v = vec![1, 2, 3];\n}\n",
+    );
+    assert_snippet_no_match(
+        lang,
+        r#"println!("$fmt", $arg)"#,
+        "fn main() {\n    println!(\"{} {}\", a, b);\n}\n",
+    );
+}
+
+#[test]
+fn rust_macro_arguments_split_around_lifetimes() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "foo!($a, $b)",
+        "fn main() {\n    foo!(&'a x, 'b');\n}\n",
+        &[("$a", "&'a x"), ("$b", "'b'")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "foo!($a, $b, $c)",
+        "fn main() {\n    foo!(&'a x, ',', y: &'static str);\n}\n",
+        &[("$a", "&'a x"), ("$b", "','"), ("$c", "y: &'static str")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "foo!($a, $b)",
+        "fn main() {\n    foo!(&'a x, ',', y);\n}\n",
+    );
+}
+
+#[test]
+fn rust_macro_snippets_with_a_single_metavariable_bind_every_argument() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    assert_snippet_matches(
+        lang,
+        "println!($args)",
+        "fn main() {\n    println!(\"{} {}\", a, b);\n}\n",
+        &[("$args", "\"{} {}\", a, b")],
+    );
+}
+
+#[test]
+fn rust_macro_arguments_can_be_rewritten() {
+    run_test_expected(TestArgExpected {
+        pattern: r#"
+            |language rust
+            |
+            |`println!("$fmt", $arg)` => `eprintln!("$fmt", $arg)`
+            |"#
+        .trim_margin()
+        .unwrap(),
+        source: r#"
+            |fn main() {
+            |    println!("{}", items.len());
+            |    println!("done");
+            |}
+            |"#
+        .trim_margin()
+        .unwrap(),
+        expected: r#"
+            |fn main() {
+            |    eprintln!("{}", items.len());
+            |    println!("done");
+            |}
+            |"#
+        .trim_margin()
+        .unwrap(),
+    })
+    .unwrap();
+}
+
+#[test]
+fn inline_alternation_matches_either_callee() {
+    assert_snippet_matches(
+        TargetLanguage::default(),
+        "$(foo|bar)($x)",
+        "bar(1)",
+        &[("$x", "1")],
+    );
+    assert_snippet_matches(
+        TargetLanguage::default(),
+        "$(foo|bar)($x)",
+        "foo(2)",
+        &[("$x", "2")],
+    );
+    assert_snippet_no_match(TargetLanguage::default(), "$(foo|bar)($x)", "baz(1)");
+    assert_snippet_matches(
+        TargetLanguage::default(),
+        "$(foo|bar)($x, $x)",
+        "bar(a, a)",
+        &[("$x", "a")],
+    );
+    assert_snippet_no_match(TargetLanguage::default(), "$(foo|bar)($x, $x)", "bar(a, b)");
+}
+
+#[test]
+fn inline_alternation_leaves_bitwise_or_alone() {
+    assert_snippet_matches(TargetLanguage::default(), "foo(a|b)", "foo(a|b)", &[]);
+    assert_snippet_no_match(TargetLanguage::default(), "foo(a|b)", "foo(a)");
+    assert_snippet_matches(TargetLanguage::default(), "foo((a|b))", "foo((a|b))", &[]);
+    assert_snippet_no_match(TargetLanguage::default(), "foo((a|b))", "foo(b)");
+    assert_snippet_matches(TargetLanguage::default(), "$(a | b)", "$(a | b)", &[]);
+    assert_snippet_no_match(TargetLanguage::default(), "$(a | b)", "$(b)");
+}
+
+#[test]
+fn inline_alternation_alternates_call_arguments() {
+    assert_snippet_matches(TargetLanguage::default(), "foo($(a|b))", "foo(b)", &[]);
+    assert_snippet_no_match(TargetLanguage::default(), "foo($(a|b))", "foo(a|b)");
+}
+
+#[test]
+fn inline_alternation_binds_metavariables_for_rewrites() {
+    run_test_expected(TestArgExpected {
+        pattern: r#"
+            |language js
+            |
+            |`$(foo|bar)($x)` => `qux($x)`
+            |"#
+        .trim_margin()
+        .unwrap(),
+        source: r#"
+            |foo(1);
+            |bar(2);
+            |baz(3);
+            |"#
+        .trim_margin()
+        .unwrap(),
+        expected: r#"
+            |qux(1);
+            |qux(2);
+            |baz(3);
+            |"#
+        .trim_margin()
+        .unwrap(),
+    })
+    .unwrap();
+}
+
+#[test]
+fn spread_arity_requires_an_exact_number_of_arguments() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(lang.clone(), "foo($...args#2)", "foo(x, y);", &[]);
+    assert_snippet_no_match(lang.clone(), "foo($...args#2)", "foo(x);");
+    assert_snippet_no_match(lang.clone(), "foo($...args#2)", "foo(x, y, z);");
+    assert_snippet_matches(
+        lang,
+        "foo($...args#1, $last)",
+        "foo(x, y);",
+        &[("$last", "y")],
+    );
+}
+
+#[test]
+fn spread_arity_can_require_a_minimum_number_of_arguments() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(lang.clone(), "foo($...args#2+)", "foo(x, y);", &[]);
+    assert_snippet_matches(lang.clone(), "foo($...args#2+)", "foo(x, y, z);", &[]);
+    assert_snippet_no_match(lang.clone(), "foo($...args#2+)", "foo(x);");
+    // a lazy spread still covers at least as many elements as required
+    assert_snippet_matches(
+        lang,
+        "foo($...args?#1+, $rest)",
+        "foo(x, y);",
+        &[("$rest", "y")],
+    );
+}
+
+#[test]
+fn spread_arity_can_limit_the_number_of_arguments() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(lang.clone(), "foo($...args#1-)", "foo();", &[]);
+    assert_snippet_matches(lang.clone(), "foo($...args#1-)", "foo(x);", &[]);
+    assert_snippet_no_match(lang.clone(), "foo($...args#1-)", "foo(x, y);");
+    assert_snippet_matches(
+        lang,
+        "foo($...head#1-, $last)",
+        "foo(x, y);",
+        &[("$last", "y")],
+    );
+}
+
+#[test]
+fn empty_snippets_are_rejected_on_the_lhs() {
+    for pattern in ["``", "`  \n `", "js\"\""] {
+        let error = src_to_problem(pattern.to_string(), TargetLanguage::default()).unwrap_err();
+        assert!(
+            format!("{error:#}").contains("empty snippet"),
+            "unexpected error for {pattern}: {error}"
+        );
+    }
+}
+
+#[test]
+fn empty_snippets_on_the_rhs_insert_nothing() {
+    // the same as rewriting to `.`, which deletes the line left empty
+    for rewrite in ["``", "` `", "`\n  `"] {
+        run_test_expected(TestArgExpected {
+            pattern: format!("language js\n\n`debugger;` => {rewrite}"),
+            source: "foo();\ndebugger;\nbar();\n".to_string(),
+            expected: "foo();\nbar();\n".to_string(),
+        })
+        .unwrap();
+    }
+}
+
+#[test]
+fn function_snippets_bind_return_types() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    let snippet = "function $f(): $ret { $body }";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "function total(): number { return 1; }",
+        &[("$f", "total"), ("$ret", "number")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "function pair(): [string, number] { return ['a', 1]; }",
+        &[("$ret", "[string, number]")],
+    );
+    assert_snippet_no_match(lang, snippet, "function total() { return 1; }");
+}
+
+#[test]
+fn function_snippets_without_return_types_match_any_return_type() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    let snippet = "function $f() { $body }";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "function total(): number { return 1; }",
+        &[("$f", "total")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "function total() { return 1; }",
+        &[("$f", "total")],
+    );
+    assert_snippet_matches(
+        lang,
+        "class $c { $m() { $body } }",
+        "class Counter { count(): number { return 1; } }",
+        &[("$m", "count")],
+    );
+}
+
+fn compilation_warnings(pattern: &str) -> Vec<String> {
+    let libs = BTreeMap::new();
+    let default_language = PatternLanguage::Tsx.try_into().unwrap();
+    src_to_problem_libs(
+        pattern.to_string(),
+        &libs,
+        default_language,
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+    .compilation_warnings
+    .iter()
+    .map(|log| log.message.clone())
+    .collect()
+}
+
+#[test]
+fn bare_metavariable_snippets_warn_that_they_match_everything() {
+    for pattern in ["`$x`", "`$_`", "`$x` => `y`"] {
+        let snippet = &pattern[..4];
+        assert_eq!(
+            compilation_warnings(pattern),
+            [format!("Warning: snippet {snippet} matches every node, write the metavariable without backticks if that is intended")],
+            "unexpected warnings for {pattern}"
+        );
+    }
+}
+
+#[test]
+fn structural_snippets_and_bare_metavariables_do_not_warn_that_they_match_everything() {
+    for pattern in ["`foo($x)`", "$x", "$x => `y`", "`$x` where { $x <: `foo` }"] {
+        assert!(
+            compilation_warnings(pattern).is_empty(),
+            "unexpected warnings for {pattern}"
+        );
+    }
+}
+
+#[test]
+fn sql_select_snippets_match_statements_with_extra_clauses() {
+    let lang: TargetLanguage = PatternLanguage::Sql.try_into().unwrap();
+    let snippet = "SELECT $cols FROM users";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "SELECT name FROM users;",
+        &[("$cols", "name")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "SELECT name FROM users WHERE age > 18;",
+        &[("$cols", "name")],
+    );
+    assert_snippet_matches(
+        lang,
+        snippet,
+        "SELECT name FROM users WHERE age > 18 ORDER BY name LIMIT 10;",
+        &[("$cols", "name")],
+    );
+}
+
+#[test]
+fn sql_select_snippets_bind_the_clauses_they_list() {
+    let lang: TargetLanguage = PatternLanguage::Sql.try_into().unwrap();
+    let snippet = "SELECT $cols FROM users WHERE $condition";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "SELECT name FROM users WHERE age > 18;",
+        &[("$cols", "name"), ("$condition", "age > 18")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "SELECT name FROM users WHERE age > 18 LIMIT 10;",
+        &[("$condition", "age > 18")],
+    );
+    assert_snippet_no_match(lang, snippet, "SELECT name FROM users LIMIT 10;");
+}
+
+#[test]
+fn recovering_from_snippet_errors_collects_every_diagnostic() {
+    let compile = |recover_snippet_errors| {
+        src_to_problem_libs_with_options(
+            "`$x($y)` as $call where { $call <: `$[x]_$[y]_$[call]` }".to_string(),
+            &BTreeMap::new(),
+            TargetLanguage::default(),
+            None,
+            None,
+            None,
+            None,
+            CompilationOptions {
+                max_snippet_metavariables: Some(2),
+                recover_snippet_errors,
+                ..Default::default()
+            },
+        )
+    };
+    assert!(compile(false).is_err());
+    let result = compile(true).unwrap();
+    assert_eq!(
+        result
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect::<Vec<_>>(),
+        [
+            "snippet `$[x]_$[y]_$[call]` has 3 distinct metavariables, more than the limit of 2",
+            "bracketed metavariables are only allowed on the rhs of a snippet",
+        ]
+    );
+    assert_eq!(result.diagnostics[0].range, result.diagnostics[1].range);
+}
+
+#[test]
+fn union_type_snippets_bind_each_member() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    let snippet = "type $name = $a | $b";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "type Id = string | number;",
+        &[("$name", "Id"), ("$a", "string"), ("$b", "number")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "type Result = Array<Ok | Err> | null;",
+        &[("$a", "Array<Ok | Err>"), ("$b", "null")],
+    );
+    assert_snippet_no_match(lang.clone(), snippet, "type Id = string | number | bigint;");
+    assert_snippet_matches(
+        lang,
+        "type $name = $first | $...",
+        "type Id = string | number | bigint;",
+        &[("$first", "string")],
+    );
+}
+
+#[test]
+fn enum_snippets_bind_variants() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    let source = "enum Shape { Circle(f64), Square(f64), Triangle }";
+    assert_snippet_matches(
+        lang.clone(),
+        "enum $name { $variants }",
+        source,
+        &[
+            ("$name", "Shape"),
+            ("$variants", "Circle(f64), Square(f64), Triangle"),
+        ],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "enum $name { $first, $... }",
+        source,
+        &[("$first", "Circle(f64)")],
+    );
+    assert_snippet_no_match(lang, "enum $name { $a, $b }", source);
+}
+
+#[test]
+fn last_sibling_constraints_match_the_last_statement_in_a_block() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`log($x); @last` => `flush($x);`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |function run() {
+                |  log(1);
+                |  work();
+                |  // done
+                |  log(2);
+                |}
+                |log(3);
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |function run() {
+                |  log(1);
+                |  work();
+                |  // done
+                |  flush(2);
+                |}
+                |flush(3);
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn nth_sibling_constraints_count_from_one() {
+    let lang = TargetLanguage::default();
+    assert_snippet_matches(
+        lang.clone(),
+        "foo($a, $b @nth(2))",
+        "foo(x, y);",
+        &[("$b", "y")],
+    );
+    assert_snippet_no_match(lang.clone(), "foo($a @nth(2), $b)", "foo(x, y);");
+    let error = src_to_problem("`foo($a @nth(0))`".to_string(), lang).unwrap_err();
+    assert!(format!("{error:?}").contains("positions start at 1"));
+}
+
+#[test]
+fn kind_accessor_branches_on_the_sort_a_snippet_matched() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`{}` as $empty where {
+                |    $kind = `$[empty.kind]`,
+                |    if ($kind <: "object") {
+                |        $empty => `new Map()`
+                |    } else {
+                |        $empty => `{ /* empty */ }`
+                |    }
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const cache = {};
+                |if (ready) {}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const cache = new Map();
+                |if (ready) { /* empty */ }
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn spread_operands_match_and_chains_as_a_flat_list() {
+    let lang = TargetLanguage::default();
+    assert_snippet_matches(
+        lang.clone(),
+        "$first && $...rest",
+        "if (a && b) {}",
+        &[("$first", "a")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "$first && $...rest",
+        "if (a && b && c && d) {}",
+        &[("$first", "a")],
+    );
+    assert_snippet_no_match(lang.clone(), "$first && $...rest", "if (a || b) {}");
+    assert_snippet_no_match(lang, "$... && ready && $...", "go(a && (b && ready) && c);");
+}
+
+#[test]
+fn dots_match_any_operands_of_an_and_chain() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`$... && isDebug() && $...` => `false`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |if (isDebug() && verbose) {}
+                |if (a && b && isDebug() && c) {}
+                |if (a || isDebug()) {}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |if (false) {}
+                |if (false) {}
+                |if (a || isDebug()) {}
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn walk_snippets_visits_the_snippets_of_compound_patterns() {
+    let pattern = r#"
+        |pattern logged() { `console.log($_)` }
+        |
+        |and {
+        |    or { `foo($x)`, `bar($x)` },
+        |    `$f($x)`,
+        |    not logged(),
+        |} => `baz($x)`
+        |"#
+    .trim_margin()
+    .unwrap();
+    let problem = src_to_problem(pattern.clone(), TargetLanguage::default()).unwrap();
+    let mut snippets = Vec::new();
+    problem.walk_snippets(|snippet| {
+        let range = snippet.range().unwrap();
+        assert_eq!(&pattern[range.start..range.end], snippet.source());
+        snippets.push(snippet.source().to_string());
+    });
+    snippets.sort();
+    assert_eq!(
+        snippets,
+        ["$f($x)", "bar($x)", "console.log($_)", "foo($x)"]
+    );
+}
+
+#[test]
+fn object_snippets_match_objects_containing_their_entries() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`{ name: $n, admin: true }` => `admin($n)`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const a = { id: 1, admin: true, name: "ada" };
+                |const b = { name: "bob" };
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const a = admin("ada");
+                |const b = { name: "bob" };
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn struct_literal_snippets_match_structs_containing_their_fields() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    let source = "fn main() { let user = User { id: 1, name: ada, admin: true }; }";
+    assert_snippet_matches(lang.clone(), "User { name: $n }", source, &[("$n", "ada")]);
+    assert_snippet_matches(
+        lang.clone(),
+        "User { admin: $a, name: $n }",
+        source,
+        &[("$a", "true"), ("$n", "ada")],
+    );
+    assert_snippet_no_match(lang, "User { email: $e }", source);
+}
+
+#[test]
+fn cancellation_stops_matching_a_broad_pattern() {
+    use crate::{
+        marzano_resolved_pattern::MarzanoResolvedPattern, pattern_compiler::PatternBuilder,
+    };
+    use grit_pattern_matcher::{
+        file_owners::FileOwners,
+        pattern::{Matcher, ResolvedPattern},
+    };
+    use marzano_language::grit_parser::MarzanoGritParser;
+    use marzano_util::runtime::CancellationToken;
+
+    let problem = src_to_problem("`$x`".to_string(), TargetLanguage::default()).unwrap();
+    let source = "foo(a, b);\n".repeat(100);
+    let tree = problem
+        .language
+        .get_parser()
+        .parse_file(&source, None, &mut vec![].into(), FileOrigin::Fresh)
+        .unwrap();
+    let cancellation = CancellationToken::new();
+    let runtime = ExecutionContext::default().with_cancellation(cancellation.clone());
+    let file = RichFile::new("test.js".to_string(), source.clone());
+    assert!(problem
+        .execute_file(&file, &runtime)
+        .iter()
+        .any(api::is_match));
+
+    cancellation.cancel();
+    assert!(problem.execute_file(&file, &runtime).is_empty());
+
+    // Without the files around it, the `contains` traversal itself stops.
+    let contains = PatternBuilder::start(
+        "contains `$x`".to_string(),
+        &BTreeMap::new(),
+        TargetLanguage::default(),
+        None,
+        &mut MarzanoGritParser::new().unwrap(),
+        None,
+    )
+    .unwrap()
+    .compile(None, None, false)
+    .unwrap()
+    .problem;
+    let owned_files = FileOwners::new();
+    let (mut state, context) = contains.get_context(&runtime, &owned_files);
+    let resolved = MarzanoResolvedPattern::from_node_binding(tree.root_node());
+    let error = contains
+        .pattern
+        .execute(&resolved, &mut state, &context, &mut vec![].into())
+        .unwrap_err();
+    assert_eq!(error.to_string(), "matching was cancelled");
+}
+
+#[test]
+fn cancellation_stops_compiling_snippets() {
+    let cancellation = marzano_util::runtime::CancellationToken::new();
+    cancellation.cancel();
+    let options = CompilationOptions {
+        cancellation: Some(cancellation),
+        ..Default::default()
+    };
+    let error = src_to_problem_libs_with_options(
+        "`console.log($message)`".to_string(),
+        &BTreeMap::new(),
+        TargetLanguage::default(),
+        None,
+        None,
+        None,
+        None,
+        options,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("was cancelled"));
+}
+
+#[test]
+fn regex_literal_snippets_bind_their_pattern_and_flags() {
+    let source = "const digits = /[0-9]+/gu;\nconst words = /\\w+/;\n";
+    assert_snippet_matches(
+        TargetLanguage::default(),
+        "/$pattern/$flags",
+        source,
+        &[("$pattern", "[0-9]+"), ("$flags", "gu")],
+    );
+    assert_snippet_matches(
+        TargetLanguage::default(),
+        "$text.replace(/$pattern/$flags, $_)",
+        "name.replace(/ /g, \"-\");",
+        &[("$text", "name"), ("$pattern", " "), ("$flags", "g")],
+    );
+}
+
+#[test]
+fn bigint_snippets_bind_their_digits() {
+    let lang = TargetLanguage::default();
+    assert_snippet_matches(
+        lang.clone(),
+        "$[n]n",
+        "const big = 9007199254740993n;",
+        &[("$n", "9007199254740993")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "BigInt.asUintN(64, $[n]n)",
+        "BigInt.asUintN(64, 0xffn);",
+        &[("$n", "0xff")],
+    );
+    assert_snippet_no_match(lang, "$[n]n", "const small = 42;");
+}
+
+#[test]
+fn doc_comment_metavariables_cross_delimiters_without_a_stop_set() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang,
+        "/** $_{$name}. */ function $f() {}",
+        "/** Uses {first} and {second}. */\nfunction noop() {}",
+        &[("$name", "first} and {second"), ("$f", "noop")],
+    );
+}
+
+#[test]
+fn doc_comment_metavariables_stop_at_their_stop_set() {
+    let lang: TargetLanguage = PatternLanguage::Tsx.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "/** $_{$name~[^}]}. */ function $f() {}",
+        "/** Uses {first} and {second}. */\nfunction noop() {}",
+        &[("$name", "second"), ("$f", "noop")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "/** {$name~[^}]}. */ function $f() {}",
+        "/** {first} and {second}. */\nfunction noop() {}",
+    );
+}
+
+#[test]
+fn doc_comment_stop_sets_must_be_closed() {
+    let pattern = "`/** {$name~[^} */ function $f() {}`";
+    let error = src_to_problem(pattern.to_string(), TargetLanguage::default()).unwrap_err();
+    assert!(format!("{error:#}").contains("unterminated stop set"));
+}
+
+#[test]
+fn typescript_class_snippets_match_with_or_without_inheritance_clauses() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    let snippet = "class $c extends $base implements $iface { $_ }";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "class Button extends Component implements Clickable {}",
+        &[
+            ("$c", "Button"),
+            ("$base", "Component"),
+            ("$iface", "Clickable"),
+        ],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "class Button extends Component {}",
+        &[("$c", "Button"), ("$base", "Component")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "class Button implements Clickable {}",
+        &[("$c", "Button"), ("$iface", "Clickable")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "class Button {}",
+        &[("$c", "Button")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "class $c extends Component { $_ }",
+        "class Button implements Clickable {}",
+    );
+}
+
+#[test]
+fn java_class_snippets_match_with_or_without_inheritance_clauses() {
+    let lang: TargetLanguage = PatternLanguage::Java.try_into().unwrap();
+    let snippet = "class $c extends $base implements $iface {}";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "class Button extends Component implements Clickable {}",
+        &[
+            ("$c", "Button"),
+            ("$base", "Component"),
+            ("$iface", "Clickable"),
+        ],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "class Button extends Component {}",
+        &[("$c", "Button"), ("$base", "Component")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "class Button implements Clickable {}",
+        &[("$c", "Button"), ("$iface", "Clickable")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "class Button {}",
+        &[("$c", "Button")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "class $c implements Clickable {}",
+        "class Button extends Component {}",
+    );
+}
+
+#[test]
+fn typescript_generic_function_snippets_bind_their_type_parameters() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    let snippet = "function $f<$T>($x: $T) { $_ }";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "function show<T>(x: T) { log(x); }",
+        &[("$f", "show"), ("$T", "T"), ("$x", "x")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "function show<T extends Shape>(x: T) { log(x); }",
+        &[("$T", "T extends Shape")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "function show(x: Shape) { log(x); }",
+        &[("$f", "show"), ("$T", "Shape")],
+    );
+    assert_snippet_no_match(
+        lang.clone(),
+        snippet,
+        "function show<T, U>(x: T) { log(x); }",
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "function $f<$first, $...>($_) { $_ }",
+        "function pair<K, V extends Map<K, string>>(key: K) { log(key); }",
+        &[("$first", "K")],
+    );
+    assert_snippet_matches(
+        lang,
+        "function $f($x) { $_ }",
+        "function show<T>(x) { log(x); }",
+        &[("$f", "show")],
+    );
+}
+
+#[test]
+fn rust_generic_function_snippets_bind_their_type_parameters() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    let snippet = "fn $f<$T>($x: $T) { $_ }";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "fn show<T>(x: T) { x }",
+        &[("$f", "show"), ("$T", "T"), ("$x", "x")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "fn show<T: Display>(x: T) { x }",
+        &[("$T", "T: Display")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        "fn show(x: u8) { x }",
+        &[("$f", "show"), ("$T", "u8")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "fn $f<$first, $...>($x: $_) { $_ }",
+        "fn show<'a, T>(x: &'a T) { x }",
+        &[("$first", "'a")],
+    );
+    assert_snippet_no_match(lang, "fn $f<T>($x: $_) { $_ }", "fn show(x: u8) { x }");
+}
+
+#[test]
+fn switch_snippets_match_any_of_the_cases() {
+    let lang: TargetLanguage = PatternLanguage::JavaScript.try_into().unwrap();
+    let source = r#"switch (kind) { case "start": run(); case "stop": halt(); default: wait(); }"#;
+    assert_snippet_matches(
+        lang.clone(),
+        "switch ($x) { case $c: $body }",
+        source,
+        &[("$x", "kind"), ("$c", r#""start""#), ("$body", "run();")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        r#"switch ($x) { case "stop": $body }"#,
+        source,
+        &[("$body", "halt();")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        r#"switch ($x) { case "start": $first case "stop": $second }"#,
+        source,
+        &[("$first", "run();"), ("$second", "halt();")],
+    );
+    assert_snippet_no_match(lang, r#"switch ($x) { case "pause": $body }"#, source);
+}
+
+#[test]
+fn switch_snippets_match_default_cases() {
+    let lang: TargetLanguage = PatternLanguage::JavaScript.try_into().unwrap();
+    let snippet = "switch ($x) { default: $fallback }";
+    assert_snippet_matches(
+        lang.clone(),
+        snippet,
+        r#"switch (kind) { case "start": run(); default: wait(); }"#,
+        &[("$x", "kind"), ("$fallback", "wait();")],
+    );
+    assert_snippet_no_match(lang, snippet, r#"switch (kind) { case "start": run(); }"#);
+}
+
+#[test]
+fn debug_bindings_logs_the_bound_metavariables() {
+    let compile = |debug_bindings| {
+        src_to_problem_libs_with_options(
+            "`console.log($message, $level)` where { debug_bindings() }".to_string(),
+            &BTreeMap::new(),
+            TargetLanguage::default(),
+            None,
+            None,
+            None,
+            None,
+            CompilationOptions {
+                debug_bindings,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .problem
+    };
+    let logged = |problem: &Problem| {
+        problem
+            .execute_file(
+                &RichFile::new("test.js".to_string(), "console.log(hello, 2);".to_string()),
+                &ExecutionContext::default(),
+            )
+            .into_iter()
+            .filter_map(|result| {
+                if let MatchResult::AnalysisLog(log) = result {
+                    Some((log.message, log.source))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut bindings = logged(&compile(true));
+    bindings.sort();
+    assert_eq!(
+        bindings,
+        [
+            ("$level".to_string(), Some("2".to_string())),
+            ("$message".to_string(), Some("hello".to_string())),
+        ]
+    );
+    assert!(logged(&compile(false)).is_empty());
+}
+
+#[test]
+fn string_concatenations_become_template_literals() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`$first + $...rest` as $concat where {
+                |    $concat <: contains string()
+                |} => template_literal(parts=[$first, $rest])
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |const greeting = "Hello, " + name + "!";
+                |const quoted = 'It\'s ' + user.name + ` and ${other}`;
+                |const width = padding + border + "px";
+                |const total = count + 1;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |const greeting = `Hello, ${name}!`;
+                |const quoted = `It's ${user.name} and ${other}`;
+                |const width = `${padding + border}px`;
+                |const total = count + 1;
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn sibling_accessors_insert_the_neighbouring_statements() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`debugger` as $pause => `/* after: $[pause.prev] | before: $[pause.next] */`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |debugger;
+                |const total = sum(items);
+                |// a comment is skipped
+                |debugger;
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |/* after:  | before: const total = sum(items); */
+                |const total = sum(items);
+                |// a comment is skipped
+                |/* after: const total = sum(items); | before:  */
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn labeled_statement_snippets_bind_the_label() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language js
+                |
+                |`$label: $stmt` where {
+                |    $label <: "outer",
+                |    $label => `search`,
+                |    $stmt <: maybe contains `continue $label` => `continue search`
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |outer: for (const row of rows) {
+                |  for (const cell of row) {
+                |    if (!cell) continue outer;
+                |  }
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |search: for (const row of rows) {
+                |  for (const cell of row) {
+                |    if (!cell) continue search;
+                |  }
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn go_labeled_statement_snippets_bind_the_statement() {
+    run_test_expected({
+        TestArgExpected {
+            pattern: r#"
+                |language go
+                |
+                |`$label: $stmt` where { $stmt <: not contains `goto $label` } => `$stmt`
+                |"#
+            .trim_margin()
+            .unwrap(),
+            source: r#"
+                |package main
+                |
+                |func main() {
+                |	unused: for i := range items { fmt.Println(i) }
+                |	retry: if err := step(); err != nil { goto retry }
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+            expected: r#"
+                |package main
+                |
+                |func main() {
+                |	for i := range items { fmt.Println(i) }
+                |	retry: if err := step(); err != nil { goto retry }
+                |}
+                |"#
+            .trim_margin()
+            .unwrap(),
+        }
+    })
+    .unwrap();
+}
+
+#[test]
+fn rewrite_callbacks_compute_the_replacement() {
+    use crate::pattern_compiler::PatternBuilder;
+    use grit_pattern_matcher::pattern::ResolvedPattern;
+    use marzano_language::grit_parser::MarzanoGritParser;
+
+    let problem = PatternBuilder::start(
+        "`ready`".to_string(),
+        &BTreeMap::new(),
+        TargetLanguage::default(),
+        None,
+        &mut MarzanoGritParser::new().unwrap(),
+        None,
+    )
+    .unwrap()
+    .rewrite_callback(Box::new(|binding, context, state, _logs| {
+        Ok(binding.text(&state.files, context.language)?.to_uppercase())
+    }))
+    .compile(None, None, true)
+    .unwrap()
+    .problem;
+    let file = RichFile::new(
+        "test.js".to_string(),
+        "if (ready) { start(ready); }\n".to_string(),
+    );
+    let results = problem.execute_file(&file, &ExecutionContext::default());
+    let rewritten = results
+        .into_iter()
+        .find_map(|result| {
+            if let MatchResult::Rewrite(rewrite) = result {
+                Some(rewrite.rewritten.content)
+            } else {
+                None
+            }
+        })
+        .unwrap();
+    assert_eq!(rewritten, "if (READY) { start(READY); }\n");
+}
+
+#[test]
+fn subscript_snippets_bind_the_base_and_index() {
+    assert_snippet_matches(
+        TargetLanguage::default(),
+        "$arr[$i]",
+        "const first = items[0];",
+        &[("$arr", "items"), ("$i", "0")],
+    );
+    let python: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert_snippet_matches(
+        python.clone(),
+        "$arr[$i]",
+        "head = items[0]",
+        &[("$arr", "items"), ("$i", "0")],
+    );
+    let rust: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    assert_snippet_matches(
+        rust.clone(),
+        "$arr[$i]",
+        "fn main() { let first = items[0]; }",
+        &[("$arr", "items"), ("$i", "0")],
+    );
+    assert_snippet_matches(
+        rust,
+        "$arr[$start..$end]",
+        "fn main() { let window = &items[1..3]; }",
+        &[("$arr", "items"), ("$start", "1"), ("$end", "3")],
+    );
+}
+
+#[test]
+fn python_slice_snippets_are_kept_apart_from_indices() {
+    let python: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+    assert_snippet_matches(
+        python.clone(),
+        "$arr[$start:$stop]",
+        "window = items[first:last]",
+        &[("$arr", "items"), ("$start", "first"), ("$stop", "last")],
+    );
+    assert_snippet_no_match(python.clone(), "$arr[$start:$stop]", "head = items[0]");
+    assert_snippet_no_match(python, "$arr[$i]", "tail = items[1:]");
+}
+
+#[test]
+fn rhs_validation_flags_rewrites_which_dont_parse() {
+    let problem = src_to_problem(
+        "`console.log($msg)` => `console.log($msg`".to_string(),
+        TargetLanguage::default(),
+    )
+    .unwrap();
+    let file = RichFile::new(
+        "test.js".to_string(),
+        "function greet() {\n  console.log(\"hi\");\n}\n".to_string(),
+    );
+
+    let results = problem.execute_file(&file, &ExecutionContext::default());
+    assert!(results
+        .iter()
+        .any(|result| matches!(result, MatchResult::Rewrite(_))));
+
+    let runtime = ExecutionContext::default().with_rhs_validation();
+    let results = problem.execute_file(&file, &runtime);
+    assert!(!results
+        .iter()
+        .any(|result| matches!(result, MatchResult::Rewrite(_))));
+    let message = results
+        .iter()
+        .find_map(|result| {
+            if let MatchResult::AnalysisLog(log) = result {
+                Some(log.message.clone())
+            } else {
+                None
+            }
+        })
+        .unwrap();
+    assert!(message.contains("rewriting test.js produced code which doesn't parse"));
+    assert!(message.contains("console.log(\"hi\";"));
+}
+
+#[test]
+fn dynamic_import_snippets_bind_the_specifier() {
+    assert_snippet_matches(
+        TargetLanguage::default(),
+        "import($mod)",
+        "const chart = import(\"./chart\");",
+        &[("$mod", "\"./chart\"")],
+    );
+    assert_snippet_matches(
+        TargetLanguage::default(),
+        "await import($mod)",
+        "async function load() { const chart = await import(\"./chart\"); }",
+        &[("$mod", "\"./chart\"")],
+    );
+    assert_snippet_no_match(
+        TargetLanguage::default(),
+        "await import($mod)",
+        "const chart = import(\"./chart\");",
+    );
+}
+
+#[test]
+fn dynamic_imports_are_kept_apart_from_static_imports() {
+    assert_snippet_no_match(
+        TargetLanguage::default(),
+        "import($mod)",
+        "import chart from \"./chart\";",
+    );
+    assert_snippet_no_match(
+        TargetLanguage::default(),
+        "import $name from $mod",
+        "const chart = import(\"./chart\");",
+    );
+}
+
+#[test]
+fn enum_member_snippets_bind_the_assigned_value() {
+    let lang: TargetLanguage = PatternLanguage::TypeScript.try_into().unwrap();
+    let source = "enum Color { Red = 1, Green, Blue = \"blue\" }";
+    assert_snippet_matches(
+        lang.clone(),
+        "enum $e { Blue = $v }",
+        source,
+        &[("$e", "Color"), ("$v", "\"blue\"")],
+    );
+    assert_snippet_matches(
+        lang.clone(),
+        "enum Color { Green, Red = $v }",
+        source,
+        &[("$v", "1")],
+    );
+    assert_snippet_matches(lang.clone(), "enum Color { Red }", source, &[]);
+    assert_snippet_no_match(lang.clone(), "enum Color { Green = $v }", source);
+    assert_snippet_no_match(lang, "enum Color { Purple }", source);
+}
+
+#[test]
+fn const_item_snippets_bind_the_value() {
+    let lang: TargetLanguage = PatternLanguage::Rust.try_into().unwrap();
+    assert_snippet_matches(
+        lang.clone(),
+        "const $name: $ty = $val;",
+        "const LIMIT: usize = 10 * 2;",
+        &[("$name", "LIMIT"), ("$ty", "usize"), ("$val", "10 * 2")],
+    );
+    assert_snippet_no_match(
+        lang,
+        "const $name: $ty = $val;",
+        "static LIMIT: usize = 10;",
+    );
+}
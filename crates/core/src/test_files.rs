@@ -429,3 +429,183 @@ fn avoid_unsafe_hoists() {
     assert_eq!(results.len(), 4);
     assert!(results.iter().any(|r| r.is_match()));
 }
+
+#[test]
+fn global_metavariables_rename_a_symbol_across_files() {
+    let pattern_src = r#"
+        multifile {
+            bubble file($name, $body) where {
+                $name <: includes "lib.js",
+                $body <: contains `export function $GLOBAL_old($params) { $impl }` where {
+                    $GLOBAL_old <: r"legacy_(.+)"($GLOBAL_new),
+                    $GLOBAL_old => $GLOBAL_new
+                }
+            },
+            bubble file($name, $body) where {
+                $name <: includes "app.js",
+                $body <: contains `$GLOBAL_old($args)` => `$GLOBAL_new($args)`
+            }
+        }
+        "#;
+    let libs = BTreeMap::new();
+
+    let pattern = src_to_problem_libs(
+        pattern_src.to_string(),
+        &libs,
+        TargetLanguage::default(),
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+    .problem;
+
+    let test_files = vec![
+        SyntheticFile::new(
+            "lib.js".to_owned(),
+            "export function legacy_fetch(url) { return get(url); }\n".to_owned(),
+            true,
+        ),
+        SyntheticFile::new(
+            "app.js".to_owned(),
+            "legacy_fetch('/users');\nother_fetch('/posts');\n".to_owned(),
+            true,
+        ),
+    ];
+    let results = run_on_test_files(&pattern, &test_files);
+    assert!(!results.iter().any(|r| r.is_error()));
+    let rewritten: BTreeMap<&str, &str> = results
+        .iter()
+        .filter_map(|r| {
+            if let MatchResult::Rewrite(Rewrite { rewritten, .. }) = r {
+                Some((rewritten.source_file.as_str(), rewritten.content.as_str()))
+            } else {
+                None
+            }
+        })
+        .collect();
+    assert_eq!(
+        rewritten.get("lib.js"),
+        Some(&"export function fetch(url) { return get(url); }\n")
+    );
+    assert_eq!(
+        rewritten.get("app.js"),
+        Some(&"fetch('/users');\nother_fetch('/posts');\n")
+    );
+}
+
+#[test]
+fn glob_scoped_rules_only_read_matching_files() {
+    let pattern_src = r#"
+        `console.log($msg)` where {
+            $filename <: glob(pattern="**/*.test.js"),
+        }
+        "#;
+    let libs = BTreeMap::new();
+
+    let pattern = src_to_problem_libs(
+        pattern_src.to_string(),
+        &libs,
+        TargetLanguage::default(),
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+    .problem;
+
+    let source = "console.log(\"flaky\");\n";
+    let test_files = vec![
+        SyntheticFile::new("src/app.test.js".to_owned(), source.to_owned(), true),
+        SyntheticFile::new("app.test.js".to_owned(), source.to_owned(), true),
+        SyntheticFile::new("src/app.js".to_owned(), String::new(), false),
+        SyntheticFile::new("src/app.test.jsx".to_owned(), String::new(), false),
+    ];
+    let results = run_on_test_files(&pattern, &test_files);
+    let matched: Vec<&str> = results
+        .iter()
+        .filter_map(|r| {
+            if let MatchResult::Match(m) = r {
+                Some(m.source_file.as_str())
+            } else {
+                None
+            }
+        })
+        .collect();
+    assert_eq!(matched, ["src/app.test.js", "app.test.js"]);
+}
+
+#[test]
+fn glob_scoped_rules_tell_test_files_from_source_files() {
+    let pattern_src = r#"
+        any {
+            `console.log($msg)` => `debug($msg)` where {
+                $filename <: glob(pattern="**/*.{test,spec}.js"),
+            },
+            `console.log($msg)` => `logger.info($msg)` where {
+                $filename <: not glob(pattern="**/*.{test,spec}.js"),
+            }
+        }
+        "#;
+    let libs = BTreeMap::new();
+
+    let pattern = src_to_problem_libs(
+        pattern_src.to_string(),
+        &libs,
+        TargetLanguage::default(),
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap()
+    .problem;
+
+    let source = "console.log(value);\n";
+    let test_files = vec![
+        SyntheticFile::new("src/app.test.js".to_owned(), source.to_owned(), true),
+        SyntheticFile::new("src/api.spec.js".to_owned(), source.to_owned(), true),
+        SyntheticFile::new("src/app.js".to_owned(), source.to_owned(), true),
+    ];
+    let results = run_on_test_files(&pattern, &test_files);
+    let rewritten: BTreeMap<&str, &str> = results
+        .iter()
+        .filter_map(|r| {
+            if let MatchResult::Rewrite(Rewrite { rewritten, .. }) = r {
+                Some((rewritten.source_file.as_str(), rewritten.content.as_str()))
+            } else {
+                None
+            }
+        })
+        .collect();
+    assert_eq!(
+        rewritten,
+        BTreeMap::from([
+            ("src/api.spec.js", "debug(value);\n"),
+            ("src/app.js", "logger.info(value);\n"),
+            ("src/app.test.js", "debug(value);\n"),
+        ])
+    );
+}
+
+#[test]
+fn globs_must_be_string_literals() {
+    let pattern_src = r#"
+        `console.log($msg)` where {
+            $filename <: glob(pattern=$msg),
+        }
+        "#;
+    let error = src_to_problem_libs(
+        pattern_src.to_string(),
+        &BTreeMap::new(),
+        TargetLanguage::default(),
+        None,
+        None,
+        None,
+        None,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("string literal"));
+}
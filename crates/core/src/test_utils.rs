@@ -1,6 +1,7 @@
 use std::{borrow::Cow, collections::BTreeMap, sync::mpsc};
 
 use anyhow::Result;
+use grit_util::{Range, VariableMatch};
 use marzano_language::target_language::TargetLanguage;
 use marzano_util::{
     cache::NullCache,
@@ -113,3 +114,91 @@ pub fn run_test(case: TestCase) -> Vec<MatchResult> {
 
     results
 }
+
+fn run_snippet(lang: TargetLanguage, snippet: &str, target: &str) -> Vec<MatchResult> {
+    let libs = BTreeMap::new();
+    let pattern = src_to_problem_libs(format!("`{snippet}`"), &libs, lang, None, None, None, None)
+        .unwrap()
+        .problem;
+    let files = vec![SyntheticFile::new(
+        "target".to_string(),
+        target.to_string(),
+        true,
+    )];
+    run_on_test_files(&pattern, &files)
+}
+
+/// Compiles `snippet` in the given language, and asserts that it matches
+/// `target`, with each of the `expected_bindings` metavariables (such as
+/// `("$a", "foo")`) bound to the given text.
+///
+/// The bindings are compared per match, so they must all come from the same
+/// match of the snippet.
+pub fn assert_snippet_matches(
+    lang: TargetLanguage,
+    snippet: &str,
+    target: &str,
+    expected_bindings: &[(&str, &str)],
+) {
+    let results = run_snippet(lang, snippet, target);
+    let matches: Vec<(&Range, &[VariableMatch])> = results
+        .iter()
+        .filter_map(|result| {
+            if let MatchResult::Match(m) = result {
+                Some(m)
+            } else {
+                None
+            }
+        })
+        .flat_map(|m| m.ranges.iter().map(|range| (range, m.variables.as_slice())))
+        .collect();
+    assert!(
+        !matches.is_empty(),
+        "Expected `{snippet}` to match `{target}`, but got none"
+    );
+    let bindings: Vec<Vec<(&str, &str)>> = matches
+        .iter()
+        .map(|(range, variables)| match_bindings(range, variables, target))
+        .collect();
+    assert!(
+        bindings.iter().any(|bound| expected_bindings
+            .iter()
+            .all(|expected| bound.contains(expected))),
+        "Expected a match of `{snippet}` binding {expected_bindings:?}, but found {bindings:?}"
+    );
+}
+
+/// The bindings of `variables` which lie within the matched `range`.
+fn match_bindings<'a>(
+    range: &Range,
+    variables: &'a [VariableMatch],
+    target: &'a str,
+) -> Vec<(&'a str, &'a str)> {
+    variables
+        .iter()
+        .flat_map(|variable| {
+            variable
+                .ranges
+                .iter()
+                .filter(|bound| {
+                    bound.start_byte >= range.start_byte && bound.end_byte <= range.end_byte
+                })
+                .map(|bound| {
+                    (
+                        variable.name.as_str(),
+                        &target[bound.start_byte as usize..bound.end_byte as usize],
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Compiles `snippet` in the given language, and asserts that it does not
+/// match `target`.
+pub fn assert_snippet_no_match(lang: TargetLanguage, snippet: &str, target: &str) {
+    let results = run_snippet(lang, snippet, target);
+    assert!(
+        !results.iter().any(|r| r.is_match()),
+        "Expected `{snippet}` not to match `{target}`"
+    );
+}
@@ -0,0 +1,180 @@
+//! Scanning for `TODO` and `FIXME` comments, with their author and message
+//! bound the same way in every language.
+
+use crate::{
+    marzano_code_snippet::MarzanoCodeSnippet, pattern_compiler::PatternBuilder, problem::Problem,
+};
+use anyhow::{anyhow, bail, Result};
+use grit_pattern_matcher::pattern::Pattern;
+use grit_util::{traverse, Ast, AstNode, ByteRange, FileOrigin, Language, Order};
+use marzano_language::{
+    grit_parser::MarzanoGritParser, language::MarzanoLanguage, target_language::TargetLanguage,
+};
+use std::collections::BTreeMap;
+
+/// The markers [`TodoCommentScanner`] looks for, in the order they are tried.
+const MARKERS: [&str; 2] = ["TODO", "FIXME"];
+
+/// How the comments to scan are delimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// Comments running to the end of the line, such as `// ...` in
+    /// JavaScript or `# ...` in Python.
+    Line,
+    /// Comments between delimiters, such as `/* ... */`.
+    Block,
+}
+
+/// A comment found by [`TodoCommentScanner::scan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoComment {
+    /// `TODO` or `FIXME`.
+    pub marker: String,
+    /// The name in parentheses after the marker, as in `TODO(alice)`.
+    pub author: Option<String>,
+    pub message: String,
+    /// Range of the whole comment.
+    pub range: ByteRange,
+}
+
+/// Finds comments written as `// TODO($author): $message`, or without the
+/// author as `// TODO: $message`, for each marker in the comment syntax of
+/// a language.
+pub struct TodoCommentScanner {
+    language: TargetLanguage,
+    /// A problem per snippet, along with the marker it matches.
+    snippets: Vec<(&'static str, Problem)>,
+}
+
+impl TodoCommentScanner {
+    pub fn new(language: TargetLanguage, style: CommentStyle) -> Result<Self> {
+        let (open, close) = match style {
+            CommentStyle::Line => (language.comment_prefix(), ""),
+            CommentStyle::Block if language.comment_prefix() == "//" => ("/*", " */"),
+            CommentStyle::Block => {
+                bail!("{} does not have block comments", language.language_name())
+            }
+        };
+        let mut parser = MarzanoGritParser::new()?;
+        let mut snippets = Vec::new();
+        for marker in MARKERS {
+            for body in ["($author): $message", ": $message"] {
+                let source = format!("`{open} {marker}{body}{close}`");
+                let problem = PatternBuilder::start(
+                    source,
+                    &BTreeMap::new(),
+                    language.clone(),
+                    None,
+                    &mut parser,
+                    None,
+                )?
+                .compile(None, None, false)?
+                .problem;
+                snippets.push((marker, problem));
+            }
+        }
+        Ok(Self { language, snippets })
+    }
+
+    /// Returns the TODO comments of `source`, in source order.
+    pub fn scan(&self, source: &str) -> Result<Vec<TodoComment>> {
+        let tree = self
+            .language
+            .get_parser()
+            .parse_file(source, None, &mut vec![].into(), FileOrigin::Fresh)
+            .ok_or_else(|| anyhow!("failed to parse the source"))?;
+        let matchers = self
+            .snippets
+            .iter()
+            .map(|(marker, problem)| Ok((*marker, snippet(problem)?.prepare(problem))))
+            .collect::<Result<Vec<_>>>()?;
+        let comments = traverse(tree.root_node().walk(), Order::Pre)
+            .filter(|node| self.language.is_comment_node(node))
+            .filter_map(|node| {
+                matchers.iter().find_map(|(marker, matcher)| {
+                    let mut bindings = matcher.matches(&node)?;
+                    Some(TodoComment {
+                        marker: marker.to_string(),
+                        author: bindings.remove("$author"),
+                        message: bindings.remove("$message")?,
+                        range: node.byte_range(),
+                    })
+                })
+            })
+            .collect();
+        Ok(comments)
+    }
+}
+
+fn snippet(problem: &Problem) -> Result<&MarzanoCodeSnippet> {
+    let Pattern::CodeSnippet(snippet) = &problem.pattern else {
+        bail!("TODO comment snippets must compile to a code snippet");
+    };
+    Ok(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use marzano_language::target_language::PatternLanguage;
+
+    fn scan(language: PatternLanguage, style: CommentStyle, source: &str) -> Vec<TodoComment> {
+        TodoCommentScanner::new(language.try_into().unwrap(), style)
+            .unwrap()
+            .scan(source)
+            .unwrap()
+    }
+
+    #[test]
+    fn javascript_line_comments_bind_the_author_and_message() {
+        let source =
+            "// TODO(alice): handle errors\nrun();\n// FIXME: flaky on CI\n// NOTE: fine\n";
+        let comments = scan(PatternLanguage::JavaScript, CommentStyle::Line, source);
+        assert_eq!(
+            comments,
+            [
+                TodoComment {
+                    marker: "TODO".to_string(),
+                    author: Some("alice".to_string()),
+                    message: "handle errors".to_string(),
+                    range: ByteRange::new(0, 29),
+                },
+                TodoComment {
+                    marker: "FIXME".to_string(),
+                    author: None,
+                    message: "flaky on CI".to_string(),
+                    range: ByteRange::new(37, 58),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn python_comments_use_the_hash_prefix() {
+        let source = "x = 1  # TODO(bob): use a constant\n# TODO: remove\n";
+        let comments = scan(PatternLanguage::Python, CommentStyle::Line, source);
+        assert_eq!(
+            comments
+                .iter()
+                .map(|comment| (comment.author.as_deref(), comment.message.as_str()))
+                .collect::<Vec<_>>(),
+            [(Some("bob"), "use a constant"), (None, "remove")]
+        );
+    }
+
+    #[test]
+    fn block_comments_are_matched_between_their_delimiters() {
+        let source = "/* FIXME(carol): leaks memory */\nfree(buffer);\n// TODO: not a block\n";
+        let comments = scan(PatternLanguage::JavaScript, CommentStyle::Block, source);
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].marker, "FIXME");
+        assert_eq!(comments[0].author.as_deref(), Some("carol"));
+        assert_eq!(comments[0].message, "leaks memory");
+    }
+
+    #[test]
+    fn block_comments_need_a_language_which_has_them() {
+        let language: TargetLanguage = PatternLanguage::Python.try_into().unwrap();
+        assert!(TodoCommentScanner::new(language, CommentStyle::Block).is_err());
+    }
+}
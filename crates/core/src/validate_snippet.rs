@@ -0,0 +1,281 @@
+use crate::{
+    pattern_compiler::PatternBuilder, problem::MarzanoQueryContext, split_snippet::split_snippet,
+};
+use anyhow::{bail, Result};
+use grit_pattern_matcher::pattern::Pattern;
+use grit_util::{ByteRange, Language};
+use marzano_language::{
+    grit_parser::MarzanoGritParser,
+    language::{nodes_from_indices, MarzanoLanguage},
+    target_language::{PatternLanguage, TargetLanguage},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A problem found in a snippet by [`validate_snippet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetDiagnostic {
+    pub message: String,
+    /// Range of the problem within the snippet source.
+    pub range: ByteRange,
+}
+
+/// Checks whether a snippet would compile, without building its pattern or
+/// registering its metavariables.
+///
+/// Reports empty snippets and bracketed metavariables on the left-hand side,
+/// and snippets that don't parse as the target language, which would
+/// otherwise be matched as text. An empty result means the snippet is valid.
+pub fn validate_snippet(
+    source: &str,
+    lang: &TargetLanguage,
+    is_rhs: bool,
+) -> Vec<SnippetDiagnostic> {
+    let metavariables = split_snippet(source, lang);
+    let bracketed = metavariables
+        .iter()
+        .rev()
+        .filter(|(range, _)| {
+            lang.metavariable_bracket_regex()
+                .is_match(&source[range.start..range.end])
+        })
+        .map(|(range, _)| *range)
+        .collect::<Vec<_>>();
+    if !bracketed.is_empty() {
+        // Bracketed metavariables make the right-hand side a text template,
+        // which doesn't need to parse.
+        if is_rhs {
+            return Vec::new();
+        }
+        return bracketed
+            .into_iter()
+            .map(|range| SnippetDiagnostic {
+                message: "bracketed metavariables are only allowed on the rhs of a snippet"
+                    .to_owned(),
+                range,
+            })
+            .collect();
+    }
+
+    let trimmed = source.trim();
+    if trimmed.is_empty() {
+        if is_rhs {
+            return Vec::new();
+        }
+        return vec![SnippetDiagnostic {
+            message: "empty snippet, use `.` to match an absent node".to_owned(),
+            range: ByteRange::new(0, source.len()),
+        }];
+    }
+    if lang.exact_variable_regex().is_match(trimmed) {
+        return Vec::new();
+    }
+    if nodes_from_indices(&lang.parse_snippet_contexts(source)).is_empty() {
+        let start = source.len() - source.trim_start().len();
+        return vec![SnippetDiagnostic {
+            message: format!("snippet does not parse as {}", lang.language_name()),
+            range: ByteRange::new(start, start + trimmed.len()),
+        }];
+    }
+    Vec::new()
+}
+
+/// Compiles the longest prefix of a snippet which parses as the target
+/// language, for previewing a snippet that is still being typed, returning
+/// the compiled pattern along with the range of the source it covers.
+///
+/// The source is the text between the backticks of a left-hand side snippet.
+/// Fails if no prefix of it parses.
+pub fn compile_snippet_lenient(
+    source: &str,
+    lang: &TargetLanguage,
+) -> Result<(Pattern<MarzanoQueryContext>, ByteRange)> {
+    let start = source.len() - source.trim_start().len();
+    let ends = source
+        .char_indices()
+        .map(|(index, _)| index)
+        .chain([source.len()])
+        .filter(|end| *end > start)
+        .rev();
+    let mut last_tried = None;
+    for end in ends {
+        let prefix = source[..end].trim_end();
+        // Trimming makes several ends try the same prefix, and a trailing
+        // backslash would escape the closing backtick.
+        if last_tried == Some(prefix.len()) || prefix.ends_with('\\') {
+            continue;
+        }
+        last_tried = Some(prefix.len());
+        if !lang.exact_variable_regex().is_match(prefix.trim_start())
+            && nodes_from_indices(&lang.parse_snippet_contexts(prefix)).is_empty()
+        {
+            continue;
+        }
+        let mut parser = MarzanoGritParser::new()?;
+        let problem = PatternBuilder::start(
+            format!("`{prefix}`"),
+            &BTreeMap::new(),
+            lang.clone(),
+            None,
+            &mut parser,
+            None,
+        )?
+        .compile(None, None, false)?
+        .problem;
+        return Ok((problem.pattern, ByteRange::new(start, prefix.len())));
+    }
+    bail!("no part of the snippet parses as {}", lang.language_name())
+}
+
+/// The languages a snippet parses as, for suggesting a language to a pattern
+/// which doesn't declare one, or warning that its snippet is ambiguous.
+///
+/// A lone metavariable parses as every language. Languages whose parser isn't
+/// available are left out.
+pub fn snippet_languages(source: &str) -> Vec<PatternLanguage> {
+    PatternLanguage::enumerate()
+        .into_iter()
+        .filter(|language| {
+            let Ok(lang) = TargetLanguage::try_from(*language) else {
+                return false;
+            };
+            lang.exact_variable_regex().is_match(source.trim())
+                || !nodes_from_indices(&lang.parse_snippet_contexts(source)).is_empty()
+        })
+        .collect()
+}
+
+/// How many metavariables a snippet is made of, as a signal of how costly it
+/// is to match, see [`snippet_complexity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnippetComplexity {
+    /// Distinct metavariables, counting `$x` and `$[x.index]` as one.
+    pub metavariables: usize,
+    /// Text and metavariable parts the snippet splits into, counting every
+    /// occurrence of a metavariable.
+    pub parts: usize,
+}
+
+/// Counts the metavariables of a snippet, the same way the compiler splits it.
+pub fn snippet_complexity(source: &str, lang: &TargetLanguage) -> SnippetComplexity {
+    let occurrences = split_snippet(source, lang);
+    let metavariables = occurrences
+        .iter()
+        .map(|(_, name)| name.split_once('.').map_or(name.as_ref(), |(name, _)| name))
+        .collect::<BTreeSet<_>>()
+        .len();
+    SnippetComplexity {
+        metavariables,
+        parts: 2 * occurrences.len() + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn js() -> TargetLanguage {
+        PatternLanguage::JavaScript.try_into().unwrap()
+    }
+
+    #[test]
+    fn test_valid_snippets() {
+        assert!(validate_snippet("console.log($message)", &js(), false).is_empty());
+        assert!(validate_snippet("$x", &js(), false).is_empty());
+        assert!(validate_snippet("$[name]Handler", &js(), true).is_empty());
+    }
+
+    #[test]
+    fn test_bracketed_metavariables_on_the_lhs() {
+        let snippet = "const $[name]Handler = $value";
+        assert_eq!(
+            validate_snippet(snippet, &js(), false),
+            [SnippetDiagnostic {
+                message: "bracketed metavariables are only allowed on the rhs of a snippet"
+                    .to_owned(),
+                range: ByteRange::new(6, 13),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unparseable_snippets() {
+        assert_eq!(
+            validate_snippet(" foo(( ", &js(), false),
+            [SnippetDiagnostic {
+                message: "snippet does not parse as JavaScript".to_owned(),
+                range: ByteRange::new(1, 6),
+            }]
+        );
+        assert_eq!(validate_snippet("a b c", &js(), true).len(), 1);
+    }
+
+    #[test]
+    fn test_empty_snippets() {
+        assert_eq!(
+            validate_snippet("  ", &js(), false),
+            [SnippetDiagnostic {
+                message: "empty snippet, use `.` to match an absent node".to_owned(),
+                range: ByteRange::new(0, 2),
+            }]
+        );
+        assert!(validate_snippet("", &js(), true).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_languages() {
+        let languages = snippet_languages("foo($x)");
+        assert!(languages.contains(&PatternLanguage::JavaScript));
+        assert!(languages.contains(&PatternLanguage::TypeScript));
+        assert!(!languages.contains(&PatternLanguage::Json));
+
+        let languages = snippet_languages("def $name($args): pass");
+        assert!(languages.contains(&PatternLanguage::Python));
+        assert!(!languages.contains(&PatternLanguage::JavaScript));
+
+        assert!(snippet_languages("$x").contains(&PatternLanguage::Json));
+    }
+
+    #[test]
+    fn test_snippet_complexity() {
+        assert_eq!(
+            snippet_complexity("$fn($a, $b, $a)", &js()),
+            SnippetComplexity {
+                metavariables: 3,
+                parts: 9,
+            }
+        );
+        assert_eq!(
+            snippet_complexity("$[item]_$[item.index]", &js()).metavariables,
+            1
+        );
+        assert_eq!(
+            snippet_complexity("foo()", &js()),
+            SnippetComplexity {
+                metavariables: 0,
+                parts: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_lenient_compilation_of_complete_snippets() {
+        let (pattern, parsed) = compile_snippet_lenient("foo($x)", &js()).unwrap();
+        assert!(matches!(pattern, Pattern::CodeSnippet(_)));
+        assert_eq!(parsed, ByteRange::new(0, 7));
+    }
+
+    #[test]
+    fn test_lenient_compilation_stops_where_parsing_does() {
+        let (pattern, parsed) = compile_snippet_lenient("console.log($message", &js()).unwrap();
+        assert!(matches!(pattern, Pattern::CodeSnippet(_)));
+        assert_eq!(parsed, ByteRange::new(0, 11));
+
+        let (_, parsed) = compile_snippet_lenient("  foo($a, $b) && (", &js()).unwrap();
+        assert_eq!(parsed, ByteRange::new(2, 13));
+    }
+
+    #[test]
+    fn test_lenient_compilation_needs_a_prefix_that_parses() {
+        assert!(compile_snippet_lenient(" ) ", &js()).is_err());
+    }
+}
@@ -8,15 +8,16 @@ use crate::{
 };
 use anyhow::Result;
 use grit_util::{AnalysisLogs, Ast, AstNode, Language};
+use serde::{de::DeserializeOwned, Serialize};
 
 /// Contains various kinds of context about the query being executed.
 pub trait QueryContext: Clone + std::fmt::Debug + Sized + 'static {
     type Node<'a>: AstNode + Clone;
-    type NodePattern: AstNodePattern<Self>;
-    type LeafNodePattern: AstLeafNodePattern<Self>;
+    type NodePattern: AstNodePattern<Self> + Serialize + DeserializeOwned;
+    type LeafNodePattern: AstLeafNodePattern<Self> + Serialize + DeserializeOwned;
     type ExecContext<'a>: ExecContext<'a, Self>;
     type Binding<'a>: Binding<'a, Self>;
-    type CodeSnippet: CodeSnippet<Self>;
+    type CodeSnippet: CodeSnippet<Self> + Serialize + DeserializeOwned;
     type ResolvedPattern<'a>: ResolvedPattern<'a, Self>;
     type Language<'a>: Language<Node<'a> = Self::Node<'a>>;
     type File<'a>: File<'a, Self>;
@@ -33,6 +34,9 @@ pub trait ExecContext<'a, Q: QueryContext> {
 
     fn ignore_limit_pattern(&self) -> bool;
 
+    /// Whether the query was cancelled, in which case traversals stop early.
+    fn is_cancelled(&self) -> bool;
+
     fn call_built_in(
         &self,
         call: &'a CallBuiltIn<Q>,
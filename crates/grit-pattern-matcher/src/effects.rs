@@ -1,9 +1,14 @@
 use crate::context::QueryContext;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EffectKind {
+    #[default]
     Rewrite,
+    /// Inserts the pattern after the binding, keeping the binding itself.
     Insert,
+    /// Inserts the pattern before the binding, keeping the binding itself.
+    InsertBefore,
 }
 
 #[derive(Debug, Clone)]
@@ -75,7 +75,7 @@ pub fn get_top_level_intervals_in_range<Q: QueryContext>(
         if e_interval.1 < left {
             break;
         }
-        if matches!(e.effect.kind, EffectKind::Insert)
+        if matches!(e.effect.kind, EffectKind::Insert | EffectKind::InsertBefore)
             && e_interval.0 >= left
             && e_interval.1 <= right
         {
@@ -15,6 +15,7 @@ mod container;
 mod contains;
 mod divide;
 mod dynamic_snippet;
+mod edit_distance;
 mod equal;
 mod every;
 mod file_pattern;
@@ -76,6 +77,7 @@ pub use container::{Container, PatternOrResolved, PatternOrResolvedMut};
 pub use contains::Contains;
 pub use divide::Divide;
 pub use dynamic_snippet::{DynamicList, DynamicPattern, DynamicSnippet, DynamicSnippetPart};
+pub use edit_distance::EditDistance;
 pub use equal::Equal;
 pub use every::Every;
 pub use file_pattern::FilePattern;
@@ -88,7 +90,7 @@ pub use int_constant::IntConstant;
 pub use iter_pattern::{PatternOrPredicate, PatternOrPredicateIterator};
 pub use like::Like;
 pub use limit::Limit;
-pub use list::List;
+pub use list::{List, Spread, SpreadArity};
 pub use list_index::{to_unsigned, ContainerOrIndex, ListIndex, ListOrContainer};
 pub use log::{Log, VariableInfo};
 pub use map::GritMap;
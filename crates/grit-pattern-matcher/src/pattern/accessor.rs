@@ -12,21 +12,24 @@ use crate::{
 };
 use anyhow::{bail, Result};
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum AccessorMap<Q: QueryContext> {
     Container(Container<Q>),
     Map(GritMap<Q>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Accessor<Q: QueryContext> {
     pub map: AccessorMap<Q>,
     pub key: AccessorKey,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AccessorKey {
     String(String),
     Variable(Variable),
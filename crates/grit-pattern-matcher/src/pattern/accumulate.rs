@@ -12,12 +12,14 @@ use crate::{
 };
 use anyhow::{bail, Result};
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Accumulate<Q: QueryContext> {
-    pub(crate) left: Pattern<Q>,
-    pub(crate) right: Pattern<Q>,
+    pub left: Pattern<Q>,
+    pub right: Pattern<Q>,
     dynamic_right: Option<DynamicPattern<Q>>,
 }
 
@@ -11,8 +11,10 @@ use crate::{
 use anyhow::{bail, Result};
 use core::fmt::Debug;
 use grit_util::{AnalysisLogs, AstNode};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct After<Q: QueryContext> {
     pub after: Pattern<Q>,
 }
@@ -7,8 +7,10 @@ use super::{
 use crate::context::QueryContext;
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct And<Q: QueryContext> {
     pub patterns: Vec<Pattern<Q>>,
 }
@@ -42,7 +44,8 @@ impl<Q: QueryContext> Matcher<Q> for And<Q> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PrAnd<Q: QueryContext> {
     pub predicates: Vec<Predicate<Q>>,
 }
@@ -8,8 +8,10 @@ use crate::context::QueryContext;
 use anyhow::Result;
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Any<Q: QueryContext> {
     pub patterns: Vec<Pattern<Q>>,
 }
@@ -55,7 +57,8 @@ impl<Q: QueryContext> Matcher<Q> for Any<Q> {
         }
     }
 }
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PrAny<Q: QueryContext> {
     pub predicates: Vec<Predicate<Q>>,
 }
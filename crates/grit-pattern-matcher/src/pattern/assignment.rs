@@ -8,8 +8,10 @@ use super::{
 use crate::context::{ExecContext, QueryContext};
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Assignment<Q: QueryContext> {
     pub container: Container<Q>,
     pub pattern: Pattern<Q>,
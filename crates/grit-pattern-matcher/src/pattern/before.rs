@@ -10,8 +10,10 @@ use crate::{
 };
 use anyhow::{bail, Result};
 use grit_util::{AnalysisLogs, AstNode};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Before<Q: QueryContext> {
     pub before: Pattern<Q>,
 }
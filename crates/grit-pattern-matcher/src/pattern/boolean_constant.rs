@@ -6,8 +6,9 @@ use super::{
 use crate::context::{ExecContext, QueryContext};
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BooleanConstant {
     pub value: bool,
 }
@@ -5,8 +5,10 @@ use super::{
 use crate::context::QueryContext;
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Bubble<Q: QueryContext> {
     pub pattern_def: PatternDefinition<Q>,
     pub args: Vec<Option<Pattern<Q>>>,
@@ -8,8 +8,10 @@ use super::{
 use crate::{context::ExecContext, context::QueryContext};
 use anyhow::{bail, Result};
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Call<Q: QueryContext> {
     pub index: usize,
     pub args: Vec<Option<Pattern<Q>>>,
@@ -43,9 +45,10 @@ impl<Q: QueryContext> Matcher<Q> for Call<Q> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PrCall<Q: QueryContext> {
-    index: usize,
+    pub index: usize,
     pub args: Vec<Option<Pattern<Q>>>,
 }
 
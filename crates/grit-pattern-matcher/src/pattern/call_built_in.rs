@@ -6,11 +6,13 @@ use super::{
 use crate::context::{ExecContext, QueryContext};
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
 // todo we can probably use a macro to generate a function that takes a vec and
 // and calls the input function with the vec args unpacked.
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct CallBuiltIn<Q: QueryContext> {
     pub index: usize,
     pub args: Vec<Option<Pattern<Q>>>,
@@ -4,6 +4,7 @@ use super::{
 };
 use crate::context::QueryContext;
 use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
 
 /// A `Container` represents anything which "contains" a reference to a Pattern.
 ///
@@ -11,7 +12,8 @@ use anyhow::{bail, Result};
 /// - Variable: a variable reference (ex. `$foo`)
 /// - Accessor: a map accessor (ex. `$foo.bar`)
 /// - ListIndex: a list index (ex. `$foo[0]`)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum Container<Q: QueryContext> {
     Variable(Variable),
     Accessor(Box<Accessor<Q>>),
@@ -8,12 +8,14 @@ use crate::{
     pattern::resolved_pattern::File,
 };
 use crate::{constants::GLOBAL_VARS_SCOPE_INDEX, context::ExecContext};
-use anyhow::Result;
+use anyhow::{bail, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
 use grit_util::{AstCursor, AstNode};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Contains<Q: QueryContext> {
     pub contains: Pattern<Q>,
     pub until: Option<Pattern<Q>>,
@@ -44,6 +46,9 @@ fn execute_until<'a, Q: QueryContext>(
     let mut cursor = node.walk();
     let mut still_computing = true;
     while still_computing {
+        if context.is_cancelled() {
+            bail!("matching was cancelled");
+        }
         let node = cursor.node();
         let node_lhs = ResolvedPattern::from_node_binding(node);
 
@@ -1,5 +1,6 @@
 use super::{
     accessor::Accessor,
+    assignment::Assignment,
     call_built_in::CallBuiltIn,
     functions::{CallForeignFunction, CallFunction},
     list_index::ListIndex,
@@ -11,24 +12,51 @@ use super::{
 use crate::context::{ExecContext, QueryContext};
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DynamicSnippetPart {
     String(String),
     Variable(Variable),
+    /// The position of the list element bound to the variable, written as
+    /// `$[item.index]` inside a snippet.
+    Index(Variable),
+    /// The quotes delimiting the string literal bound to the variable,
+    /// written as `$[text.quote]` inside a snippet.
+    Quote(Variable),
+    /// The source bound to the variable as written, which for a list covers
+    /// its separators and spacing too, written as `$[args.text]` inside a
+    /// snippet.
+    Text(Variable),
+    /// The whitespace indenting the line the variable's binding starts on,
+    /// written as `$[stmt.indent]` inside a snippet.
+    Indent(Variable),
+    /// The kind of the node bound to the variable, such as `object` or
+    /// `statement_block`, written as `$[value.kind]` inside a snippet.
+    Kind(Variable),
+    /// The source of the named sibling preceding the node bound to the
+    /// variable, skipping comments, written as `$[stmt.prev]` inside a
+    /// snippet. Renders as nothing if the node is the first of its siblings.
+    PreviousSibling(Variable),
+    /// The source of the named sibling following the node bound to the
+    /// variable, skipping comments, written as `$[stmt.next]` inside a
+    /// snippet. Renders as nothing if the node is the last of its siblings.
+    NextSibling(Variable),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DynamicSnippet {
     pub parts: Vec<DynamicSnippetPart>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct DynamicList<Q: QueryContext> {
     pub elements: Vec<DynamicPattern<Q>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum DynamicPattern<Q: QueryContext> {
     Variable(Variable),
     Accessor(Box<Accessor<Q>>),
@@ -38,6 +66,9 @@ pub enum DynamicPattern<Q: QueryContext> {
     CallBuiltIn(CallBuiltIn<Q>),
     CallFunction(CallFunction<Q>),
     CallForeignFunction(CallForeignFunction<Q>),
+    /// Binds the computed value to a variable, so it can be reused elsewhere,
+    /// and also evaluates to that value, e.g. `` $combined = `$[a]_$[b]` ``.
+    Assignment(Box<Assignment<Q>>),
 }
 
 impl<Q: QueryContext> DynamicPattern<Q> {
@@ -0,0 +1,79 @@
+use super::{
+    functions::{Evaluator, FuncEvaluation},
+    patterns::{Pattern, PatternName},
+    variable::Variable,
+    State,
+};
+use crate::context::{ExecContext, QueryContext};
+use anyhow::Result;
+use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
+
+/// Holds when the text bound to `var` is within `max_distance` single
+/// character insertions, deletions or substitutions of the text of `target`.
+///
+/// Computing the distance takes time proportional to the product of the
+/// lengths of both texts, although texts whose lengths differ by more than
+/// `max_distance` are rejected up front.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct EditDistance<Q: QueryContext> {
+    pub var: Variable,
+    pub target: Pattern<Q>,
+    pub max_distance: usize,
+}
+
+impl<Q: QueryContext> EditDistance<Q> {
+    pub fn new(var: Variable, target: Pattern<Q>, max_distance: usize) -> Self {
+        Self {
+            var,
+            target,
+            max_distance,
+        }
+    }
+}
+
+impl<Q: QueryContext> PatternName for EditDistance<Q> {
+    fn name(&self) -> &'static str {
+        "EDIT_DISTANCE"
+    }
+}
+
+impl<Q: QueryContext> Evaluator<Q> for EditDistance<Q> {
+    fn execute_func<'a>(
+        &'a self,
+        state: &mut State<'a, Q>,
+        context: &'a Q::ExecContext<'a>,
+        logs: &mut AnalysisLogs,
+    ) -> Result<FuncEvaluation<Q>> {
+        let text = self.var.text(state, context.language())?;
+        let target = self.target.text(state, context, logs)?;
+        Ok(FuncEvaluation {
+            predicator: is_within_edit_distance(&text, &target, self.max_distance),
+            ret_val: None,
+        })
+    }
+}
+
+fn is_within_edit_distance(a: &str, b: &str, max_distance: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max_distance {
+        return false;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        // Distances never decrease from one row to the next.
+        if current.iter().min().is_some_and(|min| *min > max_distance) {
+            return false;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()] <= max_distance
+}
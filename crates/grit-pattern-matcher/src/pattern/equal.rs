@@ -7,8 +7,10 @@ use super::{
 use crate::context::{ExecContext, QueryContext};
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Equal<Q: QueryContext> {
     pub var: Variable,
     pub pattern: Pattern<Q>,
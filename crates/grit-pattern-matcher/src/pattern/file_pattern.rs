@@ -10,8 +10,10 @@ use crate::{
 use crate::{context::QueryContext, pattern::resolved_pattern::File};
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct FilePattern<Q: QueryContext> {
     pub name: Pattern<Q>,
     pub body: Pattern<Q>,
@@ -6,8 +6,10 @@ use super::{
 use crate::context::QueryContext;
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Files<Q: QueryContext> {
     pub pattern: Pattern<Q>,
 }
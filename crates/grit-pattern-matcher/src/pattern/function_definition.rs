@@ -8,6 +8,7 @@ use super::{
 use crate::context::QueryContext;
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
 pub trait FunctionDefinition<Q: QueryContext> {
     fn call<'a>(
@@ -19,7 +20,8 @@ pub trait FunctionDefinition<Q: QueryContext> {
     ) -> Result<FuncEvaluation<Q>>;
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct GritFunctionDefinition<Q: QueryContext> {
     pub name: String,
     pub scope: usize,
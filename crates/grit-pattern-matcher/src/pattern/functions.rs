@@ -7,6 +7,7 @@ use crate::{context::ExecContext, context::QueryContext};
 use anyhow::{bail, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct FuncEvaluation<'a, Q: QueryContext> {
@@ -23,7 +24,8 @@ pub trait Evaluator<Q: QueryContext>: Debug {
     ) -> Result<FuncEvaluation<Q>>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct CallFunction<Q: QueryContext> {
     pub index: usize,
     pub args: Vec<Option<Pattern<Q>>>,
@@ -69,7 +71,8 @@ impl<Q: QueryContext> PatternName for CallFunction<Q> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct CallForeignFunction<Q: QueryContext> {
     pub index: usize,
     pub args: Vec<Option<Pattern<Q>>>,
@@ -8,8 +8,10 @@ use crate::context::QueryContext;
 use anyhow::{bail, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct If<Q: QueryContext> {
     pub if_: Predicate<Q>,
     pub then: Pattern<Q>,
@@ -49,7 +51,8 @@ impl<Q: QueryContext> Matcher<Q> for If<Q> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PrIf<Q: QueryContext> {
     pub if_: Predicate<Q>,
     pub then: Predicate<Q>,
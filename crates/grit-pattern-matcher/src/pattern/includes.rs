@@ -7,8 +7,10 @@ use crate::context::{ExecContext, QueryContext};
 use anyhow::{Context as _, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Includes<Q: QueryContext> {
     pub includes: Pattern<Q>,
 }
@@ -107,6 +109,7 @@ fn execute<'a, Q: QueryContext>(
         | Pattern::Divide(_)
         | Pattern::Modulo(_)
         | Pattern::Dots
+        | Pattern::Spread(_)
         | Pattern::Sequential(_)
         | Pattern::Like(_) => {
             let resolved = Q::ResolvedPattern::from_pattern(pattern, state, context, logs)
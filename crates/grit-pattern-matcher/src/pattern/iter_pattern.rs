@@ -93,6 +93,9 @@ impl<Q: QueryContext> Predicate<Q> {
                 .map(PatternOrPredicate::Pattern)
                 .collect(),
             Predicate::Equal(equal) => vec![PatternOrPredicate::Pattern(&equal.pattern)],
+            Predicate::EditDistance(edit_distance) => {
+                vec![PatternOrPredicate::Pattern(&edit_distance.target)]
+            }
             Predicate::Assignment(assignment) => {
                 vec![PatternOrPredicate::Pattern(&assignment.pattern)]
             }
@@ -144,6 +147,9 @@ impl<Q: QueryContext> DynamicPattern<Q> {
             super::dynamic_snippet::DynamicPattern::CallForeignFunction(c) => {
                 args_children(&c.args)
             }
+            super::dynamic_snippet::DynamicPattern::Assignment(a) => {
+                vec![PatternOrPredicate::Pattern(&a.pattern)]
+            }
         }
     }
 }
@@ -305,6 +311,7 @@ impl<Q: QueryContext> Pattern<Q> {
                 PatternOrPredicate::Pattern(&a.rhs),
             ],
             Pattern::Dots => Vec::new(),
+            Pattern::Spread(_) => Vec::new(),
             Pattern::Sequential(s) => s
                 .iter()
                 .map(|s| PatternOrPredicate::Pattern(&s.pattern))
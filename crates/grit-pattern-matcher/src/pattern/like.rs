@@ -6,9 +6,11 @@ use crate::context::QueryContext;
 use anyhow::{anyhow, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Like<Q: QueryContext> {
     pub like: Pattern<Q>,
     pub threshold: Pattern<Q>,
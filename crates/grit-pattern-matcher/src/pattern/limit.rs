@@ -5,15 +5,19 @@ use super::{
 use crate::{context::ExecContext, context::QueryContext};
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
     Arc,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Limit<Q: QueryContext> {
     pub pattern: Pattern<Q>,
     pub limit: usize,
+    /// Counts matches while running, so a loaded pattern starts from zero.
+    #[serde(skip)]
     pub invocation_count: Arc<AtomicUsize>,
 }
 
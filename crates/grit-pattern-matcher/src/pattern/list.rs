@@ -3,14 +3,17 @@ use super::{
     patterns::{Matcher, Pattern, PatternName},
     resolved_pattern::ResolvedPattern,
     state::State,
+    variable::Variable,
 };
 use crate::context::QueryContext;
 use anyhow::{anyhow, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct List<Q: QueryContext> {
     pub patterns: Vec<Pattern<Q>>,
 }
@@ -26,6 +29,66 @@ impl<Q: QueryContext> List<Q> {
     }
 }
 
+/// A named spread metavariable (`$...name`) inside a list pattern.
+///
+/// The spread binds the run of elements it covers as a list. Spreads are
+/// greedy by default: among the ways the surrounding patterns can match, the
+/// one where the spread covers the most elements wins. A lazy spread
+/// (`$...name?`) prefers the fewest elements instead. Because later spreads
+/// only see what earlier ones left over, in `[$...a, $mid, $...b]` the
+/// laziness of `$...a` alone decides where `$mid` is anchored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spread {
+    pub variable: Variable,
+    pub lazy: bool,
+    /// How many elements the spread may cover, if limited.
+    #[serde(default)]
+    pub arity: Option<SpreadArity>,
+}
+
+impl Spread {
+    pub fn new(variable: Variable, lazy: bool) -> Self {
+        Self {
+            variable,
+            lazy,
+            arity: None,
+        }
+    }
+
+    pub fn with_arity(mut self, arity: SpreadArity) -> Self {
+        self.arity = Some(arity);
+        self
+    }
+
+    fn allows(&self, length: usize) -> bool {
+        match self.arity {
+            Some(arity) => arity.contains(length),
+            None => true,
+        }
+    }
+}
+
+/// Bounds on the number of elements a spread covers, written after its name:
+/// `$...args#2` covers exactly two elements, `$...args#2+` at least two and
+/// `$...args#2-` at most two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpreadArity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl SpreadArity {
+    pub fn contains(&self, length: usize) -> bool {
+        length >= self.min && !self.max.is_some_and(|max| length > max)
+    }
+}
+
+impl PatternName for Spread {
+    fn name(&self) -> &'static str {
+        "SPREAD"
+    }
+}
+
 impl<Q: QueryContext> PatternName for List<Q> {
     fn name(&self) -> &'static str {
         "LIST"
@@ -61,6 +124,40 @@ fn execute_assoc<'a, Q: QueryContext>(
 ) -> Result<bool> {
     let mut working_state = current_state.clone();
     match patterns {
+        [Pattern::Spread(spread), tail_patterns @ ..] => {
+            if let Some(Pattern::Dots | Pattern::Spread(_)) = tail_patterns.first() {
+                return Err(anyhow!("Multiple subsequent spreads are not allowed."));
+            }
+            let mut lengths: Vec<usize> = (0..=children.len())
+                .filter(|length| spread.allows(*length))
+                .collect();
+            if !spread.lazy {
+                lengths.reverse();
+            }
+            for length in lengths {
+                let mut attempt_state = working_state.clone();
+                let run = Q::ResolvedPattern::from_list_parts(
+                    children[..length]
+                        .iter()
+                        .map(|child| child.clone().into_owned()),
+                );
+                if spread
+                    .variable
+                    .execute(&run, &mut attempt_state, context, logs)?
+                    && execute_assoc(
+                        tail_patterns,
+                        &children[length..],
+                        &mut attempt_state,
+                        context,
+                        logs,
+                    )?
+                {
+                    *current_state = attempt_state;
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
         // short circuit for common case
         [pattern_for_first_node, Pattern::Dots] => {
             if children.is_empty() {
@@ -75,7 +172,9 @@ fn execute_assoc<'a, Q: QueryContext>(
             }
         }
         // short circuit for common case
-        [Pattern::Dots, pattern_for_last_node] => {
+        [Pattern::Dots, pattern_for_last_node]
+            if !matches!(pattern_for_last_node, Pattern::Spread(_)) =>
+        {
             if let Some(last_node) = children.last() {
                 if pattern_for_last_node.execute(last_node, &mut working_state, context, logs)? {
                     *current_state = working_state;
@@ -88,8 +187,14 @@ fn execute_assoc<'a, Q: QueryContext>(
             }
         }
         [Pattern::Dots, head_pattern, tail_patterns @ ..] => {
-            if let Pattern::Dots = head_pattern {
-                return Err(anyhow!("Multiple subsequent dots are not allowed."));
+            match head_pattern {
+                Pattern::Dots => {
+                    return Err(anyhow!("Multiple subsequent dots are not allowed."));
+                }
+                Pattern::Spread(_) => {
+                    return Err(anyhow!("Multiple subsequent spreads are not allowed."));
+                }
+                _ => {}
             }
             for index in 0..children.len() {
                 if head_pattern.execute(&children[index], &mut working_state, context, logs)?
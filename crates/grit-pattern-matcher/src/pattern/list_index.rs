@@ -12,20 +12,24 @@ use crate::{
 };
 use anyhow::{anyhow, bail, Result};
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum ListOrContainer<Q: QueryContext> {
     Container(Container<Q>),
     List(List<Q>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum ContainerOrIndex<Q: QueryContext> {
     Container(Container<Q>),
     Index(isize),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct ListIndex<Q: QueryContext> {
     pub list: ListOrContainer<Q>,
     pub index: ContainerOrIndex<Q>,
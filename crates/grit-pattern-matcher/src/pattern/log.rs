@@ -3,7 +3,7 @@ use super::{
     patterns::{Matcher, Pattern, PatternName},
     resolved_pattern::ResolvedPattern,
     state::State,
-    variable::{get_file_name, Variable},
+    variable::{get_file_name, is_reserved_metavariable, Variable},
 };
 use crate::{
     binding::Binding,
@@ -11,8 +11,9 @@ use crate::{
 };
 use anyhow::Result;
 use grit_util::{AnalysisLogBuilder, AnalysisLogs};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VariableInfo {
     name: String,
     variable: Variable,
@@ -24,15 +25,71 @@ impl VariableInfo {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Log<Q: QueryContext> {
     pub variable: Option<VariableInfo>,
     pub message: Option<Pattern<Q>>,
+    /// Log every bound metavariable instead, see [`Log::bindings`].
+    #[serde(default)]
+    pub all_bindings: bool,
 }
 
 impl<Q: QueryContext> Log<Q> {
     pub fn new(variable: Option<VariableInfo>, message: Option<Pattern<Q>>) -> Self {
-        Self { variable, message }
+        Self {
+            variable,
+            message,
+            all_bindings: false,
+        }
+    }
+
+    /// Logs each metavariable bound at the point it is reached, with the name
+    /// of the metavariable as the message and its value as the source. Metavariables without a value and the reserved
+    /// ones, such as `$filename`, are left out.
+    pub fn bindings() -> Self {
+        Self {
+            variable: None,
+            message: None,
+            all_bindings: true,
+        }
+    }
+
+    fn add_bindings_logs<'a>(
+        &'a self,
+        state: &mut State<'a, Q>,
+        context: &'a Q::ExecContext<'a>,
+        logs: &mut AnalysisLogs,
+    ) -> Result<bool> {
+        let file = get_file_name(state, context.language())?;
+        for scope in state.bindings.iter() {
+            let Some(contents) = scope.last() else {
+                continue;
+            };
+            for content in contents.iter() {
+                let Some(value) = &content.value else {
+                    continue;
+                };
+                if is_reserved_metavariable(&content.name, Some(context.language())) {
+                    continue;
+                }
+                let mut log_builder = AnalysisLogBuilder::default();
+                #[allow(clippy::unnecessary_cast)]
+                log_builder
+                    .level(441 as u16)
+                    .file(file.clone())
+                    .message(content.name.clone())
+                    .source(value.text(&state.files, context.language())?.into_owned());
+                if let Some(position) = value
+                    .get_last_binding()
+                    .and_then(|node| node.position(context.language()))
+                {
+                    log_builder.range(position);
+                }
+                logs.push(log_builder.build()?);
+            }
+        }
+        Ok(true)
     }
 
     fn add_log<'a>(
@@ -41,6 +98,9 @@ impl<Q: QueryContext> Log<Q> {
         context: &'a Q::ExecContext<'a>,
         logs: &mut AnalysisLogs,
     ) -> Result<bool> {
+        if self.all_bindings {
+            return self.add_bindings_logs(state, context, logs);
+        }
         let mut message = String::new();
         if let Some(user_message) = &self.message {
             let resolved = Q::ResolvedPattern::from_pattern(user_message, state, context, logs)?;
@@ -6,9 +6,11 @@ use super::{
 use crate::context::QueryContext;
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct GritMap<Q: QueryContext> {
     pub elements: BTreeMap<String, Pattern<Q>>,
 }
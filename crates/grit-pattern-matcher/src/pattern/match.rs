@@ -12,8 +12,10 @@ use crate::{
 };
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Match<Q: QueryContext> {
     pub val: Container<Q>,
     pub pattern: Option<Pattern<Q>>,
@@ -7,8 +7,10 @@ use super::{
 use crate::context::QueryContext;
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Maybe<Q: QueryContext> {
     pub pattern: Pattern<Q>,
 }
@@ -41,9 +43,10 @@ impl<Q: QueryContext> PatternName for Maybe<Q> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PrMaybe<Q: QueryContext> {
-    pub(crate) predicate: Predicate<Q>,
+    pub predicate: Predicate<Q>,
 }
 
 impl<Q: QueryContext> PrMaybe<Q> {
@@ -8,8 +8,10 @@ use crate::context::QueryContext;
 use anyhow::{bail, Ok, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Not<Q: QueryContext> {
     pub pattern: Pattern<Q>,
 }
@@ -40,9 +42,10 @@ impl<Q: QueryContext> Matcher<Q> for Not<Q> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PrNot<Q: QueryContext> {
-    pub(crate) predicate: Predicate<Q>,
+    pub predicate: Predicate<Q>,
 }
 
 impl<Q: QueryContext> PrNot<Q> {
@@ -10,8 +10,10 @@ use crate::{binding::Binding, context::QueryContext};
 use anyhow::Result;
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Or<Q: QueryContext> {
     pub patterns: Vec<Pattern<Q>>,
 }
@@ -66,7 +68,8 @@ impl<Q: QueryContext> Matcher<Q> for Or<Q> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct PrOr<Q: QueryContext> {
     pub predicates: Vec<Predicate<Q>>,
 }
@@ -23,7 +23,7 @@ use super::{
     int_constant::IntConstant,
     like::Like,
     limit::Limit,
-    list::List,
+    list::{List, Spread},
     list_index::ListIndex,
     log::Log,
     map::GritMap,
@@ -55,6 +55,7 @@ use crate::{
 use anyhow::{bail, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
 pub trait Matcher<Q: QueryContext>: Debug {
     // it is important that any implementors of Pattern
@@ -78,7 +79,8 @@ pub trait PatternName {
     fn name(&self) -> &'static str;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum Pattern<Q: QueryContext> {
     AstNode(Box<Q::NodePattern>),
     List(Box<List<Q>>),
@@ -132,6 +134,7 @@ pub enum Pattern<Q: QueryContext> {
     Divide(Box<Divide<Q>>),
     Modulo(Box<Modulo<Q>>),
     Dots,
+    Spread(Spread),
     Sequential(Sequential<Q>),
     Like(Box<Like<Q>>),
 }
@@ -215,6 +218,7 @@ impl<Q: QueryContext> PatternName for Pattern<Q> {
             Pattern::Not(not) => not.name(),
             Pattern::If(if_) => if_.name(),
             Pattern::Dots => "DOTS",
+            Pattern::Spread(spread) => spread.name(),
             Pattern::Dynamic(dynamic_pattern) => dynamic_pattern.name(),
             Pattern::Sequential(sequential) => sequential.name(),
             Pattern::Like(like) => like.name(),
@@ -300,6 +304,7 @@ impl<Q: QueryContext> Matcher<Q> for Pattern<Q> {
             Pattern::Not(not) => not.execute(binding, state, context, logs),
             Pattern::If(if_) => if_.execute(binding, state, context, logs),
             Pattern::Dots => bail!("Dots should only be directly within a list pattern."),
+            Pattern::Spread(_) => bail!("Spreads should only be directly within a list pattern."),
             Pattern::Dynamic(pattern) => pattern.execute(binding, state, context, logs),
             Pattern::Sequential(sequential) => sequential.execute(binding, state, context, logs),
             Pattern::Like(like) => like.execute(binding, state, context, logs),
@@ -4,6 +4,7 @@ use super::{
     any::PrAny,
     assignment::Assignment,
     call::PrCall,
+    edit_distance::EditDistance,
     equal::Equal,
     functions::{Evaluator, FuncEvaluation},
     log::Log,
@@ -21,8 +22,10 @@ use crate::context::QueryContext;
 use anyhow::Result;
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum Predicate<Q: QueryContext> {
     Call(Box<PrCall<Q>>),
     Not(Box<PrNot<Q>>),
@@ -37,6 +40,7 @@ pub enum Predicate<Q: QueryContext> {
     Log(Log<Q>),
     Match(Box<Match<Q>>),
     Equal(Box<Equal<Q>>),
+    EditDistance(Box<EditDistance<Q>>),
     Assignment(Box<Assignment<Q>>),
     Accumulate(Box<Accumulate<Q>>),
     Return(Box<PrReturn<Q>>),
@@ -58,6 +62,7 @@ impl<Q: QueryContext> PatternName for Predicate<Q> {
             Predicate::Log(log) => log.name(),
             Predicate::Match(match_) => match_.name(),
             Predicate::Equal(equal) => equal.name(),
+            Predicate::EditDistance(edit_distance) => edit_distance.name(),
             Predicate::Assignment(assignment) => assignment.name(),
             Predicate::Accumulate(accumulate) => accumulate.name(),
             Predicate::Return(return_) => return_.name(),
@@ -82,6 +87,9 @@ impl<Q: QueryContext> Evaluator<Q> for Predicate<Q> {
             Predicate::Log(log) => log.execute_func(state, context, logs),
             Predicate::Match(match_) => match_.execute_func(state, context, logs),
             Predicate::Equal(equal) => equal.execute_func(state, context, logs),
+            Predicate::EditDistance(edit_distance) => {
+                edit_distance.execute_func(state, context, logs)
+            }
             Predicate::True => Ok(FuncEvaluation {
                 predicator: true,
                 ret_val: None,
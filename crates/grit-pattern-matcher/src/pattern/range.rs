@@ -6,8 +6,9 @@ use super::{
 use crate::context::{ExecContext, QueryContext};
 use anyhow::{anyhow, Result};
 use grit_util::{AnalysisLogs, UtilRange};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Point {
     line: u32,
     column: Option<u32>,
@@ -29,7 +30,7 @@ impl Point {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Range {
     pub start: Option<Point>,
     pub end: Option<Point>,
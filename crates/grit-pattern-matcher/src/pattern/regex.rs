@@ -12,14 +12,17 @@ use anyhow::{anyhow, bail, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct RegexPattern<Q: QueryContext> {
     pub regex: RegexLike<Q>,
     pub variables: Vec<Variable>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub enum RegexLike<Q: QueryContext> {
     Regex(String),
     Pattern(Box<Pattern<Q>>),
@@ -162,6 +162,14 @@ pub trait ResolvedPattern<'a, Q: QueryContext>: Clone + Debug + PartialEq {
         language: &Q::Language<'a>,
     ) -> Result<()>;
 
+    /// Separates text inserted before `binding` from it, as
+    /// [`ResolvedPattern::normalize_insert`] does for text inserted after it.
+    fn normalize_insert_before(
+        &mut self,
+        binding: &Q::Binding<'a>,
+        language: &Q::Language<'a>,
+    ) -> Result<()>;
+
     fn position(&self, language: &Q::Language<'a>) -> Option<Range>;
 
     fn push_binding(&mut self, binding: Q::Binding<'a>) -> Result<()>;
@@ -7,19 +7,25 @@ use super::{
     State,
 };
 use crate::{
-    context::QueryContext,
+    context::{ExecContext, QueryContext},
     effects::{Effect, EffectKind},
 };
 use anyhow::{bail, Result};
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Rewrite<Q: QueryContext> {
     pub left: Pattern<Q>,
     pub right: DynamicPattern<Q>,
     pub annotation: Option<String>,
+    /// Whether the right-hand side replaces the match or is inserted next to
+    /// it, as with `insert_before(...)` and `insert_after(...)`.
+    #[serde(default)]
+    pub kind: EffectKind,
 }
 
 impl<Q: QueryContext> Rewrite<Q> {
@@ -28,9 +34,15 @@ impl<Q: QueryContext> Rewrite<Q> {
             left,
             right,
             annotation,
+            kind: EffectKind::Rewrite,
         }
     }
 
+    pub fn with_kind(mut self, kind: EffectKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
     /**
      * Execute a rewrite rule, returning the new binding.
      *
@@ -82,11 +94,25 @@ impl<Q: QueryContext> Rewrite<Q> {
         };
         let replacement: Q::ResolvedPattern<'_> =
             ResolvedPattern::from_dynamic_pattern(&self.right, state, context, logs)?;
-        let effects = bindings.map(|b| Effect {
-            binding: b.clone(),
-            pattern: replacement.clone(),
-            kind: EffectKind::Rewrite,
-        });
+        let mut effects = Vec::new();
+        for binding in bindings {
+            let mut pattern = replacement.clone();
+            match self.kind {
+                EffectKind::Rewrite => {}
+                EffectKind::Insert => {
+                    let is_first = !state.effects.iter().any(|e| e.binding == binding);
+                    pattern.normalize_insert(&binding, is_first, context.language())?;
+                }
+                EffectKind::InsertBefore => {
+                    pattern.normalize_insert_before(&binding, context.language())?;
+                }
+            }
+            effects.push(Effect {
+                binding,
+                pattern,
+                kind: self.kind,
+            });
+        }
         state.effects.extend(effects);
         Ok(true)
     }
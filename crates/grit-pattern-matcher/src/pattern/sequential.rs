@@ -6,9 +6,11 @@ use super::{
 use crate::context::QueryContext;
 use anyhow::Result;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 use std::ops;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Sequential<Q: QueryContext>(pub Vec<Step<Q>>);
 
 impl<Q: QueryContext> Matcher<Q> for Sequential<Q> {
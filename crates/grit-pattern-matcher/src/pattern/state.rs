@@ -3,7 +3,7 @@ use crate::{
     binding::Binding,
     constants::MATCH_VAR,
     context::QueryContext,
-    effects::Effect,
+    effects::{Effect, EffectKind},
     file_owners::FileOwner,
     intervals::{earliest_deadline_sort, get_top_level_intervals_in_range, Interval},
     pattern::resolved_pattern::ResolvedPattern,
@@ -164,8 +164,13 @@ fn get_top_level_effect_ranges<'a, Q: QueryContext>(
             let byte_range = binding
                 .range(language)
                 .ok_or_else(|| anyhow!("binding has no range"))?;
-            let end_byte = byte_range.end as u32;
             let start_byte = byte_range.start as u32;
+            // Text inserted before a binding doesn't touch the binding itself,
+            // so it must not be ordered as if it spanned it.
+            let end_byte = match effect.kind {
+                EffectKind::InsertBefore => start_byte,
+                EffectKind::Rewrite | EffectKind::Insert => byte_range.end as u32,
+            };
             Ok(EffectRange {
                 range: start_byte..end_byte,
                 effect: effect.clone(),
@@ -7,8 +7,9 @@ use crate::context::{ExecContext, QueryContext};
 use anyhow::Result;
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StringConstant {
     pub text: String,
 }
@@ -12,15 +12,16 @@ use crate::{
 use anyhow::{bail, Result};
 use core::fmt::Debug;
 use grit_util::{constants::GRIT_METAVARIABLE_PREFIX, AnalysisLogs, ByteRange, Language};
+use serde::{Deserialize, Serialize};
 use std::{borrow::Cow, collections::BTreeSet};
 
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, Serialize, Deserialize)]
 pub struct Variable {
     pub scope: usize,
     pub index: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VariableSourceLocations {
     pub name: String,
     pub file: String,
@@ -8,8 +8,10 @@ use crate::context::QueryContext;
 use anyhow::Result;
 use core::fmt::Debug;
 use grit_util::AnalysisLogs;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Where<Q: QueryContext> {
     pub pattern: Pattern<Q>,
     pub side_condition: Predicate<Q>,
@@ -7,8 +7,10 @@ use crate::{binding::Binding, context::QueryContext};
 use anyhow::Result;
 use core::fmt::Debug;
 use grit_util::{AnalysisLogs, AstNode};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Within<Q: QueryContext> {
     pub pattern: Pattern<Q>,
 }
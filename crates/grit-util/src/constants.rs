@@ -11,5 +11,9 @@ pub static VARIABLE_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"\$(\.\.\.|[A-Za-z_][A-Za-z0-9_]*)").unwrap());
 pub static REPLACED_VARIABLE_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"µ(\.\.\.|[A-Za-z_][A-Za-z0-9_]*)").unwrap());
-pub static BRACKET_VAR_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"\$\[([A-Za-z_][A-Za-z0-9_]*)\]").unwrap());
+pub static BRACKET_VAR_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"\$\[([A-Za-z_][A-Za-z0-9_]*)(\.index|\.quote|\.text|\.indent|\.kind|\.prev|\.next)?\]",
+    )
+    .unwrap()
+});
@@ -1,5 +1,6 @@
 use crate::{constants::*, traverse, AstNode, ByteRange, CodeRange, Order, Range};
 use regex::Regex;
+use std::borrow::Cow;
 
 pub enum GritMetaValue {
     Underscore,
@@ -136,6 +137,32 @@ pub trait Language: Sized {
     fn make_single_line_comment(&self, text: &str) -> String {
         format!("// {text}\n")
     }
+
+    /// Fixes up a snippet before it is parsed in each of its contexts, for
+    /// fragments the grammar wouldn't otherwise accept, such as a list with a
+    /// trailing separator.
+    ///
+    /// The offsets of the snippet have to stay the same: text may only be
+    /// replaced by text of the same length, or followed by whitespace, so the
+    /// ranges of the parsed nodes and metavariables still point into the
+    /// snippet as written.
+    fn normalize_snippet<'a>(&self, source: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(source)
+    }
+
+    /// Opening and closing delimiters of doc comments, such as `/**` and `*/`.
+    ///
+    /// A snippet starting with a doc comment matches a declaration preceded by
+    /// that comment, so `/** $doc */ function $name() {}` binds both.
+    fn doc_comment_delimiters(&self) -> Option<(&'static str, &'static str)> {
+        None
+    }
+
+    /// Tidies the text of a rewrite once its metavariables are substituted,
+    /// such as by removing a semicolon that ended up doubled.
+    fn post_process_rewrite(&self, text: &str) -> String {
+        text.to_owned()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -149,3 +176,81 @@ impl Replacement {
         Self { range, replacement }
     }
 }
+
+/// How the snippets of a language are read by [`blank_trailing_separators`].
+#[derive(Clone, Copy, Debug)]
+pub struct TrailingSeparators {
+    /// The separators which may follow the last element of a list.
+    pub separators: &'static [char],
+    /// The quotes of string literals, whose contents are skipped.
+    pub quotes: &'static [char],
+    /// Whether `'` starts a character literal, such as `','`, when it
+    /// encloses a single character, and a lifetime, such as `'a`, otherwise.
+    pub char_literals: bool,
+    /// Whether a lone separator before a `)` makes a tuple, as in `(a,)`, in
+    /// which case it is kept.
+    pub tuples: bool,
+}
+
+/// Blanks out separators which directly precede a closing bracket, such as
+/// the comma in `foo($a, $b,)`, for languages where they are optional.
+///
+/// Separators are replaced by spaces rather than removed, so offsets into the
+/// snippet remain valid.
+pub fn blank_trailing_separators<'a>(source: &'a str, syntax: &TrailingSeparators) -> Cow<'a, str> {
+    let mut trailing = Vec::new();
+    // For every open bracket, whether we have seen a separator inside it.
+    let mut lists: Vec<bool> = Vec::new();
+    let mut quote = None;
+    let mut chars = source.char_indices();
+    while let Some((index, c)) = chars.next() {
+        if let Some(q) = quote {
+            if c == '\\' {
+                chars.next();
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            c if syntax.quotes.contains(&c) => quote = Some(c),
+            '\'' if syntax.char_literals => {
+                let mut ahead = chars.clone();
+                match (ahead.next(), ahead.next()) {
+                    (Some((_, '\\')), _) => quote = Some(c),
+                    (Some(_), Some((_, '\''))) => chars = ahead,
+                    // a lifetime
+                    _ => {}
+                }
+            }
+            '(' | '[' | '{' => lists.push(false),
+            ')' | ']' | '}' => {
+                lists.pop();
+            }
+            c if syntax.separators.contains(&c) => {
+                let end = index + c.len_utf8();
+                let is_trailing = match source[end..].trim_start().chars().next() {
+                    Some(')') if syntax.tuples => lists.last().copied().unwrap_or(false),
+                    Some(')' | ']' | '}') => true,
+                    _ => false,
+                };
+                if is_trailing {
+                    trailing.push(index..end);
+                }
+                if let Some(has_separator) = lists.last_mut() {
+                    *has_separator = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    if trailing.is_empty() {
+        return Cow::Borrowed(source);
+    }
+    let mut normalized = source.to_string();
+    for range in trailing {
+        let blank = " ".repeat(range.len());
+        normalized.replace_range(range, &blank);
+    }
+    Cow::Owned(normalized)
+}
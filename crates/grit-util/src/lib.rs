@@ -12,7 +12,9 @@ pub use analysis_logs::{AnalysisLog, AnalysisLogBuilder, AnalysisLogs};
 pub use ast_node::AstNode;
 pub use ast_node_traversal::{traverse, AstCursor, Order};
 pub use code_range::CodeRange;
-pub use language::{GritMetaValue, Language, Replacement};
+pub use language::{
+    blank_trailing_separators, GritMetaValue, Language, Replacement, TrailingSeparators,
+};
 pub use parser::{Ast, FileOrigin, Parser, SnippetTree};
 pub use position::Position;
 pub use ranges::{
@@ -47,6 +47,27 @@ impl Range {
         self.start_byte as usize..self.end_byte as usize
     }
 
+    /// Converts a range within `source` so that its offsets and columns count
+    /// UTF-16 code units rather than bytes, as editors such as VS Code do.
+    pub fn to_utf16_range(&self, source: &str) -> Self {
+        let to_utf16_position = |position: Position, index: u32| {
+            let index = index as usize;
+            let line_start = source.as_bytes()[..index.min(source.len())]
+                .iter()
+                .rposition(|byte| *byte == b'\n')
+                .map_or(0, |newline| newline + 1);
+            let column = byte_index_to_utf16_offset(index, source)
+                - byte_index_to_utf16_offset(line_start, source);
+            Position::new(position.line, column as u32 + 1)
+        };
+        Self {
+            start: to_utf16_position(self.start, self.start_byte),
+            end: to_utf16_position(self.end, self.end_byte),
+            start_byte: byte_index_to_utf16_offset(self.start_byte as usize, source) as u32,
+            end_byte: byte_index_to_utf16_offset(self.end_byte as usize, source) as u32,
+        }
+    }
+
     pub fn from_byteless(range: RangeWithoutByte, str: &str) -> Self {
         let mut start_byte = 0;
         let mut byte_length = 0;
@@ -206,6 +227,15 @@ impl ByteRange {
         let end = byte_index_to_char_offset(self.end, context);
         Self { start, end }
     }
+
+    /// Converts a range expressed in byte indices to a range expressed in
+    /// UTF-16 code units, in which characters outside of the Basic
+    /// Multilingual Plane, such as most emoji, count twice.
+    pub fn to_utf16_range(self, context: &str) -> Self {
+        let start = byte_index_to_utf16_offset(self.start, context);
+        let end = byte_index_to_utf16_offset(self.end, context);
+        Self { start, end }
+    }
 }
 
 impl From<Range> for ByteRange {
@@ -248,6 +278,13 @@ fn byte_index_to_char_offset(index: usize, text: &str) -> usize {
     text.char_indices().take_while(|(i, _)| *i < index).count()
 }
 
+fn byte_index_to_utf16_offset(index: usize, text: &str) -> usize {
+    text.char_indices()
+        .take_while(|(i, _)| *i < index)
+        .map(|(_, c)| c.len_utf16())
+        .sum()
+}
+
 #[derive(Debug, Clone)]
 pub struct InputRanges {
     pub ranges: Vec<Range>,
@@ -304,6 +341,15 @@ impl VariableMatch {
             ranges,
         }
     }
+
+    /// Returns the byte range of each binding, for making precise edits to
+    /// the matched source.
+    ///
+    /// Byte offsets are not deserialized, so this is only meaningful for
+    /// matches produced in the same process.
+    pub fn byte_ranges(&self) -> Vec<ByteRange> {
+        self.ranges.iter().copied().map(ByteRange::from).collect()
+    }
 }
 
 #[cfg(test)]
@@ -346,4 +392,23 @@ mod tests {
         let new_range = range.to_char_range("const [µb, fµa]");
         assert_eq!(new_range, ByteRange::new(13, 15));
     }
+
+    #[test]
+    fn byte_range_to_utf16_range() {
+        let source = "let a = \"😀\"; b";
+        let range = ByteRange::new(16, 17);
+        assert_eq!(&source[range.start..range.end], "b");
+        assert_eq!(range.to_utf16_range(source), ByteRange::new(14, 15));
+    }
+
+    #[test]
+    fn range_to_utf16_range() {
+        let source = "x\n\"😀\" + y";
+        let range = Range::from_byte_range(source, ByteRange::new(11, 12));
+        assert_eq!(&source[range.range_index()], "y");
+        assert_eq!(
+            range.to_utf16_range(source),
+            Range::new(Position::new(2, 8), Position::new(2, 9), 9, 10)
+        );
+    }
 }
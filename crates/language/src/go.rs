@@ -23,6 +23,7 @@ pub struct Go {
     node_types: &'static [Vec<Field>],
     metavariable_sort: SortId,
     comment_sort: SortId,
+    labeled_statement_sort: SortId,
     language: &'static TSLanguage,
 }
 
@@ -32,10 +33,12 @@ impl Go {
         let node_types = NODE_TYPES.get_or_init(|| fields_for_nodes(language, NODE_TYPES_STRING));
         let metavariable_sort = language.id_for_node_kind("grit_metavariable", true);
         let comment_sort = language.id_for_node_kind("comment", true);
+        let labeled_statement_sort = language.id_for_node_kind("labeled_statement", true);
         Self {
             node_types,
             metavariable_sort,
             comment_sort,
+            labeled_statement_sort,
             language,
         }
     }
@@ -88,6 +91,10 @@ impl<'a> MarzanoLanguage<'a> for Go {
     fn metavariable_sort(&self) -> SortId {
         self.metavariable_sort
     }
+
+    fn holds_unfielded_child(&self, id: SortId) -> bool {
+        id == self.labeled_statement_sort
+    }
 }
 
 #[cfg(test)]
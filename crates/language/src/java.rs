@@ -1,4 +1,8 @@
-use crate::language::{fields_for_nodes, Field, MarzanoLanguage, NodeTypes, SortId, TSLanguage};
+use crate::language::{
+    check_disregarded_field_map, fields_for_nodes, Field, FieldExpectation,
+    FieldExpectationCondition, FieldId, MarzanoLanguage, MemberModifiers, NodeTypes, SortId,
+    TSLanguage,
+};
 use grit_util::Language;
 use marzano_util::node_with_source::NodeWithSource;
 use std::sync::OnceLock;
@@ -6,6 +10,16 @@ use std::sync::OnceLock;
 static NODE_TYPES_STRING: &str = include_str!("../../../resources/node-types/java-node-types.json");
 static NODE_TYPES: OnceLock<Vec<Vec<Field>>> = OnceLock::new();
 static LANGUAGE: OnceLock<TSLanguage> = OnceLock::new();
+static DISREGARDED_SNIPPET_FIELDS: OnceLock<Vec<FieldExpectation>> = OnceLock::new();
+static INHERITANCE_CLAUSES: OnceLock<Vec<(SortId, FieldId)>> = OnceLock::new();
+
+static INHERITANCE_CLAUSE_FIELD_NAMES: &[(&str, &str)] = &[
+    ("class_declaration", "superclass"),
+    ("class_declaration", "interfaces"),
+    ("interface_declaration", "extends"),
+    ("enum_declaration", "interfaces"),
+    ("record_declaration", "interfaces"),
+];
 
 #[cfg(not(feature = "builtin-parser"))]
 fn language() -> TSLanguage {
@@ -23,7 +37,10 @@ pub struct Java {
     node_types: &'static [Vec<Field>],
     metavariable_sort: SortId,
     comment_sorts: [SortId; 2],
+    member_modifiers: MemberModifiers,
+    inheritance_clauses: &'static [(SortId, FieldId)],
     language: &'static TSLanguage,
+    disregarded_snippet_fields: &'static Vec<FieldExpectation>,
 }
 
 impl NodeTypes for Java {
@@ -41,11 +58,45 @@ impl Java {
             language.id_for_node_kind("line_comment", true),
             language.id_for_node_kind("block_comment", true),
         ];
+        let member_modifiers = MemberModifiers {
+            placeholder: "public",
+            sort: language.id_for_node_kind("modifiers", true),
+        };
+        let inheritance_clauses = INHERITANCE_CLAUSES.get_or_init(|| {
+            INHERITANCE_CLAUSE_FIELD_NAMES
+                .iter()
+                .map(|(kind, field)| {
+                    (
+                        language.id_for_node_kind(kind, true),
+                        language.field_id_for_name(field).unwrap(),
+                    )
+                })
+                .collect()
+        });
+        // so a `try` snippet matches statements with clauses it leaves out
+        let try_statement = language.id_for_node_kind("try_statement", true);
+        let disregarded_snippet_fields = DISREGARDED_SNIPPET_FIELDS.get_or_init(|| {
+            vec![
+                (
+                    try_statement,
+                    language.field_id_for_name("catch").unwrap(),
+                    FieldExpectationCondition::OnlyIf(vec![""]),
+                ),
+                (
+                    try_statement,
+                    language.field_id_for_name("finally").unwrap(),
+                    FieldExpectationCondition::OnlyIf(vec![""]),
+                ),
+            ]
+        });
         Self {
             node_types,
             metavariable_sort,
             comment_sorts,
+            member_modifiers,
+            inheritance_clauses,
             language,
+            disregarded_snippet_fields,
         }
     }
     pub(crate) fn is_initialized() -> bool {
@@ -83,6 +134,20 @@ impl<'a> MarzanoLanguage<'a> for Java {
         self.language
     }
 
+    fn is_disregarded_snippet_field(
+        &self,
+        sort_id: SortId,
+        field_id: crate::language::FieldId,
+        field_node: &Option<NodeWithSource<'_>>,
+    ) -> bool {
+        check_disregarded_field_map(
+            self.disregarded_snippet_fields,
+            sort_id,
+            field_id,
+            field_node,
+        )
+    }
+
     fn is_comment_sort(&self, id: SortId) -> bool {
         self.comment_sorts.contains(&id)
     }
@@ -90,6 +155,14 @@ impl<'a> MarzanoLanguage<'a> for Java {
     fn metavariable_sort(&self) -> SortId {
         self.metavariable_sort
     }
+
+    fn member_modifiers(&self) -> Option<&MemberModifiers> {
+        Some(&self.member_modifiers)
+    }
+
+    fn is_inheritance_clause(&self, sort: SortId, field: FieldId) -> bool {
+        self.inheritance_clauses.contains(&(sort, field))
+    }
 }
 
 #[cfg(test)]
@@ -1,16 +1,21 @@
 use crate::{
     js_like::{
-        js_disregarded_field_values, js_like_get_statement_sorts, js_like_is_comment,
-        js_like_is_metavariable, jslike_check_replacements, MarzanoJsLikeParser,
+        js_disregarded_field_values, js_like_associative_operator,
+        js_like_get_declaration_keywords, js_like_get_export_declaration, js_like_get_flag_fields,
+        js_like_get_member_chain_fields, js_like_get_statement_sorts, js_like_is_comment,
+        js_like_is_metavariable, js_like_post_process_rewrite, js_like_template_literal,
+        jslike_check_replacements, MarzanoJsLikeParser, JS_LIKE_LITERAL_PARTS,
+        JS_LIKE_TRAILING_SEPARATORS,
     },
     language::{
-        check_disregarded_field_map, fields_for_nodes, kind_and_field_id_for_field_map, Field,
-        FieldExpectation, MarzanoLanguage, NodeTypes, SortId, TSLanguage, Tree,
+        check_disregarded_field_map, fields_for_nodes, kind_and_field_id_for_field_map,
+        DeclarationKeywords, Field, FieldExpectation, FieldId, LiteralPart, MarzanoLanguage,
+        NodeTypes, SortId, TSLanguage, Tree,
     },
 };
-use grit_util::{AstNode, ByteRange, Language, Parser, Replacement};
+use grit_util::{blank_trailing_separators, AstNode, ByteRange, Language, Parser, Replacement};
 use marzano_util::node_with_source::NodeWithSource;
-use std::sync::OnceLock;
+use std::{borrow::Cow, sync::OnceLock};
 
 static NODE_TYPES_STRING: &str =
     include_str!("../../../resources/node-types/javascript-node-types.json");
@@ -18,6 +23,8 @@ static NODE_TYPES: OnceLock<Vec<Vec<Field>>> = OnceLock::new();
 static LANGUAGE: OnceLock<TSLanguage> = OnceLock::new();
 static STATEMENT_SORTS: OnceLock<Vec<SortId>> = OnceLock::new();
 static DISREGARDED_SNIPPET_FIELDS: OnceLock<Vec<FieldExpectation>> = OnceLock::new();
+static MEMBER_CHAIN_FIELDS: OnceLock<Vec<(SortId, FieldId)>> = OnceLock::new();
+static DECLARATION_KEYWORDS: OnceLock<DeclarationKeywords> = OnceLock::new();
 
 #[cfg(not(feature = "builtin-parser"))]
 fn language() -> TSLanguage {
@@ -35,10 +42,18 @@ pub struct JavaScript {
     node_types: &'static [Vec<Field>],
     metavariable_sort: SortId,
     comment_sort: SortId,
+    grouping_sort: SortId,
+    object_sort: SortId,
+    binary_expression_sort: SortId,
     jsx_sort: SortId,
+    switch_body_sort: SortId,
     statement_sorts: &'static [SortId],
     language: &'static TSLanguage,
     disregarded_snippet_fields: &'static Vec<FieldExpectation>,
+    member_chain_fields: &'static [(SortId, FieldId)],
+    declaration_keywords: &'static DeclarationKeywords,
+    export_declaration: (SortId, FieldId),
+    flag_fields: [(&'static str, FieldId); 1],
 }
 
 impl JavaScript {
@@ -47,10 +62,23 @@ impl JavaScript {
         let node_types = NODE_TYPES.get_or_init(|| fields_for_nodes(language, NODE_TYPES_STRING));
         let metavariable_sort = language.id_for_node_kind("grit_metavariable", true);
         let comment_sort = language.id_for_node_kind("comment", true);
+        let grouping_sort = language.id_for_node_kind("parenthesized_expression", true);
+        let object_sort = language.id_for_node_kind("object", true);
+        let binary_expression_sort = language.id_for_node_kind("binary_expression", true);
         let jsx_sort = language.id_for_node_kind("jsx_expression", true);
+        let switch_body_sort = language.id_for_node_kind("switch_body", true);
 
         let statement_sorts = STATEMENT_SORTS.get_or_init(|| js_like_get_statement_sorts(language));
 
+        let member_chain_fields =
+            MEMBER_CHAIN_FIELDS.get_or_init(|| js_like_get_member_chain_fields(language));
+
+        let declaration_keywords =
+            DECLARATION_KEYWORDS.get_or_init(|| js_like_get_declaration_keywords(language));
+
+        let export_declaration = js_like_get_export_declaration(language);
+        let flag_fields = js_like_get_flag_fields(language);
+
         let disregarded_snippet_fields = DISREGARDED_SNIPPET_FIELDS.get_or_init(|| {
             kind_and_field_id_for_field_map(language, js_disregarded_field_values())
         });
@@ -59,10 +87,18 @@ impl JavaScript {
             node_types,
             metavariable_sort,
             comment_sort,
+            grouping_sort,
+            object_sort,
+            binary_expression_sort,
             jsx_sort,
+            switch_body_sort,
             statement_sorts,
             language,
             disregarded_snippet_fields,
+            member_chain_fields,
+            declaration_keywords,
+            export_declaration,
+            flag_fields,
         }
     }
     pub(crate) fn is_initialized() -> bool {
@@ -113,6 +149,18 @@ impl Language for JavaScript {
         js_like_is_metavariable(node, self, &["template_content"])
     }
 
+    fn normalize_snippet<'b>(&self, source: &'b str) -> Cow<'b, str> {
+        blank_trailing_separators(source, &JS_LIKE_TRAILING_SEPARATORS)
+    }
+
+    fn doc_comment_delimiters(&self) -> Option<(&'static str, &'static str)> {
+        Some(("/**", "*/"))
+    }
+
+    fn post_process_rewrite(&self, text: &str) -> String {
+        js_like_post_process_rewrite(text)
+    }
+
     fn is_statement(&self, node: &NodeWithSource) -> bool {
         self.statement_sorts.contains(&node.node.kind_id())
     }
@@ -163,6 +211,49 @@ impl<'a> MarzanoLanguage<'a> for JavaScript {
         id == self.comment_sort
     }
 
+    fn is_grouping_sort(&self, id: SortId) -> bool {
+        id == self.grouping_sort
+    }
+
+    fn is_keyed_collection_sort(&self, id: SortId) -> bool {
+        id == self.object_sort
+    }
+
+    fn is_switch_body_sort(&self, id: SortId) -> bool {
+        id == self.switch_body_sort
+    }
+
+    fn associative_operator<'b>(&self, node: &NodeWithSource<'b>) -> Option<&'b str> {
+        js_like_associative_operator(node, self.binary_expression_sort)
+    }
+
+    fn member_chain_field(&self, sort: SortId) -> Option<FieldId> {
+        self.member_chain_fields
+            .iter()
+            .find(|(chain_sort, _)| *chain_sort == sort)
+            .map(|(_, field)| *field)
+    }
+
+    fn declaration_keywords(&self) -> Option<&DeclarationKeywords> {
+        Some(self.declaration_keywords)
+    }
+
+    fn export_declaration(&self) -> Option<(SortId, FieldId)> {
+        Some(self.export_declaration)
+    }
+
+    fn flag_fields(&self) -> &[(&'static str, FieldId)] {
+        &self.flag_fields
+    }
+
+    fn literal_parts(&self) -> &[LiteralPart] {
+        &JS_LIKE_LITERAL_PARTS
+    }
+
+    fn template_literal(&self, parts: &[NodeWithSource<'_>]) -> Option<String> {
+        js_like_template_literal(parts, self.binary_expression_sort)
+    }
+
     fn is_comment_node(&self, node: &NodeWithSource) -> bool {
         self.is_comment_sort(node.node.kind_id())
             || js_like_is_comment(node, self.comment_sort, self.jsx_sort)
@@ -188,4 +279,14 @@ mod tests {
         let nodes = nodes_from_indices(&snippets);
         assert!(!nodes.is_empty());
     }
+
+    #[test]
+    fn snippets_are_normalized_without_trailing_commas() {
+        let lang = JavaScript::new(None);
+        assert_eq!(lang.normalize_snippet("foo($a, $b,)"), "foo($a, $b )");
+        assert_eq!(lang.normalize_snippet("[$a, `,]`,]"), "[$a, `,]` ]");
+        // there are no tuples to tell apart from a lone trailing comma
+        assert_eq!(lang.normalize_snippet("foo($a,)"), "foo($a )");
+        assert_eq!(lang.normalize_snippet("foo('$a,)',)"), "foo('$a,)' )");
+    }
 }
@@ -1,12 +1,14 @@
 use crate::{
     language::{
-        FieldExpectationCondition, FieldExpectationCondition::Always,
-        FieldExpectationCondition::OnlyIf, MarzanoLanguage, MarzanoParser, SortId, TSLanguage,
-        Tree,
+        DeclarationKeywords, FieldExpectationCondition, FieldExpectationCondition::Always,
+        FieldExpectationCondition::OnlyIf, FieldId, LiteralPart, MarzanoLanguage, MarzanoParser,
+        SortId, TSLanguage, Tree,
     },
     vue::get_vue_ranges,
 };
-use grit_util::{AnalysisLogs, AstNode, FileOrigin, Parser, Replacement, SnippetTree};
+use grit_util::{
+    AnalysisLogs, AstNode, FileOrigin, Parser, Replacement, SnippetTree, TrailingSeparators,
+};
 use marzano_util::node_with_source::NodeWithSource;
 use std::path::Path;
 
@@ -47,6 +49,100 @@ pub(crate) fn js_like_get_statement_sorts(lang: &TSLanguage) -> Vec<SortId> {
         .collect()
 }
 
+static REQUIRED_SNIPPET_FIELD_NAMES: &[(&str, &str)] = &[
+    ("required_parameter", "value"),
+    ("optional_parameter", "value"),
+    ("variable_declarator", "value"),
+];
+
+pub(crate) fn js_like_get_required_snippet_fields(lang: &TSLanguage) -> Vec<(SortId, FieldId)> {
+    REQUIRED_SNIPPET_FIELD_NAMES
+        .iter()
+        .filter_map(|(kind, field)| {
+            lang.field_id_for_name(field)
+                .map(|field_id| (lang.id_for_node_kind(kind, true), field_id))
+        })
+        .collect()
+}
+
+static MEMBER_CHAIN_FIELD_NAMES: &[(&str, &str)] = &[
+    ("member_expression", "object"),
+    ("call_expression", "function"),
+];
+
+pub(crate) fn js_like_get_member_chain_fields(lang: &TSLanguage) -> Vec<(SortId, FieldId)> {
+    MEMBER_CHAIN_FIELD_NAMES
+        .iter()
+        .filter_map(|(kind, field)| {
+            lang.field_id_for_name(field)
+                .map(|field_id| (lang.id_for_node_kind(kind, true), field_id))
+        })
+        .collect()
+}
+
+static INHERITANCE_CLAUSE_FIELD_NAMES: &[(&str, &str)] = &[
+    ("class_declaration", "heritage"),
+    ("class", "heritage"),
+    ("abstract_class_declaration", "heritage"),
+    ("class_heritage", "extends"),
+    ("class_heritage", "implements"),
+];
+
+pub(crate) fn js_like_get_inheritance_clauses(lang: &TSLanguage) -> Vec<(SortId, FieldId)> {
+    INHERITANCE_CLAUSE_FIELD_NAMES
+        .iter()
+        .filter_map(|(kind, field)| {
+            lang.field_id_for_name(field)
+                .map(|field_id| (lang.id_for_node_kind(kind, true), field_id))
+        })
+        .collect()
+}
+
+static DECLARATION_KEYWORDS: &[&str] = &["var", "let", "const"];
+
+pub(crate) fn js_like_get_declaration_keywords(lang: &TSLanguage) -> DeclarationKeywords {
+    DeclarationKeywords {
+        keywords: DECLARATION_KEYWORDS
+            .iter()
+            .map(|keyword| (*keyword, lang.id_for_node_kind(keyword, true)))
+            .collect(),
+        declarator_sort: lang.id_for_node_kind("variable_declarator", true),
+    }
+}
+
+pub(crate) fn js_like_get_export_declaration(lang: &TSLanguage) -> (SortId, FieldId) {
+    (
+        lang.id_for_node_kind("export_statement", true),
+        lang.field_id_for_name("declaration").unwrap(),
+    )
+}
+
+/// The enum bodies of TypeScript, along with the sort of the members
+/// assigning a value and the field holding their name.
+pub(crate) fn js_like_get_enum_body(lang: &TSLanguage) -> (SortId, (SortId, FieldId)) {
+    (
+        lang.id_for_node_kind("enum_body", true),
+        (
+            lang.id_for_node_kind("enum_assignment", true),
+            lang.field_id_for_name("name").unwrap(),
+        ),
+    )
+}
+
+pub(crate) const JS_LIKE_TRAILING_SEPARATORS: TrailingSeparators = TrailingSeparators {
+    separators: &[','],
+    quotes: &['"', '\'', '`'],
+    char_literals: false,
+    tuples: false,
+};
+
+pub(crate) const JS_LIKE_LITERAL_PARTS: [LiteralPart; 2] =
+    [LiteralPart::RegexFlags, LiteralPart::NumberWithSuffix("n")];
+
+pub(crate) fn js_like_get_flag_fields(lang: &TSLanguage) -> [(&'static str, FieldId); 1] {
+    [("async", lang.field_id_for_name("async").unwrap())]
+}
+
 pub(crate) fn js_disregarded_field_values(
 ) -> Vec<(&'static str, &'static str, FieldExpectationCondition)> {
     vec![
@@ -65,6 +161,8 @@ pub(crate) fn js_disregarded_field_values(
         ("method_definition", "async", OnlyIf(vec![""])),
         ("function_declaration", "async", OnlyIf(vec![""])),
         ("import_statement", "import", OnlyIf(vec![""])),
+        // so `export $decl` also matches `export default` declarations:
+        ("export_statement", "default", OnlyIf(vec![""])),
     ]
 }
 
@@ -81,16 +179,62 @@ pub(crate) fn js_like_disregarded_field_values(
         // disregarded if empty:
         ("call_expression", "type_arguments", OnlyIf(vec![""])),
         ("new_expression", "type_arguments", OnlyIf(vec![""])),
+        // so a function snippet without a return type matches annotated ones:
         ("function", "return_type", OnlyIf(vec![""])),
         ("arrow_function", "return_type", OnlyIf(vec![""])),
+        ("function_declaration", "return_type", OnlyIf(vec![""])),
+        ("generator_function", "return_type", OnlyIf(vec![""])),
+        (
+            "generator_function_declaration",
+            "return_type",
+            OnlyIf(vec![""]),
+        ),
+        ("method_definition", "return_type", OnlyIf(vec![""])),
+        ("function_signature", "return_type", OnlyIf(vec![""])),
+        ("method_signature", "return_type", OnlyIf(vec![""])),
+        ("abstract_method_signature", "return_type", OnlyIf(vec![""])),
+        // so a function snippet without type parameters matches generic ones:
+        ("function", "type_parameters", OnlyIf(vec![""])),
+        ("arrow_function", "type_parameters", OnlyIf(vec![""])),
+        ("function_declaration", "type_parameters", OnlyIf(vec![""])),
+        ("generator_function", "type_parameters", OnlyIf(vec![""])),
+        (
+            "generator_function_declaration",
+            "type_parameters",
+            OnlyIf(vec![""]),
+        ),
+        ("method_definition", "type_parameters", OnlyIf(vec![""])),
         ("import_statement", "type", OnlyIf(vec![""])),
         ("public_field_definition", "static", OnlyIf(vec![""])),
         ("member_expression", "chain", OnlyIf(vec!["", "."])),
+        // so `const $x: $T` matches any value, and `const $x = $v` any type:
+        ("variable_declarator", "type", OnlyIf(vec![""])),
+        ("variable_declarator", "value", OnlyIf(vec![""])),
+        // so a `try` snippet matches statements with clauses it leaves out:
+        ("try_statement", "handler", OnlyIf(vec![""])),
+        ("try_statement", "finalizer", OnlyIf(vec![""])),
     ];
     res.extend(js_disregarded_field_values());
     res
 }
 
+/// Removes the semicolons doubled at the end of a line, as in `foo();;` when
+/// a statement is substituted before a semicolon. Empty `for` clauses, like
+/// `for (;;)`, never end a line.
+pub(crate) fn js_like_post_process_rewrite(text: &str) -> String {
+    let mut tidied = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end();
+        let mut end = content.len();
+        while content[..end].ends_with(";;") {
+            end -= 1;
+        }
+        tidied.push_str(&content[..end]);
+        tidied.push_str(&line[content.len()..]);
+    }
+    tidied
+}
+
 pub(crate) fn js_like_is_metavariable<'a>(
     node: &NodeWithSource,
     lang: &impl MarzanoLanguage<'a>,
@@ -167,6 +311,106 @@ pub(crate) fn js_like_is_comment(
                 .is_some_and(|b| b))
 }
 
+/// Operators whose chains read the same however they are grouped.
+static ASSOCIATIVE_OPERATORS: &[&str] = &["&&", "||", "??", "+", "*", "&", "|", "^"];
+
+pub(crate) fn js_like_associative_operator<'a>(
+    node: &NodeWithSource<'a>,
+    binary_expression_sort: SortId,
+) -> Option<&'a str> {
+    if node.node.kind_id() != binary_expression_sort {
+        return None;
+    }
+    let operator = node.child_by_field_name("operator")?;
+    let operator =
+        &node.source[operator.node.start_byte() as usize..operator.node.end_byte() as usize];
+    ASSOCIATIVE_OPERATORS
+        .contains(&operator)
+        .then_some(operator)
+}
+
+/// Renders the parts of a string concatenation as a template literal, see
+/// `MarzanoLanguage::template_literal()`.
+///
+/// The parts before the first string are added together rather than
+/// concatenated, so they are interpolated as one expression: `a + b + "px"`
+/// becomes `${a + b}px`.
+pub(crate) fn js_like_template_literal(
+    parts: &[NodeWithSource],
+    binary_expression_sort: SortId,
+) -> Option<String> {
+    let mut operands = Vec::new();
+    for part in parts {
+        flatten_concatenation(part.clone(), binary_expression_sort, &mut operands);
+    }
+    let is_string = |operand: &NodeWithSource| {
+        matches!(operand.node.kind().as_ref(), "string" | "template_string")
+    };
+    let added = match operands.iter().position(is_string) {
+        Some(first_string) if first_string > 1 => first_string,
+        _ => 0,
+    };
+    let mut literal = String::from("`");
+    if added > 0 {
+        let start = operands[0].node.start_byte() as usize;
+        let end = operands[added - 1].node.end_byte() as usize;
+        literal.push_str(&format!("${{{}}}", &operands[0].source[start..end]));
+    }
+    for operand in &operands[added..] {
+        let text = operand.text().ok()?;
+        match operand.node.kind().as_ref() {
+            "string" => push_template_text(&mut literal, &text),
+            "template_string" => literal.push_str(&text[1..text.len() - 1]),
+            _ => literal.push_str(&format!("${{{text}}}")),
+        }
+    }
+    literal.push('`');
+    Some(literal)
+}
+
+fn flatten_concatenation<'a>(
+    node: NodeWithSource<'a>,
+    binary_expression_sort: SortId,
+    operands: &mut Vec<NodeWithSource<'a>>,
+) {
+    if js_like_associative_operator(&node, binary_expression_sort) != Some("+") {
+        operands.push(node);
+        return;
+    }
+    for child in node.named_children() {
+        if child.node.kind() != "comment" {
+            flatten_concatenation(child, binary_expression_sort, operands);
+        }
+    }
+}
+
+/// Adds the content of a quoted string to a template literal, dropping the
+/// escapes of the quotes and escaping what a template would interpolate.
+fn push_template_text(literal: &mut String, string: &str) {
+    let mut chars = string.chars();
+    let Some(quote) = chars.next() else {
+        return;
+    };
+    let mut content = chars.as_str();
+    content = content.strip_suffix(quote).unwrap_or(content);
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped == quote => literal.push(escaped),
+                Some(escaped) => {
+                    literal.push('\\');
+                    literal.push(escaped);
+                }
+                None => literal.push('\\'),
+            },
+            '`' => literal.push_str("\\`"),
+            '$' if chars.peek() == Some(&'{') => literal.push_str("\\$"),
+            c => literal.push(c),
+        }
+    }
+}
+
 pub(crate) fn jslike_check_replacements(
     n: NodeWithSource<'_>,
     replacement_ranges: &mut Vec<Replacement>,
@@ -261,4 +505,19 @@ defineProps<{
             .unwrap();
         print_node(&tree.root_node().node);
     }
+
+    #[test]
+    fn post_process_rewrite_removes_doubled_semicolons() {
+        assert_eq!(
+            js_like_post_process_rewrite("foo();;\n  bar(); ;;\nfor (;;) {}"),
+            "foo();\n  bar(); ;\nfor (;;) {}"
+        );
+    }
+
+    #[test]
+    fn template_text_drops_quote_escapes_and_escapes_interpolation() {
+        let mut literal = String::new();
+        push_template_text(&mut literal, r#"'It\'s \n `${price}` "$5"'"#);
+        assert_eq!(literal, r#"It's \n \`\${price}\` "$5""#);
+    }
 }
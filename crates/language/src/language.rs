@@ -7,12 +7,15 @@ use grit_util::{
 use itertools::Itertools;
 use marzano_util::{cursor_wrapper::CursorWrapper, node_with_source::NodeWithSource};
 use serde_json::Value;
-use std::{borrow::Cow, cmp::max, collections::HashMap, path::Path};
+use std::{borrow::Cow, cmp::max, collections::HashMap, fmt, path::Path};
 pub(crate) use tree_sitter::{Language as TSLanguage, Parser as TSParser, Tree as TSTree};
 
 use crate::sourcemap::EmbeddedSourceMap;
 
+/// Identifies a kind of node. Sort ids are assigned by the grammar, so they
+/// are only valid within one [`GrammarVersion`].
 pub type SortId = u16;
+/// Identifies a field of a node, only valid within one [`GrammarVersion`].
 pub type FieldId = u16;
 
 #[derive(Debug, Clone)]
@@ -35,6 +38,38 @@ impl LeafNormalizer {
     }
 }
 
+/// The keywords which start a variable declaration, see
+/// [`MarzanoLanguage::declaration_keywords()`].
+#[derive(Debug, Clone)]
+pub struct DeclarationKeywords {
+    /// Each keyword, along with the sort of its node.
+    pub keywords: Vec<(&'static str, SortId)>,
+    /// The sort of the declarators following the keyword.
+    pub declarator_sort: SortId,
+}
+
+/// A part of a literal which only accepts certain characters, so a snippet
+/// can't write a metavariable there as is, see
+/// [`MarzanoLanguage::literal_parts()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiteralPart {
+    /// The flags of a regex literal, as in `/$pattern/$flags`.
+    RegexFlags,
+    /// The digits of a number followed by this suffix, written as a
+    /// bracketed metavariable followed by the suffix, such as the BigInt
+    /// `$[n]n`.
+    NumberWithSuffix(&'static str),
+}
+
+/// The modifiers of class members, see [`MarzanoLanguage::member_modifiers()`].
+#[derive(Debug, Clone, Copy)]
+pub struct MemberModifiers {
+    /// A modifier snippets are parsed with in place of their metavariable.
+    pub placeholder: &'static str,
+    /// The sort of the node holding the modifiers, as a child of the member.
+    pub sort: SortId,
+}
+
 #[derive(Debug, Clone)]
 pub struct LeafEquivalenceClass {
     representative: String,
@@ -181,6 +216,49 @@ pub trait NodeTypes {
     fn node_types(&self) -> &[Vec<Field>];
 }
 
+/// Identifies the grammar of a language by the names of its sorts and fields.
+///
+/// A new version of a tree-sitter grammar may number its node kinds and
+/// fields differently, so anything persisting [`SortId`]s or [`FieldId`]s,
+/// such as a cache of compiled patterns, should be keyed on the version of
+/// the grammar they came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GrammarVersion(u64);
+
+impl GrammarVersion {
+    pub fn new(language: &TSLanguage, node_types: &[Vec<Field>]) -> Self {
+        // FNV-1a, which unlike the standard library's hasher gives the same
+        // hash across builds.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut write = |bytes: &[u8]| {
+            for byte in bytes {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        for (sort, fields) in node_types.iter().enumerate() {
+            let kind = language
+                .node_kind_for_id(sort as SortId)
+                .unwrap_or_default();
+            write(kind.as_bytes());
+            write(&[0]);
+            for field in fields {
+                write(field.name().as_bytes());
+                write(&field.id().to_le_bytes());
+                write(&[u8::from(field.required()), u8::from(field.multiple())]);
+            }
+            write(&[0xff]);
+        }
+        Self(hash)
+    }
+}
+
+impl fmt::Display for GrammarVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Tree {
     tree: TSTree,
@@ -288,12 +366,19 @@ pub trait MarzanoLanguage<'a>: Language<Node<'a> = NodeWithSource<'a>> + NodeTyp
     /// tree sitter language to parse the source
     fn get_ts_language(&self) -> &TSLanguage;
 
+    /// The version of the grammar, which the sort and field ids of the
+    /// language are only valid within.
+    fn grammar_version(&self) -> GrammarVersion {
+        GrammarVersion::new(self.get_ts_language(), self.node_types())
+    }
+
     fn get_parser(&self) -> Box<dyn Parser<Tree = Tree>> {
         Box::new(MarzanoParser::new(self))
     }
 
     fn parse_snippet_contexts(&self, source: &str) -> Vec<SnippetTree<Tree>> {
-        let source = self.substitute_metavariable_prefix(source);
+        let source = self.normalize_snippet(source);
+        let source = self.substitute_metavariable_prefix(&source);
         self.snippet_context_strings()
             .iter()
             .map(|(pre, post)| self.get_parser().parse_snippet(pre, &source, post))
@@ -342,6 +427,16 @@ pub trait MarzanoLanguage<'a>: Language<Node<'a> = NodeWithSource<'a>> + NodeTyp
         false
     }
 
+    /// Whether an optional field must also be present in the target whenever
+    /// the snippet provides it.
+    ///
+    /// Absent fields normally match a metavariable as an empty binding, which
+    /// makes `function f($x = $default) {}` match `function f(x) {}`. Marking
+    /// the default value of a parameter as required keeps the two apart.
+    fn is_required_snippet_field(&self, _sort_id: SortId, _field_id: FieldId) -> bool {
+        false
+    }
+
     fn is_comment_sort(&self, sort: SortId) -> bool;
 
     // Same as `Language::is_comment()`.
@@ -353,6 +448,248 @@ pub trait MarzanoLanguage<'a>: Language<Node<'a> = NodeWithSource<'a>> + NodeTyp
         self.is_comment_sort(node.node.kind_id())
     }
 
+    /// Whether nodes of the given sort only group their contents, such as a
+    /// parenthesized expression, without otherwise changing their meaning.
+    ///
+    /// Snippets see through such nodes when matching, unless the snippet itself
+    /// contains the grouping, so `$a + $b` matches `(a) + b` while `($a) + $b`
+    /// still requires the parentheses.
+    fn is_grouping_sort(&self, _sort: SortId) -> bool {
+        false
+    }
+
+    /// Sorts of wrapper nodes which carry no meaning users think about when
+    /// writing snippets, such as the parenthesized expressions of Python.
+    ///
+    /// Like grouping nodes, snippets see through them when matching, both at
+    /// the top level of the snippet and for its children, unless the snippet
+    /// contains the wrapper itself.
+    fn transparent_sorts(&self) -> &[SortId] {
+        &[]
+    }
+
+    /// Whether nodes of the given sort are collections of key/value entries
+    /// whose order is not significant, such as object literals.
+    ///
+    /// Snippets match such collections by subset: `{ name: $n }` matches any
+    /// collection with a `name` entry, in any position and alongside other
+    /// entries.
+    fn is_keyed_collection_sort(&self, _sort: SortId) -> bool {
+        false
+    }
+
+    /// Whether nodes of the given sort are unions of types, such as `A | B` in
+    /// TypeScript, whose members aren't held in a field.
+    ///
+    /// Snippets match such unions member by member, with nested unions
+    /// flattened, so `$a | $b` binds each member of a two-member union and
+    /// `$first | $...` matches a union with any number of members.
+    fn is_union_type_sort(&self, _sort: SortId) -> bool {
+        false
+    }
+
+    /// Whether nodes of the given sort are lists of type parameters, such as
+    /// `<T, U>` in `function f<T, U>() {}`, whose parameters aren't held in a
+    /// field.
+    ///
+    /// Snippets match such lists parameter by parameter: `<$T>` binds the only
+    /// parameter of a list and `<$first, $...>` the first of any number. Lists
+    /// made of metavariables alone also match nodes without type parameters,
+    /// leaving the metavariables unbound.
+    fn is_type_parameters_sort(&self, _sort: SortId) -> bool {
+        false
+    }
+
+    /// Whether nodes of the given sort hold the cases of a switch statement,
+    /// such as the braces of `switch (x) { ... }`, which aren't held in a
+    /// field.
+    ///
+    /// Snippets match such nodes case by case: `switch ($x) { case $c: $body }`
+    /// matches a switch with a case binding `$c` and `$body` among any number
+    /// of other cases, and a `default` case in a snippet has to match the
+    /// default case of the switch.
+    fn is_switch_body_sort(&self, _sort: SortId) -> bool {
+        false
+    }
+
+    /// The sort of the members assigning a value in an enum body of the given
+    /// sort, along with the field holding their name, if the members of such
+    /// a body aren't held in a single field, as in TypeScript.
+    ///
+    /// As with the cases of a switch, each member of the enum in a snippet is
+    /// looked for among the members of the enum, so `enum $e { A = $v }`
+    /// binds `$v` to the value of `A`, and a member without a value, like
+    /// `B`, matches `B` whether it's assigned a value or not.
+    fn enum_member_assignment(&self, _sort: SortId) -> Option<(SortId, FieldId)> {
+        None
+    }
+
+    /// Whether nodes of the given sort hold a named child outside of their
+    /// fields, such as the statement of a Go labeled statement.
+    ///
+    /// Snippets match that child too, so `$label: $stmt` binds `$stmt` to the
+    /// statement the label is attached to.
+    fn holds_unfielded_child(&self, _sort: SortId) -> bool {
+        false
+    }
+
+    /// The sort of the slices a subscript of the given sort can hold in
+    /// place of an index, such as `a:b` in Python's `items[a:b]`, if the
+    /// grammar tells slices and indices apart.
+    ///
+    /// A lone metavariable as the index of such a subscript in a snippet, as
+    /// in `$items[$i]`, binds a single index and doesn't match slices, which
+    /// are only matched by snippets like `$items[$start:$stop]`.
+    fn subscript_slice_sort(&self, _sort: SortId) -> Option<SortId> {
+        None
+    }
+
+    /// Returns the named child of `node` which isn't held in any of its
+    /// fields, ignoring comments, see `holds_unfielded_child`.
+    fn unfielded_child<'b>(&self, node: &NodeWithSource<'b>) -> Option<NodeWithSource<'b>> {
+        let sort = node.node.kind_id();
+        if !self.holds_unfielded_child(sort) {
+            return None;
+        }
+        let fields = &self.node_types()[sort as usize];
+        node.named_children().find(|child| {
+            !self.is_comment_node(child)
+                && !fields.iter().any(|field| {
+                    node.named_children_by_field_id(field.id())
+                        .any(|fielded| fielded == *child)
+                })
+        })
+    }
+
+    /// The operator of `node` if it applies an associative operator to two
+    /// operands, such as `&&` in `a && b`.
+    ///
+    /// Snippets containing `$...` or a named spread among the operands of
+    /// such an operator match a chain of it as a flat list of operands, so
+    /// `$first && $...rest` binds every operand of `a && b && c` rather than
+    /// seeing `a && b` as the left operand.
+    fn associative_operator<'b>(&self, _node: &NodeWithSource<'b>) -> Option<&'b str> {
+        None
+    }
+
+    /// Whether an element of sort `sort` inside a list node of sort `list_sort`
+    /// is a keyword argument, which is addressed by name rather than position.
+    ///
+    /// Keyword arguments in snippets match arguments in any order, as long as
+    /// they all follow the positional arguments.
+    fn is_keyword_argument(&self, _list_sort: SortId, _sort: SortId) -> bool {
+        false
+    }
+
+    /// The field through which a node of the given sort continues a chain of
+    /// member accesses and calls, such as the object of `a.b` or the function
+    /// of `a.b()`.
+    ///
+    /// A `$_` member in the middle of a chain in a snippet, as in
+    /// `$obj.foo().$_.bar()`, matches any number of links of such a chain.
+    fn member_chain_field(&self, _sort: SortId) -> Option<FieldId> {
+        None
+    }
+
+    /// Whether the field of a node of the given sort holds an optional
+    /// inheritance clause, such as the `extends` or `implements` clause of a
+    /// class.
+    ///
+    /// A clause in a snippet which only binds metavariables, like
+    /// `implements $iface`, also matches nodes without it, leaving the
+    /// metavariables unbound, so `class $c extends $base implements $iface {}`
+    /// matches classes with either clause, both or neither.
+    fn is_inheritance_clause(&self, _sort: SortId, _field: FieldId) -> bool {
+        false
+    }
+
+    /// The keywords a variable declaration can start with, such as `let` and
+    /// `const`.
+    ///
+    /// A snippet which starts with a metavariable and doesn't parse as is, like
+    /// `$kind $x = $v`, is tried with each keyword in place of the
+    /// metavariable, which is then bound to the keyword of the declaration.
+    fn declaration_keywords(&self) -> Option<&DeclarationKeywords> {
+        None
+    }
+
+    /// The modifiers of class members, such as `public` or `static`.
+    ///
+    /// A snippet which starts with a metavariable and doesn't parse as is, like
+    /// `$mod $name() {}`, binds the metavariable to the modifiers of the
+    /// member, and also matches members without modifiers, leaving the
+    /// metavariable unbound.
+    fn member_modifiers(&self) -> Option<&MemberModifiers> {
+        None
+    }
+
+    /// The sort of export statements, along with the field holding the
+    /// declaration they export.
+    ///
+    /// A snippet starting with `!export`, like `!export function $f() {}`, only
+    /// matches declarations which aren't exported.
+    fn export_declaration(&self) -> Option<(SortId, FieldId)> {
+        None
+    }
+
+    /// Keywords stored as an optional field of the node they modify, such as
+    /// `async` for JavaScript functions, along with that field.
+    ///
+    /// Snippets leaving the keyword out match either way. A snippet starting
+    /// with the negated keyword, like `!async function $f() {}`, only matches
+    /// nodes without it, while a leading metavariable which keeps the snippet
+    /// from parsing, like `$async function $f() {}`, binds the keyword if any.
+    fn flag_fields(&self) -> &[(&'static str, FieldId)] {
+        &[]
+    }
+
+    /// The parts of literals a snippet can bind with a metavariable even
+    /// though the grammar doesn't accept one there.
+    ///
+    /// Such metavariables are replaced by literal text while the snippet is
+    /// parsed, and bind that part of the literal when matching.
+    fn literal_parts(&self) -> &[LiteralPart] {
+        &[]
+    }
+
+    /// Renders the parts of a string concatenation as a template literal,
+    /// for the `template_literal()` built-in, or `None` if the language
+    /// doesn't have template literals.
+    ///
+    /// String literals become text of the template and other parts are
+    /// interpolated. Parts which are concatenations themselves are flattened.
+    fn template_literal(&self, _parts: &[NodeWithSource<'_>]) -> Option<String> {
+        None
+    }
+
+    /// Whether the node holds the arguments of a macro invocation as a flat
+    /// list of tokens, such as the token tree of `println!("{}", x)` in Rust.
+    ///
+    /// Snippets match such arguments loosely, split at top-level commas, so a
+    /// metavariable binds a whole argument rather than a single token.
+    fn is_macro_arguments(&self, _node: &NodeWithSource<'_>) -> bool {
+        false
+    }
+
+    /// Returns the node wrapped by a grouping or transparent node, if `node` is
+    /// one and wraps exactly one node (ignoring comments).
+    fn ungrouped_node<'b>(&self, node: &NodeWithSource<'b>) -> Option<NodeWithSource<'b>> {
+        let sort = node.node.kind_id();
+        if !self.is_grouping_sort(sort) && !self.transparent_sorts().contains(&sort) {
+            return None;
+        }
+        self.wrapped_node(node)
+    }
+
+    /// Returns the only named child of `node`, ignoring comments.
+    fn wrapped_node<'b>(&self, node: &NodeWithSource<'b>) -> Option<NodeWithSource<'b>> {
+        let mut children = node
+            .named_children()
+            .filter(|child| !self.is_comment_node(child));
+        let inner = children.next()?;
+        children.next().is_none().then_some(inner)
+    }
+
     fn metavariable_sort(&self) -> SortId;
 
     fn is_metavariable_node(&self, node: &NodeWithSource<'_>) -> bool {
@@ -1,5 +1,8 @@
 use crate::{
-    language::{fields_for_nodes, Field, MarzanoLanguage, NodeTypes, SortId, TSLanguage, Tree},
+    language::{
+        check_disregarded_field_map, fields_for_nodes, Field, FieldExpectation,
+        FieldExpectationCondition, MarzanoLanguage, NodeTypes, SortId, TSLanguage, Tree,
+    },
     notebooks::MarzanoNotebookParser,
 };
 use grit_util::{Ast, AstNode, CodeRange, Language, Parser, Replacement};
@@ -10,6 +13,7 @@ static NODE_TYPES_STRING: &str =
     include_str!("../../../resources/node-types/python-node-types.json");
 static NODE_TYPES: OnceLock<Vec<Vec<Field>>> = OnceLock::new();
 static LANGUAGE: OnceLock<TSLanguage> = OnceLock::new();
+static DISREGARDED_SNIPPET_FIELDS: OnceLock<Vec<FieldExpectation>> = OnceLock::new();
 
 #[cfg(not(feature = "builtin-parser"))]
 fn language() -> TSLanguage {
@@ -27,8 +31,14 @@ pub struct Python {
     node_types: &'static [Vec<Field>],
     metavariable_sort: SortId,
     comment_sort: SortId,
+    argument_list_sort: SortId,
+    keyword_argument_sort: SortId,
+    subscript_sort: SortId,
+    slice_sort: SortId,
     skip_padding_sorts: [SortId; 1],
+    transparent_sorts: [SortId; 1],
     language: &'static TSLanguage,
+    disregarded_snippet_fields: &'static Vec<FieldExpectation>,
 }
 
 impl Python {
@@ -37,13 +47,39 @@ impl Python {
         let node_types = NODE_TYPES.get_or_init(|| fields_for_nodes(language, NODE_TYPES_STRING));
         let metavariable_sort = language.id_for_node_kind("grit_metavariable", true);
         let comment_sort = language.id_for_node_kind("comment", true);
+        let argument_list_sort = language.id_for_node_kind("argument_list", true);
+        let keyword_argument_sort = language.id_for_node_kind("keyword_argument", true);
+        let subscript_sort = language.id_for_node_kind("subscript", true);
+        let slice_sort = language.id_for_node_kind("slice", true);
         let skip_padding_sorts = [language.id_for_node_kind("string", true)];
+        let transparent_sorts = [language.id_for_node_kind("parenthesized_expression", true)];
+        // so a `try` snippet matches statements with clauses it leaves out,
+        // including the `finally` of `except*` clauses, in its `finallly` field
+        let try_statement = language.id_for_node_kind("try_statement", true);
+        let disregarded_snippet_fields = DISREGARDED_SNIPPET_FIELDS.get_or_init(|| {
+            ["except", "else", "finally", "finallly"]
+                .into_iter()
+                .map(|field| {
+                    (
+                        try_statement,
+                        language.field_id_for_name(field).unwrap(),
+                        FieldExpectationCondition::OnlyIf(vec![""]),
+                    )
+                })
+                .collect()
+        });
         Self {
             node_types,
             metavariable_sort,
             comment_sort,
+            argument_list_sort,
+            keyword_argument_sort,
+            subscript_sort,
+            slice_sort,
             skip_padding_sorts,
+            transparent_sorts,
             language,
+            disregarded_snippet_fields,
         }
     }
     pub(crate) fn is_initialized() -> bool {
@@ -70,6 +106,7 @@ impl Language for Python {
             ("{ ", " }"),
             ("", "\ndef GRIT_FUNCTION():\n    return;"),
             ("GRIT_FN(", ")"),
+            ("def GRIT_FN(", "):\n    pass"),
         ]
     }
 
@@ -139,6 +176,20 @@ impl<'a> MarzanoLanguage<'a> for Python {
         self.language
     }
 
+    fn is_disregarded_snippet_field(
+        &self,
+        sort_id: SortId,
+        field_id: crate::language::FieldId,
+        field_node: &Option<NodeWithSource<'_>>,
+    ) -> bool {
+        check_disregarded_field_map(
+            self.disregarded_snippet_fields,
+            sort_id,
+            field_id,
+            field_node,
+        )
+    }
+
     fn is_comment_sort(&self, id: SortId) -> bool {
         id == self.comment_sort
     }
@@ -147,6 +198,18 @@ impl<'a> MarzanoLanguage<'a> for Python {
         self.metavariable_sort
     }
 
+    fn transparent_sorts(&self) -> &[SortId] {
+        &self.transparent_sorts
+    }
+
+    fn is_keyword_argument(&self, list_sort: SortId, sort: SortId) -> bool {
+        list_sort == self.argument_list_sort && sort == self.keyword_argument_sort
+    }
+
+    fn subscript_slice_sort(&self, id: SortId) -> Option<SortId> {
+        (id == self.subscript_sort).then_some(self.slice_sort)
+    }
+
     fn get_parser(&self) -> Box<dyn Parser<Tree = Tree>> {
         Box::new(MarzanoNotebookParser::new(self))
     }
@@ -26,6 +26,8 @@ pub struct Ruby {
     node_types: &'static [Vec<Field>],
     metavariable_sort: SortId,
     comment_sort: SortId,
+    argument_list_sort: SortId,
+    pair_sort: SortId,
     language: &'static TSLanguage,
 }
 
@@ -35,10 +37,14 @@ impl Ruby {
         let node_types = NODE_TYPES.get_or_init(|| fields_for_nodes(language, NODE_TYPES_STRING));
         let metavariable_sort = language.id_for_node_kind("grit_metavariable", true);
         let comment_sort = language.id_for_node_kind("comment", true);
+        let argument_list_sort = language.id_for_node_kind("argument_list", true);
+        let pair_sort = language.id_for_node_kind("pair", true);
         Self {
             node_types,
             metavariable_sort,
             comment_sort,
+            argument_list_sort,
+            pair_sort,
             language,
         }
     }
@@ -58,8 +64,10 @@ lazy_static! {
         .expect("Failed to compile EXACT_VARIABLE_REGEX");
     static ref VARIABLE_REGEX: Regex =
         Regex::new(r"\^(\.\.\.|[A-Za-z_][A-Za-z0-9_]*)").expect("Failed to compile VARIABLE_REGEX");
-    static ref BRACKET_VAR_REGEX: Regex =
-        Regex::new(r"\^\[([A-Za-z_][A-Za-z0-9_]*)\]").expect("Failed to compile BRACKET_VAR_REGEX");
+    static ref BRACKET_VAR_REGEX: Regex = Regex::new(
+        r"\^\[([A-Za-z_][A-Za-z0-9_]*)(\.index|\.quote|\.text|\.indent|\.kind|\.prev|\.next)?\]"
+    )
+    .expect("Failed to compile BRACKET_VAR_REGEX");
 }
 
 impl Language for Ruby {
@@ -123,4 +131,8 @@ impl<'a> MarzanoLanguage<'a> for Ruby {
     fn metavariable_sort(&self) -> SortId {
         self.metavariable_sort
     }
+
+    fn is_keyword_argument(&self, list_sort: SortId, sort: SortId) -> bool {
+        list_sort == self.argument_list_sort && sort == self.pair_sort
+    }
 }
@@ -2,9 +2,9 @@ use crate::language::{
     check_disregarded_field_map, fields_for_nodes, Field, FieldExpectation,
     FieldExpectationCondition, MarzanoLanguage, NodeTypes, SortId, TSLanguage,
 };
-use grit_util::Language;
+use grit_util::{blank_trailing_separators, AstNode, Language, TrailingSeparators};
 use marzano_util::node_with_source::NodeWithSource;
-use std::sync::OnceLock;
+use std::{borrow::Cow, sync::OnceLock};
 
 static NODE_TYPES_STRING: &str = include_str!("../../../resources/node-types/rust-node-types.json");
 
@@ -28,6 +28,11 @@ pub struct Rust {
     node_types: &'static [Vec<Field>],
     metavariable_sort: SortId,
     comment_sorts: [SortId; 2],
+    transparent_sorts: [SortId; 1],
+    token_tree_sort: SortId,
+    macro_invocation_sort: SortId,
+    field_initializer_list_sort: SortId,
+    type_parameters_sort: SortId,
     language: &'static TSLanguage,
     disregarded_snippet_fields: &'static Vec<FieldExpectation>,
 }
@@ -41,6 +46,11 @@ impl Rust {
             language.id_for_node_kind("line_comment", true),
             language.id_for_node_kind("block_comment", true),
         ];
+        let transparent_sorts = [language.id_for_node_kind("parenthesized_expression", true)];
+        let token_tree_sort = language.id_for_node_kind("token_tree", true);
+        let macro_invocation_sort = language.id_for_node_kind("macro_invocation", true);
+        let field_initializer_list_sort = language.id_for_node_kind("field_initializer_list", true);
+        let type_parameters_sort = language.id_for_node_kind("type_parameters", true);
         let disregarded_snippet_fields = DISREGARDED_SNIPPET_FIELDS.get_or_init(|| {
             vec![
                 (
@@ -89,6 +99,11 @@ impl Rust {
             node_types,
             metavariable_sort,
             comment_sorts,
+            transparent_sorts,
+            token_tree_sort,
+            macro_invocation_sort,
+            field_initializer_list_sort,
+            type_parameters_sort,
             language,
             disregarded_snippet_fields,
         }
@@ -128,6 +143,20 @@ impl Language for Rust {
     fn is_metavariable(&self, node: &NodeWithSource) -> bool {
         MarzanoLanguage::is_metavariable_node(self, node)
     }
+
+    // a trailing comma is optional in every list, but only some of the
+    // contexts snippets are parsed in accept it
+    fn normalize_snippet<'b>(&self, source: &'b str) -> Cow<'b, str> {
+        blank_trailing_separators(
+            source,
+            &TrailingSeparators {
+                separators: &[','],
+                quotes: &['"'],
+                char_literals: true,
+                tuples: true,
+            },
+        )
+    }
 }
 
 impl<'a> MarzanoLanguage<'a> for Rust {
@@ -156,6 +185,25 @@ impl<'a> MarzanoLanguage<'a> for Rust {
     fn metavariable_sort(&self) -> SortId {
         self.metavariable_sort
     }
+
+    fn transparent_sorts(&self) -> &[SortId] {
+        &self.transparent_sorts
+    }
+
+    fn is_keyed_collection_sort(&self, id: SortId) -> bool {
+        id == self.field_initializer_list_sort
+    }
+
+    fn is_type_parameters_sort(&self, id: SortId) -> bool {
+        id == self.type_parameters_sort
+    }
+
+    fn is_macro_arguments(&self, node: &NodeWithSource<'_>) -> bool {
+        node.node.kind_id() == self.token_tree_sort
+            && node
+                .parent()
+                .is_some_and(|parent| parent.node.kind_id() == self.macro_invocation_sort)
+    }
 }
 
 #[cfg(test)]
@@ -175,4 +223,29 @@ mod tests {
         println!("NODE: {}", nodes[0].node.to_sexp());
         assert!(!nodes.is_empty());
     }
+
+    #[test]
+    fn snippets_are_normalized_without_trailing_commas() {
+        let lang = Rust::new(None);
+        assert_eq!(lang.normalize_snippet("foo($a, $b,)"), "foo($a, $b )");
+        assert_eq!(
+            lang.normalize_snippet("Point { x: $x, y: $y, }"),
+            "Point { x: $x, y: $y  }"
+        );
+        // a single trailing comma makes a tuple
+        assert_eq!(lang.normalize_snippet("($a,)"), "($a,)");
+        // lifetimes don't open a literal, while character literals do
+        assert_eq!(
+            lang.normalize_snippet("fn f<'a>(x: &'a T, y: $y,) {}"),
+            "fn f<'a>(x: &'a T, y: $y ) {}"
+        );
+        assert_eq!(
+            lang.normalize_snippet("foo(',', '\\'', $c,)"),
+            "foo(',', '\\'', $c )"
+        );
+        assert!(matches!(
+            lang.normalize_snippet("foo($a)"),
+            Cow::Borrowed(_)
+        ));
+    }
 }
@@ -1,4 +1,7 @@
-use crate::language::{fields_for_nodes, Field, MarzanoLanguage, NodeTypes, SortId, TSLanguage};
+use crate::language::{
+    check_disregarded_field_map, fields_for_nodes, Field, FieldExpectation,
+    FieldExpectationCondition, FieldId, MarzanoLanguage, NodeTypes, SortId, TSLanguage,
+};
 use grit_util::Language;
 use marzano_util::node_with_source::NodeWithSource;
 use std::sync::OnceLock;
@@ -6,6 +9,7 @@ use std::sync::OnceLock;
 static NODE_TYPES_STRING: &str = include_str!("../../../resources/node-types/sql-node-types.json");
 static NODE_TYPES: OnceLock<Vec<Vec<Field>>> = OnceLock::new();
 static LANGUAGE: OnceLock<TSLanguage> = OnceLock::new();
+static DISREGARDED_SNIPPET_FIELDS: OnceLock<Vec<FieldExpectation>> = OnceLock::new();
 
 #[cfg(not(feature = "builtin-parser"))]
 fn language() -> TSLanguage {
@@ -24,6 +28,7 @@ pub struct Sql {
     metavariable_sort: SortId,
     comment_sort: SortId,
     language: &'static TSLanguage,
+    disregarded_snippet_fields: &'static Vec<FieldExpectation>,
 }
 
 impl Sql {
@@ -32,11 +37,22 @@ impl Sql {
         let node_types = NODE_TYPES.get_or_init(|| fields_for_nodes(language, NODE_TYPES_STRING));
         let metavariable_sort = language.id_for_node_kind("grit_metavariable", true);
         let comment_sort = language.id_for_node_kind("comment", true);
+        // so a `select ... from ...` snippet matches statements with clauses
+        // it leaves out, such as `where` and `limit`
+        let from = language.id_for_node_kind("from", true);
+        let disregarded_snippet_fields = DISREGARDED_SNIPPET_FIELDS.get_or_init(|| {
+            ["where", "order", "limit"]
+                .into_iter()
+                .filter_map(|field| language.field_id_for_name(field))
+                .map(|field_id| (from, field_id, FieldExpectationCondition::OnlyIf(vec![""])))
+                .collect()
+        });
         Self {
             node_types,
             metavariable_sort,
             comment_sort,
             language,
+            disregarded_snippet_fields,
         }
     }
     pub(crate) fn is_initialized() -> bool {
@@ -91,6 +107,20 @@ impl<'a> MarzanoLanguage<'a> for Sql {
         self.language
     }
 
+    fn is_disregarded_snippet_field(
+        &self,
+        sort_id: SortId,
+        field_id: FieldId,
+        field_node: &Option<NodeWithSource<'_>>,
+    ) -> bool {
+        check_disregarded_field_map(
+            self.disregarded_snippet_fields,
+            sort_id,
+            field_id,
+            field_node,
+        )
+    }
+
     fn is_comment_sort(&self, id: SortId) -> bool {
         id == self.comment_sort
     }
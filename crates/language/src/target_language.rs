@@ -9,7 +9,8 @@ use crate::{
     javascript::JavaScript,
     json::Json,
     language::{
-        Field, FieldId, LeafEquivalenceClass, MarzanoLanguage, NodeTypes, SortId, TSLanguage, Tree,
+        DeclarationKeywords, Field, FieldId, GrammarVersion, LeafEquivalenceClass, LiteralPart,
+        MarzanoLanguage, MemberModifiers, NodeTypes, SortId, TSLanguage, Tree,
     },
     markdown_block::MarkdownBlock,
     markdown_inline::MarkdownInline,
@@ -145,9 +146,12 @@ impl PatternLanguage {
         Self::get_language_with_parser(&mut parser, src)
     }
 
+    /// Resolves a language name, such as the one in a `language` declaration.
+    /// Names are matched regardless of case, so `JS` and `Python` resolve to
+    /// `js` and `python`; flavors are matched exactly.
     pub fn from_string(name: &str, flavor: Option<&str>) -> Option<Self> {
-        match name {
-            "js" => match flavor {
+        match name.to_ascii_lowercase().as_str() {
+            "js" | "javascript" => match flavor {
                 Some("jsx") => Some(Self::Tsx),
                 Some("flow") => Some(Self::Tsx),
                 Some("flowComments") => Some(Self::Tsx),
@@ -186,6 +190,17 @@ impl PatternLanguage {
         }
     }
 
+    /// The flavors accepted by [`Self::from_string`] for the given language
+    /// name, each of which selects a different grammar or dialect.
+    pub fn flavors(name: &str) -> &'static [&'static str] {
+        match name.to_ascii_lowercase().as_str() {
+            "js" | "javascript" => &["jsx", "flow", "flowComments", "typescript", "js_do_not_use"],
+            "markdown" => &["block", "inline"],
+            "php" => &["html", "only"],
+            _ => &[],
+        }
+    }
+
     fn get_file_extensions(&self) -> &'static [&'static str] {
         match self {
             PatternLanguage::JavaScript => &["js", "jsx", "cjs", "mjs", "vue"],
@@ -521,6 +536,24 @@ macro_rules! generate_target_language {
                     $(Self::$language(lang) => Language::make_single_line_comment(lang, text)),+
                 }
             }
+
+            fn normalize_snippet<'b>(&self, source: &'b str) -> std::borrow::Cow<'b, str> {
+                match self {
+                    $(Self::$language(lang) => Language::normalize_snippet(lang, source)),+
+                }
+            }
+
+            fn doc_comment_delimiters(&self) -> Option<(&'static str, &'static str)> {
+                match self {
+                    $(Self::$language(lang) => Language::doc_comment_delimiters(lang)),+
+                }
+            }
+
+            fn post_process_rewrite(&self, text: &str) -> String {
+                match self {
+                    $(Self::$language(lang) => Language::post_process_rewrite(lang, text)),+
+                }
+            }
         }
 
         impl NodeTypes for TargetLanguage {
@@ -538,6 +571,12 @@ macro_rules! generate_target_language {
                 }
             }
 
+            fn grammar_version(&self) -> GrammarVersion {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::grammar_version(lang)),+
+                }
+            }
+
             fn get_parser(&self) -> Box<dyn Parser<Tree = Tree>> {
                 match self {
                     $(Self::$language(lang) => MarzanoLanguage::get_parser(lang)),+
@@ -556,12 +595,138 @@ macro_rules! generate_target_language {
                 }
             }
 
+            fn is_required_snippet_field(&self, sort_id: SortId, field_id: FieldId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_required_snippet_field(lang, sort_id, field_id)),+
+                }
+            }
+
             fn is_comment_sort(&self, id: SortId) -> bool {
                 match self {
                     $(Self::$language(lang) => MarzanoLanguage::is_comment_sort(lang, id)),+
                 }
             }
 
+            fn is_grouping_sort(&self, id: SortId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_grouping_sort(lang, id)),+
+                }
+            }
+
+            fn transparent_sorts(&self) -> &[SortId] {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::transparent_sorts(lang)),+
+                }
+            }
+
+            fn is_keyed_collection_sort(&self, id: SortId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_keyed_collection_sort(lang, id)),+
+                }
+            }
+
+            fn is_union_type_sort(&self, id: SortId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_union_type_sort(lang, id)),+
+                }
+            }
+
+            fn is_type_parameters_sort(&self, id: SortId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_type_parameters_sort(lang, id)),+
+                }
+            }
+
+            fn is_switch_body_sort(&self, id: SortId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_switch_body_sort(lang, id)),+
+                }
+            }
+
+            fn enum_member_assignment(&self, id: SortId) -> Option<(SortId, FieldId)> {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::enum_member_assignment(lang, id)),+
+                }
+            }
+
+            fn holds_unfielded_child(&self, id: SortId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::holds_unfielded_child(lang, id)),+
+                }
+            }
+
+            fn subscript_slice_sort(&self, id: SortId) -> Option<SortId> {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::subscript_slice_sort(lang, id)),+
+                }
+            }
+
+            fn associative_operator<'b>(&self, node: &NodeWithSource<'b>) -> Option<&'b str> {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::associative_operator(lang, node)),+
+                }
+            }
+
+            fn is_keyword_argument(&self, list_sort: SortId, sort: SortId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_keyword_argument(lang, list_sort, sort)),+
+                }
+            }
+
+            fn member_chain_field(&self, sort: SortId) -> Option<FieldId> {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::member_chain_field(lang, sort)),+
+                }
+
+            fn is_inheritance_clause(&self, sort: SortId, field: FieldId) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_inheritance_clause(lang, sort, field)),+
+                }
+            }
+            }
+
+            fn declaration_keywords(&self) -> Option<&DeclarationKeywords> {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::declaration_keywords(lang)),+
+                }
+            }
+
+            fn export_declaration(&self) -> Option<(SortId, FieldId)> {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::export_declaration(lang)),+
+                }
+            }
+
+            fn member_modifiers(&self) -> Option<&MemberModifiers> {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::member_modifiers(lang)),+
+                }
+            }
+
+            fn flag_fields(&self) -> &[(&'static str, FieldId)] {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::flag_fields(lang)),+
+                }
+            }
+
+            fn literal_parts(&self) -> &[LiteralPart] {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::literal_parts(lang)),+
+                }
+            }
+
+            fn template_literal(&self, parts: &[NodeWithSource<'_>]) -> Option<String> {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::template_literal(lang, parts)),+
+                }
+            }
+
+            fn is_macro_arguments(&self, node: &NodeWithSource<'_>) -> bool {
+                match self {
+                    $(Self::$language(lang) => MarzanoLanguage::is_macro_arguments(lang, node)),+
+                }
+            }
+
             fn is_comment_node(&self, node: &NodeWithSource<'_>) -> bool {
                 match self {
                     $(Self::$language(lang) => MarzanoLanguage::is_comment_node(lang, node)),+
@@ -750,6 +915,28 @@ impl Default for TargetLanguage {
 mod tests {
     use super::*;
 
+    #[test]
+    fn language_names_are_case_insensitive() {
+        assert_eq!(
+            PatternLanguage::from_string("JS", None),
+            Some(PatternLanguage::Tsx)
+        );
+        assert_eq!(
+            PatternLanguage::from_string("JavaScript", None),
+            Some(PatternLanguage::Tsx)
+        );
+        assert_eq!(
+            PatternLanguage::from_string("PYTHON", None),
+            Some(PatternLanguage::Python)
+        );
+        assert_eq!(
+            PatternLanguage::from_string("JS", Some("typescript")),
+            Some(PatternLanguage::TypeScript)
+        );
+        assert_eq!(PatternLanguage::flavors("Markdown"), ["block", "inline"]);
+        assert_eq!(PatternLanguage::from_string("Pythonic", None), None);
+    }
+
     #[test]
     fn extract_javascript_comment() {
         let text = "// this is a comment\nconsole.log('hello')";
@@ -1,22 +1,32 @@
 use crate::{
     js_like::{
-        js_like_disregarded_field_values, js_like_get_statement_sorts, js_like_is_comment,
-        js_like_is_metavariable, jslike_check_replacements, MarzanoJsLikeParser,
+        js_like_associative_operator, js_like_disregarded_field_values,
+        js_like_get_declaration_keywords, js_like_get_enum_body, js_like_get_export_declaration,
+        js_like_get_flag_fields, js_like_get_inheritance_clauses, js_like_get_member_chain_fields,
+        js_like_get_required_snippet_fields, js_like_get_statement_sorts, js_like_is_comment,
+        js_like_is_metavariable, js_like_post_process_rewrite, js_like_template_literal,
+        jslike_check_replacements, MarzanoJsLikeParser, JS_LIKE_LITERAL_PARTS,
+        JS_LIKE_TRAILING_SEPARATORS,
     },
     language::{
-        check_disregarded_field_map, fields_for_nodes, kind_and_field_id_for_field_map, Field,
-        FieldExpectation, MarzanoLanguage, NodeTypes, SortId, TSLanguage, Tree,
+        check_disregarded_field_map, fields_for_nodes, kind_and_field_id_for_field_map,
+        DeclarationKeywords, Field, FieldExpectation, FieldId, LiteralPart, MarzanoLanguage,
+        MemberModifiers, NodeTypes, SortId, TSLanguage, Tree,
     },
 };
-use grit_util::{AstNode, ByteRange, Language, Parser, Replacement};
+use grit_util::{blank_trailing_separators, AstNode, ByteRange, Language, Parser, Replacement};
 use marzano_util::node_with_source::NodeWithSource;
-use std::sync::OnceLock;
+use std::{borrow::Cow, sync::OnceLock};
 
 static NODE_TYPES_STRING: &str = include_str!("../../../resources/node-types/tsx-node-types.json");
 static NODE_TYPES: OnceLock<Vec<Vec<Field>>> = OnceLock::new();
 static LANGUAGE: OnceLock<TSLanguage> = OnceLock::new();
 static STATEMENT_SORTS: OnceLock<Vec<SortId>> = OnceLock::new();
 static DISREGARDED_SNIPPET_FIELDS: OnceLock<Vec<FieldExpectation>> = OnceLock::new();
+static MEMBER_CHAIN_FIELDS: OnceLock<Vec<(SortId, FieldId)>> = OnceLock::new();
+static INHERITANCE_CLAUSES: OnceLock<Vec<(SortId, FieldId)>> = OnceLock::new();
+static DECLARATION_KEYWORDS: OnceLock<DeclarationKeywords> = OnceLock::new();
+static REQUIRED_SNIPPET_FIELDS: OnceLock<Vec<(SortId, FieldId)>> = OnceLock::new();
 
 #[cfg(not(feature = "builtin-parser"))]
 fn language() -> TSLanguage {
@@ -34,10 +44,24 @@ pub struct Tsx {
     node_types: &'static [Vec<Field>],
     metavariable_sort: SortId,
     comment_sort: SortId,
+    grouping_sort: SortId,
+    object_sort: SortId,
+    binary_expression_sort: SortId,
+    union_type_sort: SortId,
+    type_parameters_sort: SortId,
+    switch_body_sort: SortId,
+    enum_body: (SortId, (SortId, FieldId)),
     jsx_sort: SortId,
     statement_sorts: &'static [SortId],
     language: &'static TSLanguage,
     disregarded_snippet_fields: &'static Vec<FieldExpectation>,
+    member_chain_fields: &'static [(SortId, FieldId)],
+    inheritance_clauses: &'static [(SortId, FieldId)],
+    declaration_keywords: &'static DeclarationKeywords,
+    export_declaration: (SortId, FieldId),
+    flag_fields: [(&'static str, FieldId); 1],
+    member_modifiers: MemberModifiers,
+    required_snippet_fields: &'static [(SortId, FieldId)],
 }
 
 impl Tsx {
@@ -46,22 +70,63 @@ impl Tsx {
         let node_types = NODE_TYPES.get_or_init(|| fields_for_nodes(language, NODE_TYPES_STRING));
         let metavariable_sort = language.id_for_node_kind("grit_metavariable", true);
         let comment_sort = language.id_for_node_kind("comment", true);
+        let grouping_sort = language.id_for_node_kind("parenthesized_expression", true);
+        let object_sort = language.id_for_node_kind("object", true);
+        let binary_expression_sort = language.id_for_node_kind("binary_expression", true);
+        let union_type_sort = language.id_for_node_kind("union_type", true);
+        let type_parameters_sort = language.id_for_node_kind("type_parameters", true);
+        let switch_body_sort = language.id_for_node_kind("switch_body", true);
+        let enum_body = js_like_get_enum_body(language);
         let jsx_sort = language.id_for_node_kind("jsx_expression", true);
 
         let disregarded_snippet_fields = DISREGARDED_SNIPPET_FIELDS.get_or_init(|| {
             kind_and_field_id_for_field_map(language, js_like_disregarded_field_values())
         });
 
+        let required_snippet_fields =
+            REQUIRED_SNIPPET_FIELDS.get_or_init(|| js_like_get_required_snippet_fields(language));
+
         let statement_sorts = STATEMENT_SORTS.get_or_init(|| js_like_get_statement_sorts(language));
 
+        let member_chain_fields =
+            MEMBER_CHAIN_FIELDS.get_or_init(|| js_like_get_member_chain_fields(language));
+
+        let inheritance_clauses =
+            INHERITANCE_CLAUSES.get_or_init(|| js_like_get_inheritance_clauses(language));
+
+        let declaration_keywords =
+            DECLARATION_KEYWORDS.get_or_init(|| js_like_get_declaration_keywords(language));
+
+        let export_declaration = js_like_get_export_declaration(language);
+        let flag_fields = js_like_get_flag_fields(language);
+
+        let member_modifiers = MemberModifiers {
+            placeholder: "public",
+            sort: language.id_for_node_kind("accessibility_modifier", true),
+        };
+
         Self {
             node_types,
             metavariable_sort,
             comment_sort,
+            grouping_sort,
+            object_sort,
+            binary_expression_sort,
+            union_type_sort,
+            type_parameters_sort,
+            switch_body_sort,
+            enum_body,
             jsx_sort,
             statement_sorts,
             language,
             disregarded_snippet_fields,
+            member_chain_fields,
+            inheritance_clauses,
+            declaration_keywords,
+            export_declaration,
+            flag_fields,
+            member_modifiers,
+            required_snippet_fields,
         }
     }
     pub(crate) fn is_initialized() -> bool {
@@ -117,6 +182,18 @@ impl Language for Tsx {
         )
     }
 
+    fn normalize_snippet<'b>(&self, source: &'b str) -> Cow<'b, str> {
+        blank_trailing_separators(source, &JS_LIKE_TRAILING_SEPARATORS)
+    }
+
+    fn doc_comment_delimiters(&self) -> Option<(&'static str, &'static str)> {
+        Some(("/**", "*/"))
+    }
+
+    fn post_process_rewrite(&self, text: &str) -> String {
+        js_like_post_process_rewrite(text)
+    }
+
     fn is_statement(&self, node: &NodeWithSource) -> bool {
         self.statement_sorts.contains(&node.node.kind_id())
     }
@@ -163,10 +240,78 @@ impl<'a> MarzanoLanguage<'a> for Tsx {
         )
     }
 
+    fn is_required_snippet_field(&self, sort_id: SortId, field_id: FieldId) -> bool {
+        self.required_snippet_fields.contains(&(sort_id, field_id))
+    }
+
     fn is_comment_sort(&self, id: SortId) -> bool {
         id == self.comment_sort
     }
 
+    fn is_grouping_sort(&self, id: SortId) -> bool {
+        id == self.grouping_sort
+    }
+
+    fn is_keyed_collection_sort(&self, id: SortId) -> bool {
+        id == self.object_sort
+    }
+
+    fn is_switch_body_sort(&self, id: SortId) -> bool {
+        id == self.switch_body_sort
+    }
+
+    fn enum_member_assignment(&self, id: SortId) -> Option<(SortId, FieldId)> {
+        let (enum_body_sort, assignment) = self.enum_body;
+        (id == enum_body_sort).then_some(assignment)
+    }
+
+    fn associative_operator<'b>(&self, node: &NodeWithSource<'b>) -> Option<&'b str> {
+        js_like_associative_operator(node, self.binary_expression_sort)
+    }
+
+    fn is_union_type_sort(&self, id: SortId) -> bool {
+        id == self.union_type_sort
+    }
+
+    fn is_type_parameters_sort(&self, id: SortId) -> bool {
+        id == self.type_parameters_sort
+    }
+
+    fn member_chain_field(&self, sort: SortId) -> Option<FieldId> {
+        self.member_chain_fields
+            .iter()
+            .find(|(chain_sort, _)| *chain_sort == sort)
+            .map(|(_, field)| *field)
+    }
+
+    fn is_inheritance_clause(&self, sort: SortId, field: FieldId) -> bool {
+        self.inheritance_clauses.contains(&(sort, field))
+    }
+
+    fn declaration_keywords(&self) -> Option<&DeclarationKeywords> {
+        Some(self.declaration_keywords)
+    }
+
+    fn export_declaration(&self) -> Option<(SortId, FieldId)> {
+        Some(self.export_declaration)
+    }
+
+    fn flag_fields(&self) -> &[(&'static str, FieldId)] {
+        &self.flag_fields
+    }
+
+    fn literal_parts(&self) -> &[LiteralPart] {
+        &JS_LIKE_LITERAL_PARTS
+    }
+
+    fn template_literal(&self, parts: &[NodeWithSource<'_>]) -> Option<String> {
+        js_like_template_literal(parts, self.binary_expression_sort)
+    }
+
+    fn member_modifiers(&self) -> Option<&MemberModifiers> {
+        Some(&self.member_modifiers)
+    }
+
     fn is_comment_node(&self, node: &NodeWithSource) -> bool {
         self.is_comment_sort(node.node.kind_id())
             || js_like_is_comment(node, self.comment_sort, self.jsx_sort)
@@ -81,6 +81,7 @@ pub fn check_file(
         let CompilationResult {
             problem,
             compilation_warnings,
+            ..
         } = src_to_problem_libs(
             pattern.body.to_string(),
             pattern_libs,
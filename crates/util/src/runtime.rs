@@ -1,6 +1,12 @@
 use anyhow::Result;
 use http::HeaderMap;
-use std::env;
+use std::{
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 #[cfg(feature = "network_requests")]
 use tokio::runtime::Handle;
 
@@ -21,6 +27,14 @@ pub struct ExecutionContext {
     reqwest: reqwest::Client,
     /// Ignore limit patterns - this is important for scans
     pub ignore_limit_pattern: bool,
+    /// Report match ranges in UTF-16 code units rather than bytes, as editors
+    /// such as VS Code expect
+    pub utf16_ranges: bool,
+    /// Stops matching early once cancelled
+    pub cancellation: Option<CancellationToken>,
+    /// Fail a file's rewrite, rather than emitting it, if the rewritten code
+    /// doesn't parse while the original did
+    pub validate_rhs: bool,
 }
 
 #[cfg(all(
@@ -54,6 +68,9 @@ pub struct ExecutionContext {
     fetch: FetchFn,
     pub exec_external: ExecExternalFn,
     pub ignore_limit_pattern: bool,
+    pub utf16_ranges: bool,
+    pub cancellation: Option<CancellationToken>,
+    pub validate_rhs: bool,
 }
 
 #[cfg(not(feature = "network_requests_common"))]
@@ -61,6 +78,9 @@ pub struct ExecutionContext {
 pub struct ExecutionContext {
     llm_api: Option<LanguageModelAPI>,
     pub ignore_limit_pattern: bool,
+    pub utf16_ranges: bool,
+    pub cancellation: Option<CancellationToken>,
+    pub validate_rhs: bool,
 }
 
 impl ExecutionContext {
@@ -107,6 +127,9 @@ impl ExecutionContext {
             fetch,
             exec_external,
             ignore_limit_pattern: false,
+            utf16_ranges: false,
+            cancellation: None,
+            validate_rhs: false,
         }
     }
 
@@ -115,6 +138,23 @@ impl ExecutionContext {
         self
     }
 
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    pub fn with_rhs_validation(mut self) -> Self {
+        self.validate_rhs = true;
+        self
+    }
+
+    /// Whether matching should stop, checked periodically while matching
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
     #[cfg(feature = "network_requests")]
     pub fn send_request(
         &self,
@@ -191,6 +231,9 @@ impl Default for ExecutionContext {
             handle: Handle::try_current().ok(),
             reqwest: reqwest::Client::new(),
             ignore_limit_pattern: false,
+            utf16_ranges: false,
+            cancellation: None,
+            validate_rhs: false,
         }
     }
 
@@ -208,6 +251,9 @@ impl Default for ExecutionContext {
                 Err(anyhow::anyhow!("External functions are disabled"))
             },
             ignore_limit_pattern: false,
+            utf16_ranges: false,
+            cancellation: None,
+            validate_rhs: false,
         }
     }
 
@@ -216,10 +262,36 @@ impl Default for ExecutionContext {
         Self {
             llm_api: None,
             ignore_limit_pattern: false,
+            utf16_ranges: false,
+            cancellation: None,
+            validate_rhs: false,
         }
     }
 }
 
+/**
+ * A flag shared between the caller and a long-running match or compilation,
+ * which can be set from another thread to make it return early.
+ *
+ * Clones share the same flag.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LanguageModelAPI {
     pub base_endpoint: String,